@@ -9,14 +9,22 @@ use crate::{
         parser::{MappingStart, Scalar, ScalarStyle, SequenceStart},
         tag::Tag,
     },
-    loader::{Document, Loader},
+    loader::{Document, DocumentState, Loader, Step},
+    mapping::EntryKey,
+    modules::base64,
     modules::error::{self, Error, ErrorImpl},
     modules::path::Path,
+    ser::YamlVersion,
+    value::{styled, Value},
 };
 use serde::de::{
-    self, value::StrDeserializer, Deserialize, DeserializeOwned,
-    DeserializeSeed, Expected, IgnoredAny, Unexpected, Visitor,
+    self,
+    value::{SeqDeserializer, StrDeserializer},
+    Deserialize, DeserializeOwned, DeserializeSeed, Expected,
+    IgnoredAny, Unexpected, Visitor,
 };
+use std::borrow::Cow;
+use std::collections::{hash_map, BTreeMap, HashMap};
 use std::fmt::Debug;
 use std::fmt::Formatter;
 use std::fmt::Result as FmtResult;
@@ -65,6 +73,435 @@ type Result<T, E = Error> = std::result::Result<T, E>;
 #[derive(Debug)]
 pub struct Deserializer<'de> {
     progress: Progress<'de>,
+    config: DeserializerConfig,
+}
+
+/// A builder for a [`Deserializer`] with custom input settings.
+///
+/// The same builder can be used to create any number of deserializers, or
+/// passed to [`from_str_with`], [`from_slice_with`] and
+/// [`from_reader_with`].
+///
+/// # Example
+///
+/// ```
+/// use anyhow::Result;
+/// use serde_yml::{DeserializerBuilder, Value};
+///
+/// fn main() -> Result<()> {
+///     let builder = DeserializerBuilder::new().preserve_scalars(true);
+///     let value: Value =
+///         serde_yml::from_str_with("port: 0x1F90\n", &builder)?;
+///
+///     assert_eq!(value["port"], 8080);
+///     assert_eq!(serde_yml::to_string(&value)?, "port: 0x1F90\n");
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct DeserializerBuilder {
+    config: DeserializerConfig,
+}
+
+/// Deserializer settings.
+#[derive(Clone, Debug)]
+struct DeserializerConfig {
+    preserve_scalars: bool,
+    /// How deeply sequences and mappings may be nested.
+    max_depth: usize,
+    /// How many times aliases may be followed, or `None` for 100 times
+    /// the number of events in the document.
+    max_alias_expansions: Option<usize>,
+    reject_unknown_tags: bool,
+    duplicate_keys: DuplicateKeys,
+    /// The version whose rules resolve scalars, which is replaced by the
+    /// version in the `%YAML` directive of a document that has one.
+    yaml_version: YamlVersion,
+    schema: Schema,
+    limits: Limits,
+}
+
+impl Default for DeserializerConfig {
+    fn default() -> Self {
+        DeserializerConfig {
+            preserve_scalars: false,
+            max_depth: 128,
+            max_alias_expansions: None,
+            reject_unknown_tags: false,
+            duplicate_keys: DuplicateKeys::default(),
+            yaml_version: YamlVersion::V1_2,
+            schema: Schema::default(),
+            limits: Limits::default(),
+        }
+    }
+}
+
+/// What happens to a key that appears more than once in the same
+/// mapping.
+///
+/// Keys are compared as [`Value`](crate::Value)s, so `~` and `null`, or
+/// `1` and `0x1`, are the same key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DuplicateKeys {
+    /// Leave it to the type being deserialized: a
+    /// [`Value`](crate::Value), a [`Mapping`](crate::Mapping) or a struct
+    /// rejects the mapping, while a `HashMap` or a `BTreeMap` keeps the
+    /// last value.
+    Target,
+    /// Fail with an error that points at both occurrences of the key.
+    Error,
+    /// Keep the value of the first occurrence of the key.
+    FirstWins,
+    /// Keep the value of the last occurrence of the key, in the position
+    /// of the first one.
+    LastWins,
+    /// Keep the values of every occurrence of the key. Every mapping is
+    /// read as a multimap: each key appears once and its value is a
+    /// sequence of all the values given for it, in document order, so a
+    /// [`Value`](crate::Value) holds a sequence under every key and a
+    /// struct needs a `Vec` for each field.
+    CollectAll,
+}
+
+impl Default for DuplicateKeys {
+    fn default() -> Self {
+        DuplicateKeys::Target
+    }
+}
+
+/// The schema by which plain scalars without a tag are given a type.
+///
+/// The schema decides what [`Value`](crate::Value) or an untagged enum
+/// receives. A field that asks for a specific type still reads the
+/// scalar as that type, following the rules of the JSON schema under
+/// [`Schema::Json`] and those of the core schema otherwise.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Schema {
+    /// Every plain scalar is a string, so `no` stays `"no"`.
+    Failsafe,
+    /// Only `true`, `false`, `null` and numbers in JSON syntax are
+    /// resolved; `True`, `~`, `0x1F`, `.inf` and empty scalars are
+    /// strings.
+    Json,
+    /// The core schema of YAML 1.2, or the types of YAML 1.1 in a
+    /// document read as YAML 1.1.
+    Core,
+}
+
+impl Default for Schema {
+    fn default() -> Self {
+        Schema::Core
+    }
+}
+
+/// The rules by which plain scalars without a tag, and scalars with a
+/// core tag, are resolved.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Resolution {
+    /// The core schema of YAML 1.2.
+    Core,
+    /// The types of YAML 1.1, as PyYAML resolves them: `yes`, `no`, `on`
+    /// and `off` are booleans, `0777` is octal, `1_000` and `1:30` are
+    /// integers, and floats need a `.`.
+    Yaml11,
+    /// The failsafe schema: plain scalars are strings.
+    Failsafe,
+    /// The JSON schema.
+    Json,
+}
+
+/// Limits that the [`Loader`] checks while it parses the input, so that
+/// it stops as soon as one is exceeded.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct Limits {
+    pub(crate) input_size: Option<usize>,
+    pub(crate) scalar_length: Option<usize>,
+    pub(crate) events: Option<usize>,
+    pub(crate) aliases: Option<usize>,
+}
+
+impl DeserializerBuilder {
+    /// Creates a builder with the default settings, which accept any
+    /// input apart from a nesting depth of more than 128 and aliases
+    /// that are followed more than 100 times per event in the document.
+    pub fn new() -> Self {
+        DeserializerBuilder::default()
+    }
+
+    /// Creates a builder for untrusted input, which rejects aliases and
+    /// tags other than the YAML core tags and enum variants, and sets
+    /// these limits:
+    ///
+    /// | Setting | Limit |
+    /// |---|---|
+    /// | [`max_input_size`](Self::max_input_size) | 4 MiB |
+    /// | [`max_scalar_length`](Self::max_scalar_length) | 1 MiB |
+    /// | [`max_events`](Self::max_events) | 1,000,000 |
+    /// | [`max_depth`](Self::max_depth) | 64 |
+    /// | [`max_aliases`](Self::max_aliases) | 0 |
+    ///
+    /// Each limit can still be changed on the returned builder.
+    ///
+    /// ```
+    /// use serde_yml::{DeserializerBuilder, Value};
+    ///
+    /// let builder = DeserializerBuilder::safe();
+    /// let yaml = "base: &base {a: 1}\ncopy: *base\n";
+    /// let err = serde_yml::from_str_with::<Value>(yaml, &builder)
+    ///     .unwrap_err();
+    /// assert_eq!(
+    ///     err.to_string(),
+    ///     "number of aliases exceeds the limit of 0 at line 2 column 7",
+    /// );
+    /// ```
+    pub fn safe() -> Self {
+        DeserializerBuilder::new()
+            .max_input_size(4 * 1024 * 1024)
+            .max_scalar_length(1024 * 1024)
+            .max_events(1_000_000)
+            .max_depth(64)
+            .max_aliases(0)
+            .reject_unknown_tags(true)
+    }
+
+    /// Chooses whether scalars loaded into a [`Value`](crate::Value)
+    /// keep the style and the text they were written with, as
+    /// [`Value::Styled`](crate::Value::Styled). Disabled by default.
+    ///
+    /// The serializer writes such a scalar back as it was read as long as
+    /// its value is unchanged, so that loading and saving a file does not
+    /// turn `0x1F` into `31`, `1.50` into `1.5` or `"quoted"` into
+    /// `quoted`. Only scalars without a tag, in documents resolved with
+    /// the core schema of YAML 1.2, are preserved.
+    ///
+    /// The text is kept without the escapes and line breaks of its style,
+    /// which the emitter writes afresh: `"\u00e9"` comes back as `"é"`,
+    /// and the lines of a folded scalar are folded again.
+    ///
+    /// This mode is meant for deserializing `Value`s: scalars are handed
+    /// to other types that call `deserialize_any`, such as untagged
+    /// enums, as newtype structs they usually don't expect.
+    ///
+    /// ```
+    /// use serde::Deserialize;
+    /// use serde_yml::{DeserializerBuilder, Value};
+    ///
+    /// let yaml = "ratio: 1.50\nname: \"web\"\n";
+    /// let builder = DeserializerBuilder::new().preserve_scalars(true);
+    /// let mut value = Value::deserialize(builder.from_str(yaml)).unwrap();
+    /// assert_eq!(serde_yml::to_string(&value).unwrap(), yaml);
+    ///
+    /// value["ratio"] = Value::from(2.5);
+    /// assert_eq!(
+    ///     serde_yml::to_string(&value).unwrap(),
+    ///     "ratio: 2.5\nname: \"web\"\n",
+    /// );
+    /// ```
+    pub fn preserve_scalars(mut self, preserve: bool) -> Self {
+        self.config.preserve_scalars = preserve;
+        self
+    }
+
+    /// Sets how deeply sequences and mappings may be nested. Deeper input
+    /// fails with a recursion limit error. The default is 128.
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.config.max_depth = depth;
+        self
+    }
+
+    /// Sets how many times aliases may be followed while deserializing a
+    /// document, which bounds the work that aliases to aliases can cause.
+    /// Exceeding it fails with a repetition limit error. By default the
+    /// limit is 100 times the number of events in the document, or in
+    /// the part of it parsed so far when it is deserialized as it is read
+    /// from an IO stream.
+    pub fn max_alias_expansions(mut self, expansions: usize) -> Self {
+        self.config.max_alias_expansions = Some(expansions);
+        self
+    }
+
+    /// Sets the largest input, in bytes, that is accepted. Input read
+    /// from an IO stream stops being read once it passes the limit.
+    pub fn max_input_size(mut self, bytes: usize) -> Self {
+        self.config.limits.input_size = Some(bytes);
+        self
+    }
+
+    /// Sets the longest scalar, in bytes after unescaping, that is
+    /// accepted.
+    pub fn max_scalar_length(mut self, bytes: usize) -> Self {
+        self.config.limits.scalar_length = Some(bytes);
+        self
+    }
+
+    /// Sets how many parser events a single document may have. Every
+    /// scalar, alias, and start or end of a collection is one event.
+    pub fn max_events(mut self, events: usize) -> Self {
+        self.config.limits.events = Some(events);
+        self
+    }
+
+    /// Sets how many aliases a single document may contain. Zero rejects
+    /// aliases entirely.
+    pub fn max_aliases(mut self, aliases: usize) -> Self {
+        self.config.limits.aliases = Some(aliases);
+        self
+    }
+
+    /// Chooses whether tags other than the YAML core tags, like `!!str`
+    /// or `!!int`, are rejected, unless they select the variant of an
+    /// enum being deserialized. Disabled by default.
+    ///
+    /// This rejects [`Value::Tagged`](crate::Value::Tagged) values, as
+    /// well as tags that would otherwise be ignored.
+    ///
+    /// ```
+    /// use serde::Deserialize;
+    /// use serde_yml::{DeserializerBuilder, Value};
+    ///
+    /// #[derive(Deserialize, Debug, PartialEq)]
+    /// enum Shape {
+    ///     Circle(f64),
+    /// }
+    ///
+    /// let builder = DeserializerBuilder::new().reject_unknown_tags(true);
+    /// let shape: Shape =
+    ///     serde_yml::from_str_with("!Circle 1.5", &builder).unwrap();
+    /// assert_eq!(shape, Shape::Circle(1.5));
+    ///
+    /// let err = serde_yml::from_str_with::<Value>("!Circle 1.5", &builder)
+    ///     .unwrap_err();
+    /// assert_eq!(err.to_string(), "unknown tag !Circle");
+    /// ```
+    pub fn reject_unknown_tags(mut self, reject: bool) -> Self {
+        self.config.reject_unknown_tags = reject;
+        self
+    }
+
+    /// Sets what happens to a key that appears more than once in the
+    /// same mapping, whatever the type being deserialized. Defaults to
+    /// [`DuplicateKeys::Target`].
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use serde_yml::{DeserializerBuilder, DuplicateKeys};
+    ///
+    /// let yaml = "port: 80\nhost: a\nport: 8080\n";
+    ///
+    /// let builder =
+    ///     DeserializerBuilder::new().duplicate_keys(DuplicateKeys::FirstWins);
+    /// let map: HashMap<String, String> =
+    ///     serde_yml::from_str_with(yaml, &builder).unwrap();
+    /// assert_eq!(map["port"], "80");
+    ///
+    /// let builder =
+    ///     DeserializerBuilder::new().duplicate_keys(DuplicateKeys::Error);
+    /// let err = serde_yml::from_str_with::<HashMap<String, String>>(
+    ///     yaml, &builder,
+    /// )
+    /// .unwrap_err();
+    /// assert_eq!(
+    ///     err.to_string(),
+    ///     "duplicate entry with key \"port\" at line 3 column 1, \
+    ///      first defined at line 1 column 1",
+    /// );
+    /// ```
+    pub fn duplicate_keys(mut self, policy: DuplicateKeys) -> Self {
+        self.config.duplicate_keys = policy;
+        self
+    }
+
+    /// Sets the YAML version whose rules resolve the scalars of documents
+    /// without a `%YAML` directive. A document with a directive always
+    /// follows the version it names. Defaults to [`YamlVersion::V1_2`].
+    ///
+    /// With YAML 1.1, as written for PyYAML or Ruby, `yes`, `no`, `on`
+    /// and `off` are booleans, `0777` is octal, `1_000` is a thousand and
+    /// `1:30` is 90. Where a scalar is expected, a mapping stands for the
+    /// value of its `=` key.
+    ///
+    /// ```
+    /// use serde::Deserialize;
+    /// use serde_yml::{DeserializerBuilder, YamlVersion};
+    ///
+    /// #[derive(Deserialize, Debug, PartialEq)]
+    /// struct Service {
+    ///     enabled: bool,
+    ///     mode: u32,
+    ///     timeout: u64,
+    /// }
+    ///
+    /// let yaml = "enabled: yes\nmode: 0755\ntimeout: 1:30\n";
+    /// let builder = DeserializerBuilder::new().yaml_version(YamlVersion::V1_1);
+    /// let service: Service = serde_yml::from_str_with(yaml, &builder).unwrap();
+    /// assert_eq!(
+    ///     service,
+    ///     Service { enabled: true, mode: 0o755, timeout: 90 },
+    /// );
+    ///
+    /// // The directive switches to YAML 1.1 without any setting.
+    /// let yaml = format!("%YAML 1.1\n---\n{}", yaml);
+    /// let service: Service = serde_yml::from_str(&yaml).unwrap();
+    /// assert_eq!(service.timeout, 90);
+    /// ```
+    pub fn yaml_version(mut self, version: YamlVersion) -> Self {
+        self.config.yaml_version = version;
+        self
+    }
+
+    /// Sets the schema that resolves plain scalars without a tag.
+    /// Defaults to [`Schema::Core`].
+    ///
+    /// ```
+    /// use serde::Deserialize;
+    /// use serde_yml::{DeserializerBuilder, Schema, Value};
+    ///
+    /// let yaml = "country: no\ncode: 047\n";
+    ///
+    /// let builder = DeserializerBuilder::new().schema(Schema::Failsafe);
+    /// let value = Value::deserialize(builder.from_str(yaml)).unwrap();
+    /// assert_eq!(value["country"], "no");
+    ///
+    /// let builder = DeserializerBuilder::new().schema(Schema::Json);
+    /// let value = Value::deserialize(builder.from_str(yaml)).unwrap();
+    /// assert_eq!(value["country"], "no");
+    /// assert_eq!(value["code"], "047");
+    /// ```
+    pub fn schema(mut self, schema: Schema) -> Self {
+        self.config.schema = schema;
+        self
+    }
+
+    /// Creates a deserializer with these settings that reads a string of
+    /// YAML text.
+    #[allow(clippy::wrong_self_convention)]
+    pub fn from_str<'de>(&self, s: &'de str) -> Deserializer<'de> {
+        self.build(Progress::Str(s))
+    }
+
+    /// Creates a deserializer with these settings that reads bytes of
+    /// YAML text.
+    #[allow(clippy::wrong_self_convention)]
+    pub fn from_slice<'de>(&self, v: &'de [u8]) -> Deserializer<'de> {
+        self.build(Progress::Slice(v))
+    }
+
+    /// Creates a deserializer with these settings that reads YAML from
+    /// an IO stream.
+    #[allow(clippy::wrong_self_convention)]
+    pub fn from_reader<'de, R>(&self, rdr: R) -> Deserializer<'de>
+    where
+        R: io::Read + 'de,
+    {
+        self.build(Progress::Read(Box::new(rdr)))
+    }
+
+    fn build<'de>(&self, progress: Progress<'de>) -> Deserializer<'de> {
+        Deserializer {
+            progress,
+            config: self.config.clone(),
+        }
+    }
 }
 
 /// Represents the progress of parsing a YAML document.
@@ -163,8 +600,7 @@ impl<'de> Deserializer<'de> {
     /// ```
     #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &'de str) -> Self {
-        let progress = Progress::Str(s);
-        Deserializer { progress }
+        DeserializerBuilder::new().from_str(s)
     }
 
     /// Deserializes an instance of type `T` from bytes of YAML text.
@@ -194,8 +630,7 @@ impl<'de> Deserializer<'de> {
     /// println!("{:?}", item); // Item { name: "Widget", quantity: 100 }
     ///
     pub fn from_slice(v: &'de [u8]) -> Self {
-        let progress = Progress::Slice(v);
-        Deserializer { progress }
+        DeserializerBuilder::new().from_slice(v)
     }
 
     /// Deserializes an instance of type `T` from an IO stream of YAML.
@@ -204,6 +639,11 @@ impl<'de> Deserializer<'de> {
     /// reading from a file or over the network. It accepts any type that implements the `io::Read`
     /// trait. As with `from_str`, the target type `T` must implement the `Deserialize` trait.
     ///
+    /// The stream is read in chunks as it is parsed, and a single document is deserialized as it
+    /// is read rather than loaded first. Only the nodes that aliases may refer to, which have an
+    /// anchor, are kept in memory. When iterating over the documents of the stream, each one is
+    /// loaded before it is deserialized.
+    ///
     /// # Errors
     ///
     /// Deserialization might fail due to IO errors (e.g., if the stream is not readable), YAML syntax
@@ -235,8 +675,13 @@ impl<'de> Deserializer<'de> {
     where
         R: io::Read + 'de,
     {
-        let progress = Progress::Read(Box::new(rdr));
-        Deserializer { progress }
+        DeserializerBuilder::new().from_reader(rdr)
+    }
+
+    /// Creates a [`DeserializerBuilder`] for a deserializer with custom
+    /// input settings.
+    pub fn builder() -> DeserializerBuilder {
+        DeserializerBuilder::new()
     }
 
     fn de<T>(
@@ -247,6 +692,7 @@ impl<'de> Deserializer<'de> {
     ) -> Result<T> {
         let mut pos = 0;
         let mut jumpcount = 0;
+        let mut config = self.config;
 
         match self.progress {
             Progress::Iterable(_) => {
@@ -255,42 +701,73 @@ impl<'de> Deserializer<'de> {
             Progress::Document(document) => {
                 let t = f(&mut DeserializerFromEvents {
                     document: &document,
+                    config: &config,
                     pos: &mut pos,
                     jumpcount: &mut jumpcount,
                     path: Path::Root,
-                    remaining_depth: 128,
+                    remaining_depth: config.max_depth,
                     current_enum: None,
-                })?;
+                });
+                // The events of a document that failed to parse stop
+                // short, so an error from `f` is most likely caused by
+                // the parse error, which is reported instead.
                 if let Some(parse_error) = document.error {
                     return Err(error::shared(parse_error));
                 }
-                return Ok(t);
+                return t;
             }
             _ => {}
         }
 
-        let mut loader = Loader::new(self.progress)?;
+        let mut loader =
+            Loader::with_limits(self.progress, config.limits)?;
         let document = match loader.next_document() {
             Some(document) => document,
             None => return Err(error::new(ErrorImpl::EndOfStream)),
         };
+        if let Some(version) = loader.document_version() {
+            config.yaml_version = version;
+        }
         let t = f(&mut DeserializerFromEvents {
             document: &document,
+            config: &config,
             pos: &mut pos,
             jumpcount: &mut jumpcount,
             path: Path::Root,
-            remaining_depth: 128,
+            remaining_depth: config.max_depth,
             current_enum: None,
-        })?;
+        });
         if let Some(parse_error) = document.error {
             return Err(error::shared(parse_error));
         }
+        let t = t?;
         if loader.next_document().is_none() {
             Ok(t)
         } else {
             Err(error::new(ErrorImpl::MoreThanOneDocument))
         }
     }
+
+    /// Deserializes the only document of a reader with `f` as the reader
+    /// is parsed, without loading the document first.
+    fn stream<T>(
+        self,
+        f: impl for<'stream> FnOnce(
+            &mut StreamDeserializer<'de, 'stream>,
+        ) -> Result<T>,
+    ) -> Result<T> {
+        let loader =
+            Loader::with_limits(self.progress, self.config.limits)?;
+        let mut stream = EventStream::new(loader);
+        let t = f(&mut StreamDeserializer {
+            stream: &mut stream,
+            config: &self.config,
+            path: Path::Root,
+            remaining_depth: self.config.max_depth,
+        })?;
+        stream.finish()?;
+        Ok(t)
+    }
 }
 
 impl Iterator for Deserializer<'_> {
@@ -300,14 +777,20 @@ impl Iterator for Deserializer<'_> {
         match &mut self.progress {
             Progress::Iterable(loader) => {
                 let document = loader.next_document()?;
+                let mut config = self.config.clone();
+                if let Some(version) = loader.document_version() {
+                    config.yaml_version = version;
+                }
                 return Some(Deserializer {
                     progress: Progress::Document(document),
+                    config,
                 });
             }
             Progress::Document(_) => return None,
             Progress::Fail(err) => {
                 return Some(Deserializer {
                     progress: Progress::Fail(Arc::clone(err)),
+                    config: self.config.clone(),
                 });
             }
             _ => {}
@@ -315,7 +798,7 @@ impl Iterator for Deserializer<'_> {
 
         let dummy = Progress::Str("");
         let input = mem::replace(&mut self.progress, dummy);
-        match Loader::new(input) {
+        match Loader::with_limits(input, self.config.limits) {
             Ok(loader) => {
                 self.progress = Progress::Iterable(loader);
                 self.next()
@@ -325,12 +808,25 @@ impl Iterator for Deserializer<'_> {
                 self.progress = Progress::Fail(Arc::clone(&fail));
                 Some(Deserializer {
                     progress: Progress::Fail(fail),
+                    config: self.config.clone(),
                 })
             }
         }
     }
 }
 
+/// Deserializes the only document of the input of a [`Deserializer`]
+/// with `$body`, as it is parsed if the input comes from a reader.
+macro_rules! de_document {
+    ($self:ident, |$state:ident| $body:expr) => {
+        if let Progress::Read(_) = $self.progress {
+            $self.stream(|$state| $body)
+        } else {
+            $self.de(|$state| $body)
+        }
+    };
+}
+
 impl<'de> de::Deserializer<'de> for Deserializer<'de> {
     type Error = Error;
 
@@ -338,147 +834,147 @@ impl<'de> de::Deserializer<'de> for Deserializer<'de> {
     where
         V: Visitor<'de>,
     {
-        self.de(|state| state.deserialize_any(visitor))
+        de_document!(self, |state| state.deserialize_any(visitor))
     }
 
     fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.de(|state| state.deserialize_bool(visitor))
+        de_document!(self, |state| state.deserialize_bool(visitor))
     }
 
     fn deserialize_i8<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.de(|state| state.deserialize_i8(visitor))
+        de_document!(self, |state| state.deserialize_i8(visitor))
     }
 
     fn deserialize_i16<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.de(|state| state.deserialize_i16(visitor))
+        de_document!(self, |state| state.deserialize_i16(visitor))
     }
 
     fn deserialize_i32<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.de(|state| state.deserialize_i32(visitor))
+        de_document!(self, |state| state.deserialize_i32(visitor))
     }
 
     fn deserialize_i64<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.de(|state| state.deserialize_i64(visitor))
+        de_document!(self, |state| state.deserialize_i64(visitor))
     }
 
     fn deserialize_i128<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.de(|state| state.deserialize_i128(visitor))
+        de_document!(self, |state| state.deserialize_i128(visitor))
     }
 
     fn deserialize_u8<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.de(|state| state.deserialize_u8(visitor))
+        de_document!(self, |state| state.deserialize_u8(visitor))
     }
 
     fn deserialize_u16<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.de(|state| state.deserialize_u16(visitor))
+        de_document!(self, |state| state.deserialize_u16(visitor))
     }
 
     fn deserialize_u32<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.de(|state| state.deserialize_u32(visitor))
+        de_document!(self, |state| state.deserialize_u32(visitor))
     }
 
     fn deserialize_u64<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.de(|state| state.deserialize_u64(visitor))
+        de_document!(self, |state| state.deserialize_u64(visitor))
     }
 
     fn deserialize_u128<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.de(|state| state.deserialize_u128(visitor))
+        de_document!(self, |state| state.deserialize_u128(visitor))
     }
 
     fn deserialize_f32<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.de(|state| state.deserialize_f32(visitor))
+        de_document!(self, |state| state.deserialize_f32(visitor))
     }
 
     fn deserialize_f64<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.de(|state| state.deserialize_f64(visitor))
+        de_document!(self, |state| state.deserialize_f64(visitor))
     }
 
     fn deserialize_char<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.de(|state| state.deserialize_char(visitor))
+        de_document!(self, |state| state.deserialize_char(visitor))
     }
 
     fn deserialize_str<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.de(|state| state.deserialize_str(visitor))
+        de_document!(self, |state| state.deserialize_str(visitor))
     }
 
     fn deserialize_string<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.de(|state| state.deserialize_string(visitor))
+        de_document!(self, |state| state.deserialize_string(visitor))
     }
 
     fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.de(|state| state.deserialize_bytes(visitor))
+        de_document!(self, |state| state.deserialize_bytes(visitor))
     }
 
     fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.de(|state| state.deserialize_byte_buf(visitor))
+        de_document!(self, |state| state.deserialize_byte_buf(visitor))
     }
 
     fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.de(|state| state.deserialize_option(visitor))
+        de_document!(self, |state| state.deserialize_option(visitor))
     }
 
     fn deserialize_unit<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.de(|state| state.deserialize_unit(visitor))
+        de_document!(self, |state| state.deserialize_unit(visitor))
     }
 
     fn deserialize_unit_struct<V>(
@@ -489,7 +985,8 @@ impl<'de> de::Deserializer<'de> for Deserializer<'de> {
     where
         V: Visitor<'de>,
     {
-        self.de(|state| state.deserialize_unit_struct(name, visitor))
+        de_document!(self, |state| state
+            .deserialize_unit_struct(name, visitor))
     }
 
     fn deserialize_newtype_struct<V>(
@@ -500,14 +997,15 @@ impl<'de> de::Deserializer<'de> for Deserializer<'de> {
     where
         V: Visitor<'de>,
     {
-        self.de(|state| state.deserialize_newtype_struct(name, visitor))
+        de_document!(self, |state| state
+            .deserialize_newtype_struct(name, visitor))
     }
 
     fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.de(|state| state.deserialize_seq(visitor))
+        de_document!(self, |state| state.deserialize_seq(visitor))
     }
 
     fn deserialize_tuple<V>(
@@ -518,7 +1016,8 @@ impl<'de> de::Deserializer<'de> for Deserializer<'de> {
     where
         V: Visitor<'de>,
     {
-        self.de(|state| state.deserialize_tuple(len, visitor))
+        de_document!(self, |state| state
+            .deserialize_tuple(len, visitor))
     }
 
     fn deserialize_tuple_struct<V>(
@@ -530,7 +1029,7 @@ impl<'de> de::Deserializer<'de> for Deserializer<'de> {
     where
         V: Visitor<'de>,
     {
-        self.de(|state| {
+        de_document!(self, |state| {
             state.deserialize_tuple_struct(name, len, visitor)
         })
     }
@@ -539,7 +1038,7 @@ impl<'de> de::Deserializer<'de> for Deserializer<'de> {
     where
         V: Visitor<'de>,
     {
-        self.de(|state| state.deserialize_map(visitor))
+        de_document!(self, |state| state.deserialize_map(visitor))
     }
 
     fn deserialize_struct<V>(
@@ -551,7 +1050,8 @@ impl<'de> de::Deserializer<'de> for Deserializer<'de> {
     where
         V: Visitor<'de>,
     {
-        self.de(|state| state.deserialize_struct(name, fields, visitor))
+        de_document!(self, |state| state
+            .deserialize_struct(name, fields, visitor))
     }
 
     fn deserialize_enum<V>(
@@ -563,21 +1063,24 @@ impl<'de> de::Deserializer<'de> for Deserializer<'de> {
     where
         V: Visitor<'de>,
     {
-        self.de(|state| state.deserialize_enum(name, variants, visitor))
+        de_document!(self, |state| state
+            .deserialize_enum(name, variants, visitor))
     }
 
     fn deserialize_identifier<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.de(|state| state.deserialize_identifier(visitor))
+        de_document!(self, |state| state
+            .deserialize_identifier(visitor))
     }
 
     fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.de(|state| state.deserialize_ignored_any(visitor))
+        de_document!(self, |state| state
+            .deserialize_ignored_any(visitor))
     }
 }
 
@@ -613,10 +1116,11 @@ pub enum Event<'de> {
 
 struct DeserializerFromEvents<'de, 'document> {
     document: &'document Document<'de>,
+    config: &'document DeserializerConfig,
     pos: &'document mut usize,
     jumpcount: &'document mut usize,
     path: Path<'document>,
-    remaining_depth: u8,
+    remaining_depth: usize,
     current_enum: Option<CurrentEnum<'document>>,
 }
 
@@ -632,7 +1136,16 @@ impl<'de, 'document> DeserializerFromEvents<'de, 'document> {
     }
 
     fn peek_event_mark(&self) -> Result<(&'document Event<'de>, Mark)> {
-        match self.document.events.get(*self.pos) {
+        self.event_mark_at(*self.pos)
+    }
+
+    /// The event at `pos`, or the error that ended the document before
+    /// it.
+    fn event_mark_at(
+        &self,
+        pos: usize,
+    ) -> Result<(&'document Event<'de>, Mark)> {
+        match self.document.events.get(pos) {
             Some((event, mark)) => Ok((event, *mark)),
             None => Err(match &self.document.error {
                 Some(parse_error) => {
@@ -643,6 +1156,19 @@ impl<'de, 'document> DeserializerFromEvents<'de, 'document> {
         }
     }
 
+    /// The rules that resolve the scalars of the document.
+    fn resolution(&self) -> Resolution {
+        match self.config.schema {
+            Schema::Failsafe => return Resolution::Failsafe,
+            Schema::Json => return Resolution::Json,
+            Schema::Core => {}
+        }
+        match self.config.yaml_version {
+            YamlVersion::V1_1 => Resolution::Yaml11,
+            YamlVersion::V1_2 => Resolution::Core,
+        }
+    }
+
     fn next_event(&mut self) -> Result<&'document Event<'de>> {
         self.next_event_mark().map(|(event, _mark)| event)
     }
@@ -650,11 +1176,22 @@ impl<'de, 'document> DeserializerFromEvents<'de, 'document> {
     fn next_event_mark(
         &mut self,
     ) -> Result<(&'document Event<'de>, Mark)> {
-        self.peek_event_mark().map(|(event, mark)| {
-            *self.pos += 1;
-            self.current_enum = None;
-            (event, mark)
-        })
+        let (event, mark) = self.peek_event_mark()?;
+        // The tag of an event that is consumed as the content of an enum
+        // has already been read as its variant.
+        if self.config.reject_unknown_tags
+            && self.current_enum.is_none()
+        {
+            if let Some(tag) = unknown_tag(event) {
+                let tag = String::from_utf8_lossy(tag).into_owned();
+                return Err(error::new(ErrorImpl::UnknownTag(
+                    tag, mark,
+                )));
+            }
+        }
+        *self.pos += 1;
+        self.current_enum = None;
+        Ok((event, mark))
     }
 
     fn jump<'anchor>(
@@ -662,7 +1199,11 @@ impl<'de, 'document> DeserializerFromEvents<'de, 'document> {
         pos: &'anchor mut usize,
     ) -> Result<DeserializerFromEvents<'de, 'anchor>> {
         *self.jumpcount += 1;
-        if *self.jumpcount > self.document.events.len() * 100 {
+        let limit = self
+            .config
+            .max_alias_expansions
+            .unwrap_or(self.document.events.len() * 100);
+        if *self.jumpcount > limit {
             return Err(error::new(ErrorImpl::RepetitionLimitExceeded));
         }
         match self.document.anchor_event_map.get(pos) {
@@ -670,6 +1211,7 @@ impl<'de, 'document> DeserializerFromEvents<'de, 'document> {
                 *pos = *found;
                 Ok(DeserializerFromEvents {
                     document: self.document,
+                    config: self.config,
                     pos,
                     jumpcount: self.jumpcount,
                     path: Path::Alias { parent: &self.path },
@@ -738,14 +1280,24 @@ impl<'de, 'document> DeserializerFromEvents<'de, 'document> {
         Ok(value)
     }
 
+    /// Visits the mapping that starts at the next event. With `merge`,
+    /// the entries of the mappings given under `<<` keys are merged into
+    /// it, as [`Value::apply_merge`](crate::Value::apply_merge) does.
     fn visit_mapping<V>(
         &mut self,
         visitor: V,
         mark: Mark,
+        merge: bool,
     ) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
+        if self.config.duplicate_keys != DuplicateKeys::Target
+            || (merge && self.has_merge_key())
+        {
+            return self
+                .visit_deduplicated_mapping(visitor, mark, merge);
+        }
         let (value, len) = self.recursion_check(mark, |de| {
             let mut map = MapAccess {
                 empty: false,
@@ -760,119 +1312,495 @@ impl<'de, 'document> DeserializerFromEvents<'de, 'document> {
         Ok(value)
     }
 
-    fn end_sequence(&mut self, len: usize) -> Result<()> {
-        let total = {
-            let mut seq = SeqAccess {
-                empty: false,
-                de: self,
-                len,
-            };
-            while de::SeqAccess::next_element::<IgnoredAny>(&mut seq)?
-                .is_some()
-            {}
-            seq.len
-        };
-        match self.next_event()? {
-            Event::SequenceEnd | Event::Void => {}
-            _ => panic!("expected a SequenceEnd event"),
-        }
-        if total == len {
-            Ok(())
-        } else {
-            struct ExpectedSeq(usize);
-            impl Expected for ExpectedSeq {
-                fn fmt(
-                    &self,
-                    formatter: &mut Formatter<'_>,
-                ) -> fmt::Result {
-                    if self.0 == 1 {
-                        write!(formatter, "sequence of 1 element")
-                    } else {
-                        write!(
-                            formatter,
-                            "sequence of {} elements",
-                            self.0
-                        )
-                    }
-                }
-            }
-            Err(de::Error::invalid_length(total, &ExpectedSeq(len)))
-        }
-    }
-
-    fn end_mapping(&mut self, len: usize) -> Result<()> {
-        let total = {
-            let mut map = MapAccess {
-                empty: false,
-                de: self,
-                len,
-                key: None,
-            };
-            while de::MapAccess::next_entry::<IgnoredAny, IgnoredAny>(
-                &mut map,
-            )?
-            .is_some()
-            {}
-            map.len
-        };
+    /// Visits a mapping whose entries with the same key are combined
+    /// according to the [`DuplicateKeys`] setting, and into which, with
+    /// `merge`, the mappings given under `<<` keys are merged.
+    fn visit_deduplicated_mapping<V>(
+        &mut self,
+        visitor: V,
+        mark: Mark,
+        merge: bool,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        let (value, len, total, end) =
+            self.recursion_check(mark, |de| {
+                let (entries, end) = de.mapping_entries(merge)?;
+                let total = entries.len();
+                let mut map = DeduplicatedMapAccess {
+                    de,
+                    entries: entries.into_iter(),
+                    len: 0,
+                    key: None,
+                    values: Vec::new(),
+                };
+                let value = visitor.visit_map(&mut map)?;
+                Ok((value, map.len, total, end))
+            })?;
+        *self.pos = end;
         match self.next_event()? {
             Event::MappingEnd | Event::Void => {}
             _ => panic!("expected a MappingEnd event"),
         }
         if total == len {
-            Ok(())
+            Ok(value)
         } else {
-            struct ExpectedMap(usize);
-            impl Expected for ExpectedMap {
-                fn fmt(
-                    &self,
-                    formatter: &mut Formatter<'_>,
-                ) -> fmt::Result {
-                    if self.0 == 1 {
-                        write!(formatter, "map containing 1 entry")
-                    } else {
-                        write!(
-                            formatter,
-                            "map containing {} entries",
-                            self.0
-                        )
-                    }
-                }
-            }
             Err(de::Error::invalid_length(total, &ExpectedMap(len)))
         }
     }
 
-    fn recursion_check<F: FnOnce(&mut Self) -> Result<T>, T>(
+    /// Reads ahead through the entries of the mapping that starts at the
+    /// next event and groups them by key. Returns the groups in the order
+    /// in which their keys first appear, and the position of the end of
+    /// the mapping.
+    fn mapping_entries(
         &mut self,
-        mark: Mark,
-        f: F,
-    ) -> Result<T> {
-        let previous_depth = self.remaining_depth;
-        self.remaining_depth = match previous_depth.checked_sub(1) {
-            Some(depth) => depth,
-            None => {
-                return Err(error::new(
-                    ErrorImpl::RecursionLimitExceeded(mark),
-                ))
+        merge: bool,
+    ) -> Result<(Vec<MappingEntry>, usize)> {
+        let mut entries = Vec::new();
+        let mut seen = HashMap::new();
+        let end = self.add_entries(
+            *self.pos,
+            merge,
+            false,
+            &mut entries,
+            &mut seen,
+        )?;
+        Ok((entries, end))
+    }
+
+    /// Adds the entries of the mapping whose first key is at `pos` to
+    /// `entries`, where `seen` holds the index of the group of every key
+    /// added so far. The entries of a `merged` mapping only add the keys
+    /// that are not there yet. Returns the position of the end of the
+    /// mapping.
+    fn add_entries(
+        &mut self,
+        mut pos: usize,
+        merge: bool,
+        merged: bool,
+        entries: &mut Vec<MappingEntry>,
+        seen: &mut HashMap<Value, usize>,
+    ) -> Result<usize> {
+        let mut merges = Vec::new();
+        loop {
+            match self.document.events.get(pos) {
+                None
+                | Some((Event::MappingEnd, _))
+                | Some((Event::Void, _)) => break,
+                Some(_) => {}
             }
-        };
-        let result = f(self);
-        self.remaining_depth = previous_depth;
-        result
-    }
-}
+            let key_pos = pos;
+            let value_pos = self.node_end(key_pos);
+            pos = self.node_end(value_pos);
 
-struct SeqAccess<'de, 'document, 'seq> {
-    empty: bool,
-    de: &'seq mut DeserializerFromEvents<'de, 'document>,
-    len: usize,
-}
+            if merge && self.is_merge_key(key_pos) {
+                merges.push(value_pos);
+                continue;
+            }
 
-impl<'de> de::SeqAccess<'de> for SeqAccess<'de, '_, '_> {
-    type Error = Error;
+            let first = match self.key_at(key_pos) {
+                Some(key) => match seen.entry(key) {
+                    hash_map::Entry::Occupied(first) => {
+                        Some((*first.get(), first.key().clone()))
+                    }
+                    hash_map::Entry::Vacant(vacant) => {
+                        vacant.insert(entries.len());
+                        None
+                    }
+                },
+                None => None,
+            };
+            let (index, key) = match first {
+                // The keys of the mapping itself, and those of the
+                // mappings merged before, take precedence.
+                Some(_) if merged => continue,
+                Some(first) => first,
+                None => {
+                    entries.push(MappingEntry {
+                        key: key_pos,
+                        values: vec![value_pos],
+                    });
+                    continue;
+                }
+            };
+            let entry = &mut entries[index];
+            match self.config.duplicate_keys {
+                // The type being deserialized decides what to do with
+                // the duplicate.
+                DuplicateKeys::Target => entries.push(MappingEntry {
+                    key: key_pos,
+                    values: vec![value_pos],
+                }),
+                DuplicateKeys::FirstWins => {}
+                DuplicateKeys::Error => {
+                    return Err(error::new(ErrorImpl::DuplicateKey(
+                        EntryKey(&key).to_string(),
+                        self.document.events[entry.key].1,
+                        self.document.events[key_pos].1,
+                    )));
+                }
+                DuplicateKeys::LastWins => {
+                    entry.values = vec![value_pos]
+                }
+                DuplicateKeys::CollectAll => {
+                    entry.values.push(value_pos)
+                }
+            }
+        }
+        for value_pos in merges {
+            self.add_merged_entries(value_pos, entries, seen)?;
+        }
+        Ok(pos)
+    }
 
-    fn next_element_seed<T>(
+    /// Reads the key at `pos` as a `Value` to compare it with the other
+    /// keys of its mapping. A key that cannot be read as a `Value` is
+    /// never a duplicate. Whatever is wrong with it is reported when it is
+    /// deserialized.
+    fn key_at(&mut self, mut pos: usize) -> Option<Value> {
+        Value::deserialize(&mut DeserializerFromEvents {
+            document: self.document,
+            config: self.config,
+            pos: &mut pos,
+            jumpcount: self.jumpcount,
+            path: Path::Unknown { parent: &self.path },
+            remaining_depth: self.remaining_depth,
+            current_enum: None,
+        })
+        .ok()
+    }
+
+    /// Adds the entries of the mapping, or of each mapping in the
+    /// sequence, that is the value of a `<<` key at `pos`.
+    fn add_merged_entries(
+        &mut self,
+        pos: usize,
+        entries: &mut Vec<MappingEntry>,
+        seen: &mut HashMap<Value, usize>,
+    ) -> Result<()> {
+        let pos = self.resolve_alias(pos)?;
+        let (event, mark) = self.event_mark_at(pos)?;
+        match event {
+            Event::MappingStart(mapping) => {
+                if parse_tag(&mapping.tag).is_some() {
+                    return Err(error::new(ErrorImpl::TaggedInMerge));
+                }
+                self.recursion_check(mark, |de| {
+                    de.add_entries(pos + 1, true, true, entries, seen)
+                })?;
+            }
+            Event::SequenceStart(sequence) => {
+                if parse_tag(&sequence.tag).is_some() {
+                    return Err(error::new(ErrorImpl::TaggedInMerge));
+                }
+                let mut element = pos + 1;
+                loop {
+                    let pos = match self.document.events.get(element) {
+                        None
+                        | Some((Event::SequenceEnd, _))
+                        | Some((Event::Void, _)) => break,
+                        Some(_) => self.resolve_alias(element)?,
+                    };
+                    let (event, mark) = self.event_mark_at(pos)?;
+                    let tag = match event {
+                        Event::MappingStart(mapping) => &mapping.tag,
+                        Event::SequenceStart(sequence) => &sequence.tag,
+                        Event::Scalar(scalar) => &scalar.tag,
+                        _ => &None,
+                    };
+                    if parse_tag(tag).is_some() {
+                        return Err(error::new(
+                            ErrorImpl::TaggedInMerge,
+                        ));
+                    }
+                    match event {
+                        Event::MappingStart(_) => {
+                            self.recursion_check(mark, |de| {
+                                de.add_entries(
+                                    pos + 1,
+                                    true,
+                                    true,
+                                    entries,
+                                    seen,
+                                )
+                            })?;
+                        }
+                        Event::SequenceStart(_) => {
+                            return Err(error::new(
+                                ErrorImpl::SequenceInMergeElement,
+                            ));
+                        }
+                        _ => {
+                            return Err(error::new(
+                                ErrorImpl::ScalarInMergeElement,
+                            ));
+                        }
+                    }
+                    element = self.node_end(element);
+                }
+            }
+            Event::Scalar(scalar) => {
+                if parse_tag(&scalar.tag).is_some() {
+                    return Err(error::new(ErrorImpl::TaggedInMerge));
+                }
+                return Err(error::new(ErrorImpl::ScalarInMerge));
+            }
+            _ => return Err(error::new(ErrorImpl::ScalarInMerge)),
+        }
+        Ok(())
+    }
+
+    /// Whether the node at `pos` is a `<<` merge key.
+    fn is_merge_key(&self, pos: usize) -> bool {
+        match self.document.events.get(pos) {
+            Some((Event::Scalar(scalar), _mark)) => {
+                scalar.tag.is_none()
+                    && scalar.style == ScalarStyle::Plain
+                    && *scalar.value == *b"<<"
+            }
+            _ => false,
+        }
+    }
+
+    /// Checks whether the mapping whose first key is at the next event
+    /// has a `<<` merge key.
+    fn has_merge_key(&self) -> bool {
+        let mut pos = *self.pos;
+        loop {
+            match self.document.events.get(pos) {
+                None
+                | Some((Event::MappingEnd, _))
+                | Some((Event::Void, _)) => return false,
+                Some(_) => {}
+            }
+            if self.is_merge_key(pos) {
+                return true;
+            }
+            pos = self.node_end(self.node_end(pos));
+        }
+    }
+
+    /// Returns the position of the node that the alias at `pos` refers
+    /// to, or `pos` itself if it is not an alias.
+    fn resolve_alias(&mut self, pos: usize) -> Result<usize> {
+        let mut pos = pos;
+        if let Some((Event::Alias(id), _mark)) =
+            self.document.events.get(pos)
+        {
+            pos = *id;
+            self.jump(&mut pos)?;
+        }
+        Ok(pos)
+    }
+
+    /// Under the YAML 1.1 rules, a mapping stands for the value of its
+    /// `=` key where a scalar is expected. If the next event starts such
+    /// a mapping, returns the position of that value and of the end of
+    /// the mapping.
+    fn default_value(&self) -> Option<(usize, usize)> {
+        if self.resolution() != Resolution::Yaml11
+            || self.current_enum.is_some()
+        {
+            return None;
+        }
+        match self.document.events.get(*self.pos) {
+            Some((Event::MappingStart(_), _mark)) => {}
+            _ => return None,
+        }
+        let mut pos = *self.pos + 1;
+        loop {
+            match self.document.events.get(pos) {
+                None
+                | Some((Event::MappingEnd, _))
+                | Some((Event::Void, _)) => return None,
+                Some((Event::Scalar(key), _mark))
+                    if key.tag.is_none()
+                        && key.style == ScalarStyle::Plain
+                        && *key.value == *b"=" =>
+                {
+                    let value = self.node_end(pos);
+                    return Some((value, self.node_end(*self.pos)));
+                }
+                Some(_) => pos = self.node_end(self.node_end(pos)),
+            }
+        }
+    }
+
+    /// Skips the mapping that starts at the next event and returns a
+    /// deserializer for the value of its `=` key at `pos`.
+    fn default_value_de<'a>(
+        &'a mut self,
+        pos: &'a mut usize,
+        end: usize,
+    ) -> DeserializerFromEvents<'de, 'a> {
+        *self.pos = end;
+        DeserializerFromEvents {
+            document: self.document,
+            config: self.config,
+            pos,
+            jumpcount: self.jumpcount,
+            path: Path::Map {
+                parent: &self.path,
+                key: "=",
+            },
+            remaining_depth: self.remaining_depth,
+            current_enum: None,
+        }
+    }
+
+    /// Returns the position just after the node that starts at `pos`.
+    fn node_end(&self, mut pos: usize) -> usize {
+        let mut depth = 0usize;
+        while let Some((event, _mark)) = self.document.events.get(pos) {
+            match event {
+                Event::SequenceStart(_) | Event::MappingStart(_) => {
+                    depth += 1;
+                }
+                Event::SequenceEnd | Event::MappingEnd => {
+                    if depth == 0 {
+                        break;
+                    }
+                    depth -= 1;
+                }
+                Event::Alias(_) | Event::Scalar(_) | Event::Void => {}
+            }
+            pos += 1;
+            if depth == 0 {
+                break;
+            }
+        }
+        pos
+    }
+
+    /// Parses an enum written as a mapping with a single entry, whose key
+    /// is the variant and whose value is the content. This is how a
+    /// variant that is the direct content of another one is serialized.
+    fn visit_single_entry_enum<V>(
+        &mut self,
+        visitor: V,
+        mark: Mark,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.next_event()?;
+        let value = self.recursion_check(mark, |de| {
+            visitor.visit_enum(SingleEntryEnumAccess { de })
+        })?;
+        self.end_mapping(1)?;
+        Ok(value)
+    }
+
+    /// Checks whether the mapping that starts at the next event has
+    /// exactly one entry.
+    fn is_single_entry_mapping(&self) -> bool {
+        let mut depth = 0;
+        let mut nodes = 0;
+        for (event, _mark) in &self.document.events[*self.pos + 1..] {
+            match event {
+                Event::SequenceStart(_) | Event::MappingStart(_) => {
+                    if depth == 0 {
+                        nodes += 1;
+                    }
+                    depth += 1;
+                }
+                Event::SequenceEnd | Event::MappingEnd => {
+                    if depth == 0 {
+                        return nodes == 2;
+                    }
+                    depth -= 1;
+                }
+                Event::Alias(_) | Event::Scalar(_) | Event::Void => {
+                    if depth == 0 {
+                        nodes += 1;
+                    }
+                }
+            }
+        }
+        false
+    }
+
+    fn end_sequence(&mut self, len: usize) -> Result<()> {
+        let total = {
+            let mut seq = SeqAccess {
+                empty: false,
+                de: self,
+                len,
+            };
+            while de::SeqAccess::next_element::<IgnoredAny>(&mut seq)?
+                .is_some()
+            {}
+            seq.len
+        };
+        match self.next_event()? {
+            Event::SequenceEnd | Event::Void => {}
+            _ => panic!("expected a SequenceEnd event"),
+        }
+        if total == len {
+            Ok(())
+        } else {
+            Err(de::Error::invalid_length(total, &ExpectedSeq(len)))
+        }
+    }
+
+    fn end_mapping(&mut self, len: usize) -> Result<()> {
+        let total = {
+            let mut map = MapAccess {
+                empty: false,
+                de: self,
+                len,
+                key: None,
+            };
+            while de::MapAccess::next_entry::<IgnoredAny, IgnoredAny>(
+                &mut map,
+            )?
+            .is_some()
+            {}
+            map.len
+        };
+        match self.next_event()? {
+            Event::MappingEnd | Event::Void => {}
+            _ => panic!("expected a MappingEnd event"),
+        }
+        if total == len {
+            Ok(())
+        } else {
+            Err(de::Error::invalid_length(total, &ExpectedMap(len)))
+        }
+    }
+
+    fn recursion_check<F: FnOnce(&mut Self) -> Result<T>, T>(
+        &mut self,
+        mark: Mark,
+        f: F,
+    ) -> Result<T> {
+        let previous_depth = self.remaining_depth;
+        self.remaining_depth = match previous_depth.checked_sub(1) {
+            Some(depth) => depth,
+            None => {
+                return Err(error::new(
+                    ErrorImpl::RecursionLimitExceeded(mark),
+                ))
+            }
+        };
+        let result = f(self);
+        self.remaining_depth = previous_depth;
+        result
+    }
+}
+
+struct SeqAccess<'de, 'document, 'seq> {
+    empty: bool,
+    de: &'seq mut DeserializerFromEvents<'de, 'document>,
+    len: usize,
+}
+
+impl<'de> de::SeqAccess<'de> for SeqAccess<'de, '_, '_> {
+    type Error = Error;
+
+    fn next_element_seed<T>(
         &mut self,
         seed: T,
     ) -> Result<Option<T::Value>>
@@ -887,6 +1815,7 @@ impl<'de> de::SeqAccess<'de> for SeqAccess<'de, '_, '_> {
             _ => {
                 let mut element_de = DeserializerFromEvents {
                     document: self.de.document,
+                    config: self.de.config,
                     pos: self.de.pos,
                     jumpcount: self.de.jumpcount,
                     path: Path::Seq {
@@ -941,6 +1870,7 @@ impl<'de> de::MapAccess<'de> for MapAccess<'de, '_, '_> {
     {
         let mut value_de = DeserializerFromEvents {
             document: self.de.document,
+            config: self.de.config,
             pos: self.de.pos,
             jumpcount: self.de.jumpcount,
             path: if let Some(key) =
@@ -962,6 +1892,185 @@ impl<'de> de::MapAccess<'de> for MapAccess<'de, '_, '_> {
     }
 }
 
+struct ExpectedSeq(usize);
+
+impl Expected for ExpectedSeq {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> fmt::Result {
+        if self.0 == 1 {
+            write!(formatter, "sequence of 1 element")
+        } else {
+            write!(formatter, "sequence of {} elements", self.0)
+        }
+    }
+}
+
+struct ExpectedMap(usize);
+
+impl Expected for ExpectedMap {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> fmt::Result {
+        if self.0 == 1 {
+            write!(formatter, "map containing 1 entry")
+        } else {
+            write!(formatter, "map containing {} entries", self.0)
+        }
+    }
+}
+
+/// The entries of a mapping that have the same key: the position of the
+/// first key, and the positions of the values that are kept.
+struct MappingEntry {
+    key: usize,
+    values: Vec<usize>,
+}
+
+struct DeduplicatedMapAccess<'de, 'document, 'map> {
+    de: &'map mut DeserializerFromEvents<'de, 'document>,
+    entries: std::vec::IntoIter<MappingEntry>,
+    len: usize,
+    key: Option<&'document str>,
+    values: Vec<usize>,
+}
+
+impl<'de> de::MapAccess<'de> for DeduplicatedMapAccess<'de, '_, '_> {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        let entry = match self.entries.next() {
+            Some(entry) => entry,
+            None => return Ok(None),
+        };
+        self.len += 1;
+        self.key = match &self.de.document.events[entry.key].0 {
+            Event::Scalar(scalar) => str::from_utf8(&scalar.value).ok(),
+            _ => None,
+        };
+        self.values = entry.values;
+        let mut pos = entry.key;
+        let mut key_de = DeserializerFromEvents {
+            document: self.de.document,
+            config: self.de.config,
+            pos: &mut pos,
+            jumpcount: self.de.jumpcount,
+            path: self.de.path,
+            remaining_depth: self.de.remaining_depth,
+            current_enum: None,
+        };
+        seed.deserialize(&mut key_de).map(Some)
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let values = mem::take(&mut self.values);
+        let path = match self.key {
+            Some(key) => Path::Map {
+                parent: &self.de.path,
+                key,
+            },
+            None => Path::Unknown {
+                parent: &self.de.path,
+            },
+        };
+        if self.de.config.duplicate_keys == DuplicateKeys::CollectAll {
+            return seed.deserialize(CollectedValues {
+                document: self.de.document,
+                config: self.de.config,
+                jumpcount: self.de.jumpcount,
+                path,
+                remaining_depth: self.de.remaining_depth,
+                values: values.into_iter(),
+                len: 0,
+            });
+        }
+        let mut pos = values[0];
+        let mut value_de = DeserializerFromEvents {
+            document: self.de.document,
+            config: self.de.config,
+            pos: &mut pos,
+            jumpcount: self.de.jumpcount,
+            path,
+            remaining_depth: self.de.remaining_depth,
+            current_enum: None,
+        };
+        seed.deserialize(&mut value_de)
+    }
+}
+
+/// The values of every occurrence of a key, which are deserialized as a
+/// sequence when duplicate keys are collected.
+struct CollectedValues<'de, 'document, 'map> {
+    document: &'document Document<'de>,
+    config: &'document DeserializerConfig,
+    jumpcount: &'map mut usize,
+    path: Path<'map>,
+    remaining_depth: usize,
+    values: std::vec::IntoIter<usize>,
+    len: usize,
+}
+
+impl<'de> de::Deserializer<'de> for CollectedValues<'de, '_, '_> {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_seq(self)
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_some(self)
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str
+        string bytes byte_buf unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+impl<'de> de::SeqAccess<'de> for CollectedValues<'de, '_, '_> {
+    type Error = Error;
+
+    fn next_element_seed<T>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        let mut pos = match self.values.next() {
+            Some(pos) => pos,
+            None => return Ok(None),
+        };
+        let mut element_de = DeserializerFromEvents {
+            document: self.document,
+            config: self.config,
+            pos: &mut pos,
+            jumpcount: self.jumpcount,
+            path: Path::Seq {
+                parent: &self.path,
+                index: self.len,
+            },
+            remaining_depth: self.remaining_depth,
+            current_enum: None,
+        };
+        self.len += 1;
+        seed.deserialize(&mut element_de).map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.values.len())
+    }
+}
+
 struct EnumAccess<'de, 'document, 'variant> {
     de: &'variant mut DeserializerFromEvents<'de, 'document>,
     name: Option<&'static str>,
@@ -985,6 +2094,7 @@ impl<'de, 'variant> de::EnumAccess<'de>
         let variant = seed.deserialize(str_de)?;
         let visitor = DeserializerFromEvents {
             document: self.de.document,
+            config: self.de.config,
             pos: self.de.pos,
             jumpcount: self.de.jumpcount,
             path: self.de.path,
@@ -1037,6 +2147,48 @@ impl<'de> de::VariantAccess<'de> for DeserializerFromEvents<'de, '_> {
     }
 }
 
+struct SingleEntryEnumAccess<'de, 'document, 'variant> {
+    de: &'variant mut DeserializerFromEvents<'de, 'document>,
+}
+
+impl<'de, 'variant> de::EnumAccess<'de>
+    for SingleEntryEnumAccess<'de, '_, 'variant>
+{
+    type Error = Error;
+    type Variant = DeserializerFromEvents<'de, 'variant>;
+
+    fn variant_seed<V>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Self::Variant)>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let key = match self.de.peek_event()? {
+            Event::Scalar(scalar) => str::from_utf8(&scalar.value).ok(),
+            _ => None,
+        };
+        let variant = seed.deserialize(&mut *self.de)?;
+        let de = self.de;
+        let visitor = DeserializerFromEvents {
+            document: de.document,
+            config: de.config,
+            pos: de.pos,
+            jumpcount: de.jumpcount,
+            path: match key {
+                Some(key) => Path::Map {
+                    parent: &de.path,
+                    key,
+                },
+                None => Path::Unknown { parent: &de.path },
+            },
+            remaining_depth: de.remaining_depth,
+            current_enum: None,
+        };
+        Ok((variant, visitor))
+    }
+}
+
 struct UnitVariantAccess<'de, 'document, 'variant> {
     de: &'variant mut DeserializerFromEvents<'de, 'document>,
 }
@@ -1106,6 +2258,7 @@ fn visit_scalar<'de, V>(
     visitor: V,
     scalar: &Scalar<'de>,
     tagged_already: bool,
+    resolution: Resolution,
 ) -> Result<V::Value>
 where
     V: Visitor<'de>,
@@ -1121,7 +2274,7 @@ where
     };
     if let (Some(tag), false) = (&scalar.tag, tagged_already) {
         if tag == Tag::BOOL {
-            return match parse_bool(v) {
+            return match parse_bool(v, resolution) {
                 Some(v) => visitor.visit_bool(v),
                 None => Err(de::Error::invalid_value(
                     Unexpected::Str(v),
@@ -1129,7 +2282,7 @@ where
                 )),
             };
         } else if tag == Tag::INT {
-            return match visit_int(visitor, v) {
+            return match visit_int(visitor, v, resolution) {
                 Ok(result) => result,
                 Err(_) => Err(de::Error::invalid_value(
                     Unexpected::Str(v),
@@ -1137,7 +2290,7 @@ where
                 )),
             };
         } else if tag == Tag::FLOAT {
-            return match parse_f64(v) {
+            return match parse_f64(v, resolution) {
                 Some(v) => visitor.visit_f64(v),
                 None => Err(de::Error::invalid_value(
                     Unexpected::Str(v),
@@ -1145,13 +2298,15 @@ where
                 )),
             };
         } else if tag == Tag::NULL {
-            return match parse_null(v.as_bytes()) {
+            return match parse_null(v.as_bytes(), resolution) {
                 Some(()) => visitor.visit_unit(),
                 None => Err(de::Error::invalid_value(
                     Unexpected::Str(v),
                     &"null",
                 )),
             };
+        } else if tag == Tag::BINARY {
+            return visit_binary(visitor, v);
         } else if tag.starts_with("!")
             && scalar.style == ScalarStyle::Plain
         {
@@ -1160,6 +2315,7 @@ where
                 v,
                 scalar.repr,
                 scalar.style,
+                resolution,
             );
         }
     } else if scalar.style == ScalarStyle::Plain {
@@ -1168,6 +2324,7 @@ where
             v,
             scalar.repr,
             scalar.style,
+            resolution,
         );
     }
     if let Some(borrowed) =
@@ -1203,17 +2360,35 @@ fn parse_borrowed_str<'de>(
     None
 }
 
-fn parse_null(scalar: &[u8]) -> Option<()> {
+fn parse_null(scalar: &[u8], resolution: Resolution) -> Option<()> {
     match scalar {
-        b"null" | b"Null" | b"NULL" | b"~" => Some(()),
+        b"null" => Some(()),
+        b"Null" | b"NULL" | b"~" if resolution != Resolution::Json => {
+            Some(())
+        }
         _ => None,
     }
 }
 
-fn parse_bool(scalar: &str) -> Option<bool> {
+fn parse_bool(scalar: &str, resolution: Resolution) -> Option<bool> {
     match scalar {
-        "true" | "True" | "TRUE" => Some(true),
-        "false" | "False" | "FALSE" => Some(false),
+        "true" => Some(true),
+        "false" => Some(false),
+        "True" | "TRUE" if resolution != Resolution::Json => Some(true),
+        "False" | "FALSE" if resolution != Resolution::Json => {
+            Some(false)
+        }
+        // As in PyYAML, `y` and `n` stay strings.
+        "yes" | "Yes" | "YES" | "on" | "On" | "ON"
+            if resolution == Resolution::Yaml11 =>
+        {
+            Some(true)
+        }
+        "no" | "No" | "NO" | "off" | "Off" | "OFF"
+            if resolution == Resolution::Yaml11 =>
+        {
+            Some(false)
+        }
         _ => None,
     }
 }
@@ -1221,7 +2396,22 @@ fn parse_bool(scalar: &str) -> Option<bool> {
 fn parse_unsigned_int<T>(
     scalar: &str,
     from_str_radix: fn(&str, radix: u32) -> Result<T, ParseIntError>,
+    resolution: Resolution,
 ) -> Option<T> {
+    if resolution == Resolution::Yaml11 {
+        return match parse_yaml11_int(scalar)? {
+            (false, digits, radix) => {
+                from_str_radix(&digits, radix).ok()
+            }
+            (true, _, _) => None,
+        };
+    }
+    if resolution == Resolution::Json {
+        if !is_json_int(scalar) || scalar.starts_with('-') {
+            return None;
+        }
+        return from_str_radix(scalar, 10).ok();
+    }
     let unpositive = scalar.strip_prefix('+').unwrap_or(scalar);
     if let Some(rest) = unpositive.strip_prefix("0x") {
         if rest.starts_with(['+', '-']) {
@@ -1259,7 +2449,24 @@ fn parse_unsigned_int<T>(
 fn parse_signed_int<T>(
     scalar: &str,
     from_str_radix: fn(&str, radix: u32) -> Result<T, ParseIntError>,
+    resolution: Resolution,
 ) -> Option<T> {
+    if resolution == Resolution::Yaml11 {
+        return match parse_yaml11_int(scalar)? {
+            (false, digits, radix) => {
+                from_str_radix(&digits, radix).ok()
+            }
+            (true, digits, radix) => {
+                from_str_radix(&format!("-{}", digits), radix).ok()
+            }
+        };
+    }
+    if resolution == Resolution::Json {
+        if !is_json_int(scalar) {
+            return None;
+        }
+        return from_str_radix(scalar, 10).ok();
+    }
     let unpositive = if let Some(unpositive) = scalar.strip_prefix('+')
     {
         if unpositive.starts_with(['+', '-']) {
@@ -1320,7 +2527,22 @@ fn parse_signed_int<T>(
 fn parse_negative_int<T>(
     scalar: &str,
     from_str_radix: fn(&str, radix: u32) -> Result<T, ParseIntError>,
+    resolution: Resolution,
 ) -> Option<T> {
+    if resolution == Resolution::Yaml11 {
+        return match parse_yaml11_int(scalar)? {
+            (true, digits, radix) => {
+                from_str_radix(&format!("-{}", digits), radix).ok()
+            }
+            (false, _, _) => None,
+        };
+    }
+    if resolution == Resolution::Json {
+        if !is_json_int(scalar) || !scalar.starts_with('-') {
+            return None;
+        }
+        return from_str_radix(scalar, 10).ok();
+    }
     if let Some(rest) = scalar.strip_prefix("-0x") {
         let negative = format!("-{}", rest);
         if let Ok(int) = from_str_radix(&negative, 16) {
@@ -1345,7 +2567,19 @@ fn parse_negative_int<T>(
     from_str_radix(scalar, 10).ok()
 }
 
-pub(crate) fn parse_f64(scalar: &str) -> Option<f64> {
+pub(crate) fn parse_f64(
+    scalar: &str,
+    resolution: Resolution,
+) -> Option<f64> {
+    if resolution == Resolution::Yaml11 {
+        return parse_yaml11_f64(scalar);
+    }
+    if resolution == Resolution::Json {
+        if !is_json_float(scalar) {
+            return None;
+        }
+        return scalar.parse::<f64>().ok().filter(|f| f.is_finite());
+    }
     let unpositive = if let Some(unpositive) = scalar.strip_prefix('+')
     {
         if unpositive.starts_with(['+', '-']) {
@@ -1372,6 +2606,164 @@ pub(crate) fn parse_f64(scalar: &str) -> Option<f64> {
     None
 }
 
+/// Whether `scalar` is an integer in JSON syntax: an optional `-` and
+/// digits without leading zeros.
+fn is_json_int(scalar: &str) -> bool {
+    let digits = scalar.strip_prefix('-').unwrap_or(scalar);
+    match digits.as_bytes() {
+        [b'0'] => true,
+        [b'1'..=b'9', rest @ ..] => rest.iter().all(u8::is_ascii_digit),
+        _ => false,
+    }
+}
+
+/// Whether `scalar` is a number in JSON syntax: an integer, followed by
+/// an optional fraction and an optional exponent.
+fn is_json_float(scalar: &str) -> bool {
+    let all_digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+    let mantissa = match scalar.find(['e', 'E']) {
+        Some(e) => {
+            let exponent = &scalar[e + 1..];
+            let digits =
+                exponent.strip_prefix(['+', '-']).unwrap_or(exponent);
+            if digits.is_empty() || !all_digits(digits) {
+                return false;
+            }
+            &scalar[..e]
+        }
+        None => scalar,
+    };
+    match mantissa.find('.') {
+        Some(dot) => {
+            is_json_int(&mantissa[..dot])
+                && all_digits(&mantissa[dot + 1..])
+        }
+        None => is_json_int(mantissa),
+    }
+}
+
+/// Splits an integer written with the YAML 1.1 rules into whether it is
+/// negative, its digits without underscores and their radix. A
+/// sexagesimal integer such as `1:30` is converted to base 10.
+fn parse_yaml11_int(scalar: &str) -> Option<(bool, String, u32)> {
+    let (negative, unsigned) = match scalar.as_bytes().first()? {
+        b'-' => (true, &scalar[1..]),
+        b'+' => (false, &scalar[1..]),
+        _ => (false, scalar),
+    };
+    let (rest, radix) = if let Some(rest) = unsigned.strip_prefix("0b")
+    {
+        (rest, 2)
+    } else if let Some(rest) = unsigned.strip_prefix("0x") {
+        (rest, 16)
+    } else if unsigned.len() > 1 && unsigned.starts_with('0') {
+        (&unsigned[1..], 8)
+    } else if unsigned.contains(':') {
+        let int = parse_sexagesimal(unsigned)?;
+        return Some((negative, int.to_string(), 10));
+    } else if unsigned.starts_with(|c: char| c.is_ascii_digit()) {
+        (unsigned, 10)
+    } else {
+        return None;
+    };
+    let digits: String = rest.chars().filter(|&c| c != '_').collect();
+    if digits.is_empty() || !digits.chars().all(|c| c.is_digit(radix)) {
+        return None;
+    }
+    Some((negative, digits, radix))
+}
+
+/// Parses a YAML 1.1 float, which needs a `.` unless it is sexagesimal
+/// and may contain underscores.
+fn parse_yaml11_f64(scalar: &str) -> Option<f64> {
+    if let ".nan" | ".NaN" | ".NAN" = scalar {
+        return Some(f64::NAN.copysign(1.0));
+    }
+    let (negative, unsigned) = match scalar.as_bytes().first()? {
+        b'-' => (true, &scalar[1..]),
+        b'+' => (false, &scalar[1..]),
+        _ => (false, scalar),
+    };
+    let float = if let ".inf" | ".Inf" | ".INF" = unsigned {
+        f64::INFINITY
+    } else if unsigned.contains(':') {
+        let (int, fraction) = unsigned.split_once('.')?;
+        let fraction: String =
+            fraction.chars().filter(|&c| c != '_').collect();
+        if !fraction.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        let fraction = format!("0.{}", fraction).parse::<f64>().ok()?;
+        parse_sexagesimal(int)? as f64 + fraction
+    } else {
+        if unsigned.starts_with('_') {
+            return None;
+        }
+        let float: String =
+            unsigned.chars().filter(|&c| c != '_').collect();
+        let (mantissa, exponent) = match float.find(['e', 'E']) {
+            Some(e) => (&float[..e], Some(&float[e + 1..])),
+            None => (&float[..], None),
+        };
+        let (int, fraction) = mantissa.split_once('.')?;
+        let exponent = exponent.map(|exponent| {
+            exponent.strip_prefix(['+', '-']).unwrap_or(exponent)
+        });
+        if int.is_empty() && fraction.is_empty()
+            || !int.bytes().all(|b| b.is_ascii_digit())
+            || !fraction.bytes().all(|b| b.is_ascii_digit())
+            || exponent.map_or(false, |exponent| {
+                exponent.is_empty()
+                    || !exponent.bytes().all(|b| b.is_ascii_digit())
+            })
+        {
+            return None;
+        }
+        let float = float.parse::<f64>().ok()?;
+        if !float.is_finite() {
+            return None;
+        }
+        float
+    };
+    Some(if negative { -float } else { float })
+}
+
+/// Parses the part of a YAML 1.1 sexagesimal number before any `.`, such
+/// as `1:30:00`, whose first component may contain underscores and whose
+/// other components are below 60.
+fn parse_sexagesimal(scalar: &str) -> Option<u128> {
+    let mut components = scalar.split(':');
+    let first = components.next()?;
+    if !first.starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+    let first: String = first.chars().filter(|&c| c != '_').collect();
+    if !first.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let mut value: u128 = first.parse().ok()?;
+    let mut any = false;
+    for component in components {
+        if component.is_empty()
+            || component.len() > 2
+            || !component.bytes().all(|b| b.is_ascii_digit())
+        {
+            return None;
+        }
+        let component: u128 = component.parse().ok()?;
+        if component >= 60 {
+            return None;
+        }
+        value = value.checked_mul(60)?.checked_add(component)?;
+        any = true;
+    }
+    if any {
+        Some(value)
+    } else {
+        None
+    }
+}
+
 pub(crate) fn digits_but_not_number(scalar: &str) -> bool {
     // Leading zero(s) followed by numeric characters is a string according to
     // the YAML 1.2 spec. https://yaml.org/spec/1.2/spec.html#id2761292
@@ -1390,8 +2782,9 @@ pub(crate) fn digits_but_not_number(scalar: &str) -> bool {
 #[allow(clippy::bytes_nth)]
 pub(crate) fn ambiguous_string(scalar: &str) -> bool {
     let lower_scalar = scalar.to_lowercase();
-    parse_bool(&lower_scalar).is_some()
-        || parse_null(&lower_scalar.as_bytes()).is_some()
+    parse_bool(&lower_scalar, Resolution::Core).is_some()
+        || parse_null(&lower_scalar.as_bytes(), Resolution::Core)
+            .is_some()
         || lower_scalar.len() == 0
         // Can unwrap because we just checked the length.
         || lower_scalar.bytes().nth(0).unwrap().is_ascii_digit()
@@ -1417,46 +2810,78 @@ pub(crate) fn ambiguous_string(scalar: &str) -> bool {
 pub(crate) fn visit_int<'de, V>(
     visitor: V,
     v: &str,
+    resolution: Resolution,
 ) -> Result<Result<V::Value>, V>
 where
     V: Visitor<'de>,
 {
-    if let Some(int) = parse_unsigned_int(v, u64::from_str_radix) {
+    if let Some(int) =
+        parse_unsigned_int(v, u64::from_str_radix, resolution)
+    {
         return Ok(visitor.visit_u64(int));
     }
-    if let Some(int) = parse_negative_int(v, i64::from_str_radix) {
+    if let Some(int) =
+        parse_negative_int(v, i64::from_str_radix, resolution)
+    {
         return Ok(visitor.visit_i64(int));
     }
-    if let Some(int) = parse_unsigned_int(v, u128::from_str_radix) {
+    if let Some(int) =
+        parse_unsigned_int(v, u128::from_str_radix, resolution)
+    {
         return Ok(visitor.visit_u128(int));
     }
-    if let Some(int) = parse_negative_int(v, i128::from_str_radix) {
+    if let Some(int) =
+        parse_negative_int(v, i128::from_str_radix, resolution)
+    {
         return Ok(visitor.visit_i128(int));
     }
     Err(visitor)
 }
 
+/// Decodes the base64 content of a `!!binary` scalar.
+fn visit_binary<'de, V>(visitor: V, v: &str) -> Result<V::Value>
+where
+    V: Visitor<'de>,
+{
+    match base64::decode(v.as_bytes()) {
+        Some(bytes) => visitor.visit_byte_buf(bytes),
+        None => Err(de::Error::invalid_value(
+            Unexpected::Str(v),
+            &"base64-encoded binary data",
+        )),
+    }
+}
+
 pub(crate) fn visit_untagged_scalar<'de, V>(
     visitor: V,
     v: &str,
     repr: Option<&'de [u8]>,
     style: ScalarStyle,
+    resolution: Resolution,
 ) -> Result<V::Value>
 where
     V: Visitor<'de>,
 {
-    if v.is_empty() || parse_null(v.as_bytes()) == Some(()) {
+    if resolution == Resolution::Failsafe {
+        if let Some(borrowed) = parse_borrowed_str(v, repr, style) {
+            return visitor.visit_borrowed_str(borrowed);
+        }
+        return visitor.visit_str(v);
+    }
+    if (v.is_empty() && resolution != Resolution::Json)
+        || parse_null(v.as_bytes(), resolution) == Some(())
+    {
         return visitor.visit_unit();
     }
-    if let Some(boolean) = parse_bool(v) {
+    if let Some(boolean) = parse_bool(v, resolution) {
         return visitor.visit_bool(boolean);
     }
-    let visitor = match visit_int(visitor, v) {
+    let visitor = match visit_int(visitor, v, resolution) {
         Ok(result) => return result,
         Err(visitor) => visitor,
     };
-    if !digits_but_not_number(v) {
-        if let Some(float) = parse_f64(v) {
+    if resolution == Resolution::Yaml11 || !digits_but_not_number(v) {
+        if let Some(float) = parse_f64(v, resolution) {
             return visitor.visit_f64(float);
         }
     }
@@ -1474,12 +2899,18 @@ fn is_plain_or_tagged_literal_scalar(
 ) -> bool {
     match (scalar.style, &scalar.tag, tagged_already) {
         (ScalarStyle::Plain, _, _) => true,
-        (ScalarStyle::Literal, Some(tag), false) => tag == expected,
+        // An explicit tag decides the type whatever the style, as in
+        // canonical output where every scalar is double quoted.
+        (_, Some(tag), false) => tag == expected,
         _ => false,
     }
 }
 
-fn invalid_type(event: &Event<'_>, exp: &dyn Expected) -> Error {
+fn invalid_type(
+    event: &Event<'_>,
+    exp: &dyn Expected,
+    resolution: Resolution,
+) -> Error {
     enum Void {}
 
     struct InvalidType<'a> {
@@ -1501,7 +2932,7 @@ fn invalid_type(event: &Event<'_>, exp: &dyn Expected) -> Error {
         Event::Alias(_) => unreachable!(),
         Event::Scalar(scalar) => {
             let get_type = InvalidType { exp };
-            match visit_scalar(get_type, scalar, false) {
+            match visit_scalar(get_type, scalar, false, resolution) {
                 Ok(void) => match void {},
                 Err(invalid_type) => invalid_type,
             }
@@ -1518,6 +2949,38 @@ fn invalid_type(event: &Event<'_>, exp: &dyn Expected) -> Error {
     }
 }
 
+/// Returns the tag of `event` unless it is one of the YAML core tags or
+/// the non-specific `!` tag.
+fn unknown_tag<'a>(event: &'a Event<'_>) -> Option<&'a Tag> {
+    let tag = match event {
+        Event::Scalar(scalar) => scalar.tag.as_ref(),
+        Event::SequenceStart(sequence) => sequence.tag.as_ref(),
+        Event::MappingStart(mapping) => mapping.tag.as_ref(),
+        _ => None,
+    }?;
+    let known = [
+        "!",
+        Tag::NULL,
+        Tag::BOOL,
+        Tag::INT,
+        Tag::FLOAT,
+        Tag::BINARY,
+        Tag::STR,
+        Tag::SEQ,
+        Tag::MAP,
+    ];
+    if known.iter().any(|known| tag == *known) {
+        None
+    } else {
+        Some(tag)
+    }
+}
+
+/// Returns the tag of a node, without the leading `!` of a local tag.
+///
+/// A global tag, such as one written through a `%TAG` handle, is returned
+/// in full. Tags of the YAML core schema, such as `!!str`, are not
+/// returned, since they only resolve the type of the node.
 fn parse_tag(libyml_tag: &Option<Tag>) -> Option<&str> {
     let mut bytes: &[u8] = libyml_tag.as_ref()?;
     if let (b'!', rest) = bytes.split_first()? {
@@ -1525,8 +2988,10 @@ fn parse_tag(libyml_tag: &Option<Tag>) -> Option<&str> {
             bytes = rest;
         }
         str::from_utf8(bytes).ok()
-    } else {
+    } else if bytes.starts_with(b"tag:yaml.org,2002:") {
         None
+    } else {
+        str::from_utf8(bytes).ok()
     }
 }
 
@@ -1567,10 +3032,26 @@ impl<'de> de::Deserializer<'de>
                             tag,
                         });
                     }
+                    if self.config.preserve_scalars
+                        && self.resolution() == Resolution::Core
+                        && scalar.tag.is_none()
+                        && !tagged_already
+                    {
+                        if let Ok(v) = str::from_utf8(&scalar.value) {
+                            let contents =
+                                styled::contents(scalar.style, v);
+                            break visitor.visit_newtype_struct(
+                                SeqDeserializer::new(
+                                    contents.into_iter(),
+                                ),
+                            );
+                        }
+                    }
                     break visit_scalar(
                         visitor,
                         scalar,
                         tagged_already,
+                        self.resolution(),
                     );
                 }
                 Event::SequenceStart(sequence) => {
@@ -1597,7 +3078,7 @@ impl<'de> de::Deserializer<'de>
                             tag,
                         });
                     }
-                    break self.visit_mapping(visitor, mark);
+                    break self.visit_mapping(visitor, mark, false);
                 }
                 Event::SequenceEnd => {
                     panic!("unexpected end of sequence")
@@ -1617,6 +3098,11 @@ impl<'de> de::Deserializer<'de>
     where
         V: Visitor<'de>,
     {
+        if let Some((mut pos, end)) = self.default_value() {
+            return self
+                .default_value_de(&mut pos, end)
+                .deserialize_bool(visitor);
+        }
         let tagged_already = self.current_enum.is_some();
         let (next, mark) = self.next_event_mark()?;
         #[allow(clippy::never_loop)]
@@ -1635,14 +3121,16 @@ impl<'de> de::Deserializer<'de>
                     ) =>
                 {
                     if let Ok(value) = str::from_utf8(&scalar.value) {
-                        if let Some(boolean) = parse_bool(value) {
+                        if let Some(boolean) =
+                            parse_bool(value, self.resolution())
+                        {
                             break visitor.visit_bool(boolean);
                         }
                     }
                 }
                 _ => {}
             }
-            break Err(invalid_type(next, &visitor));
+            break Err(invalid_type(next, &visitor, self.resolution()));
         }
         .map_err(|err| error::fix_mark(err, mark, self.path))
     }
@@ -1672,6 +3160,11 @@ impl<'de> de::Deserializer<'de>
     where
         V: Visitor<'de>,
     {
+        if let Some((mut pos, end)) = self.default_value() {
+            return self
+                .default_value_de(&mut pos, end)
+                .deserialize_i64(visitor);
+        }
         let tagged_already = self.current_enum.is_some();
         let (next, mark) = self.next_event_mark()?;
         #[allow(clippy::never_loop)]
@@ -1688,16 +3181,18 @@ impl<'de> de::Deserializer<'de>
                     ) =>
                 {
                     if let Ok(value) = str::from_utf8(&scalar.value) {
-                        if let Some(int) =
-                            parse_signed_int(value, i64::from_str_radix)
-                        {
+                        if let Some(int) = parse_signed_int(
+                            value,
+                            i64::from_str_radix,
+                            self.resolution(),
+                        ) {
                             break visitor.visit_i64(int);
                         }
                     }
                 }
                 _ => {}
             }
-            break Err(invalid_type(next, &visitor));
+            break Err(invalid_type(next, &visitor, self.resolution()));
         }
         .map_err(|err| error::fix_mark(err, mark, self.path))
     }
@@ -1706,6 +3201,11 @@ impl<'de> de::Deserializer<'de>
     where
         V: Visitor<'de>,
     {
+        if let Some((mut pos, end)) = self.default_value() {
+            return self
+                .default_value_de(&mut pos, end)
+                .deserialize_i128(visitor);
+        }
         let tagged_already = self.current_enum.is_some();
         let (next, mark) = self.next_event_mark()?;
         #[allow(clippy::never_loop)]
@@ -1727,6 +3227,7 @@ impl<'de> de::Deserializer<'de>
                         if let Some(int) = parse_signed_int(
                             value,
                             i128::from_str_radix,
+                            self.resolution(),
                         ) {
                             break visitor.visit_i128(int);
                         }
@@ -1734,7 +3235,7 @@ impl<'de> de::Deserializer<'de>
                 }
                 _ => {}
             }
-            break Err(invalid_type(next, &visitor));
+            break Err(invalid_type(next, &visitor, self.resolution()));
         }
         .map_err(|err| error::fix_mark(err, mark, self.path))
     }
@@ -1764,6 +3265,11 @@ impl<'de> de::Deserializer<'de>
     where
         V: Visitor<'de>,
     {
+        if let Some((mut pos, end)) = self.default_value() {
+            return self
+                .default_value_de(&mut pos, end)
+                .deserialize_u64(visitor);
+        }
         let tagged_already = self.current_enum.is_some();
         let (next, mark) = self.next_event_mark()?;
         #[allow(clippy::never_loop)]
@@ -1783,6 +3289,7 @@ impl<'de> de::Deserializer<'de>
                         if let Some(int) = parse_unsigned_int(
                             value,
                             u64::from_str_radix,
+                            self.resolution(),
                         ) {
                             break visitor.visit_u64(int);
                         }
@@ -1790,7 +3297,7 @@ impl<'de> de::Deserializer<'de>
                 }
                 _ => {}
             }
-            break Err(invalid_type(next, &visitor));
+            break Err(invalid_type(next, &visitor, self.resolution()));
         }
         .map_err(|err| error::fix_mark(err, mark, self.path))
     }
@@ -1799,6 +3306,11 @@ impl<'de> de::Deserializer<'de>
     where
         V: Visitor<'de>,
     {
+        if let Some((mut pos, end)) = self.default_value() {
+            return self
+                .default_value_de(&mut pos, end)
+                .deserialize_u128(visitor);
+        }
         let tagged_already = self.current_enum.is_some();
         let (next, mark) = self.next_event_mark()?;
         #[allow(clippy::never_loop)]
@@ -1820,6 +3332,7 @@ impl<'de> de::Deserializer<'de>
                         if let Some(int) = parse_unsigned_int(
                             value,
                             u128::from_str_radix,
+                            self.resolution(),
                         ) {
                             break visitor.visit_u128(int);
                         }
@@ -1827,7 +3340,7 @@ impl<'de> de::Deserializer<'de>
                 }
                 _ => {}
             }
-            break Err(invalid_type(next, &visitor));
+            break Err(invalid_type(next, &visitor, self.resolution()));
         }
         .map_err(|err| error::fix_mark(err, mark, self.path))
     }
@@ -1843,6 +3356,11 @@ impl<'de> de::Deserializer<'de>
     where
         V: Visitor<'de>,
     {
+        if let Some((mut pos, end)) = self.default_value() {
+            return self
+                .default_value_de(&mut pos, end)
+                .deserialize_f64(visitor);
+        }
         let tagged_already = self.current_enum.is_some();
         let (next, mark) = self.next_event_mark()?;
         #[allow(clippy::never_loop)]
@@ -1859,14 +3377,16 @@ impl<'de> de::Deserializer<'de>
                     ) =>
                 {
                     if let Ok(value) = str::from_utf8(&scalar.value) {
-                        if let Some(float) = parse_f64(value) {
+                        if let Some(float) =
+                            parse_f64(value, self.resolution())
+                        {
                             break visitor.visit_f64(float);
                         }
                     }
                 }
                 _ => {}
             }
-            break Err(invalid_type(next, &visitor));
+            break Err(invalid_type(next, &visitor, self.resolution()));
         }
         .map_err(|err| error::fix_mark(err, mark, self.path))
     }
@@ -1875,111 +3395,1160 @@ impl<'de> de::Deserializer<'de>
     where
         V: Visitor<'de>,
     {
-        self.deserialize_str(visitor)
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_str<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        if let Some((mut pos, end)) = self.default_value() {
+            return self
+                .default_value_de(&mut pos, end)
+                .deserialize_str(visitor);
+        }
+        let (next, mark) = self.next_event_mark()?;
+        match next {
+            Event::Scalar(scalar) => {
+                if let Ok(v) = str::from_utf8(&scalar.value) {
+                    if let Some(borrowed) =
+                        parse_borrowed_str(v, scalar.repr, scalar.style)
+                    {
+                        visitor.visit_borrowed_str(borrowed)
+                    } else {
+                        visitor.visit_str(v)
+                    }
+                } else {
+                    Err(invalid_type(next, &visitor, self.resolution()))
+                }
+            }
+            Event::Alias(mut pos) => {
+                self.jump(&mut pos)?.deserialize_str(visitor)
+            }
+            other => {
+                Err(invalid_type(other, &visitor, self.resolution()))
+            }
+        }
+        .map_err(|err: Error| error::fix_mark(err, mark, self.path))
+    }
+
+    fn deserialize_string<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_str(visitor)
+    }
+
+    /// Decodes `!!binary` scalars, and scalars whose tag selected an enum
+    /// variant, from base64. Sequences are passed to the visitor as
+    /// sequences of bytes.
+    fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        let tagged_already = self.current_enum.is_some();
+        let (next, mark) = self.next_event_mark()?;
+        match next {
+            Event::Scalar(scalar)
+                if tagged_already
+                    || scalar
+                        .tag
+                        .as_ref()
+                        .map_or(false, |tag| tag == Tag::BINARY) =>
+            {
+                match str::from_utf8(&scalar.value) {
+                    Ok(v) => visit_binary(visitor, v),
+                    Err(_) => Err(invalid_type(
+                        next,
+                        &visitor,
+                        self.resolution(),
+                    )),
+                }
+            }
+            Event::Alias(mut pos) => {
+                self.jump(&mut pos)?.deserialize_bytes(visitor)
+            }
+            Event::SequenceStart(_) => {
+                self.visit_sequence(visitor, mark)
+            }
+            other => {
+                Err(invalid_type(other, &visitor, self.resolution()))
+            }
+        }
+        .map_err(|err: Error| error::fix_mark(err, mark, self.path))
+    }
+
+    fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_bytes(visitor)
+    }
+
+    /// Parses `null` as None and any other values as `Some(...)`.
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        let resolution = self.resolution();
+        let is_some = match self.peek_event()? {
+            Event::Alias(mut pos) => {
+                *self.pos += 1;
+                return self
+                    .jump(&mut pos)?
+                    .deserialize_option(visitor);
+            }
+            Event::Scalar(scalar) => {
+                let tagged_already = self.current_enum.is_some();
+                if let (Some(tag), false) =
+                    (&scalar.tag, tagged_already)
+                {
+                    if tag == Tag::NULL {
+                        if let Some(()) =
+                            parse_null(&scalar.value, resolution)
+                        {
+                            false
+                        } else if let Ok(v) =
+                            str::from_utf8(&scalar.value)
+                        {
+                            return Err(de::Error::invalid_value(
+                                Unexpected::Str(v),
+                                &"null",
+                            ));
+                        } else {
+                            return Err(de::Error::invalid_value(
+                                Unexpected::Bytes(&scalar.value),
+                                &"null",
+                            ));
+                        }
+                    } else {
+                        true
+                    }
+                } else if scalar.style != ScalarStyle::Plain {
+                    true
+                } else {
+                    !scalar.value.is_empty()
+                        && parse_null(&scalar.value, resolution)
+                            .is_none()
+                }
+            }
+            Event::SequenceStart(_) | Event::MappingStart(_) => true,
+            Event::SequenceEnd => panic!("unexpected end of sequence"),
+            Event::MappingEnd => panic!("unexpected end of mapping"),
+            Event::Void => false,
+        };
+        if is_some {
+            visitor.visit_some(self)
+        } else {
+            *self.pos += 1;
+            self.current_enum = None;
+            visitor.visit_none()
+        }
+    }
+
+    fn deserialize_unit<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        let resolution = self.resolution();
+        let tagged_already = self.current_enum.is_some();
+        let (next, mark) = self.next_event_mark()?;
+        match next {
+            Event::Scalar(scalar) => {
+                let is_null = if let (Some(tag), false) =
+                    (&scalar.tag, tagged_already)
+                {
+                    tag == Tag::NULL
+                        && parse_null(&scalar.value, resolution)
+                            .is_some()
+                } else if scalar.style != ScalarStyle::Plain {
+                    false
+                } else {
+                    scalar.value.is_empty()
+                        || parse_null(&scalar.value, resolution)
+                            .is_some()
+                };
+                if is_null {
+                    visitor.visit_unit()
+                } else if let Ok(v) = str::from_utf8(&scalar.value) {
+                    Err(de::Error::invalid_value(
+                        Unexpected::Str(v),
+                        &"null",
+                    ))
+                } else {
+                    Err(de::Error::invalid_value(
+                        Unexpected::Bytes(&scalar.value),
+                        &"null",
+                    ))
+                }
+            }
+            Event::Alias(mut pos) => {
+                self.jump(&mut pos)?.deserialize_unit(visitor)
+            }
+            Event::Void => visitor.visit_unit(),
+            other => {
+                Err(invalid_type(other, &visitor, self.resolution()))
+            }
+        }
+        .map_err(|err| error::fix_mark(err, mark, self.path))
+    }
+
+    fn deserialize_unit_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_unit(visitor)
+    }
+
+    /// Parses a newtype struct as the underlying value.
+    fn deserialize_newtype_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        let (_event, mark) = self.peek_event_mark()?;
+        self.recursion_check(mark, |de| {
+            visitor.visit_newtype_struct(de)
+        })
+    }
+
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        let (next, mark) = self.next_event_mark()?;
+        match next {
+            Event::Alias(mut pos) => {
+                self.jump(&mut pos)?.deserialize_seq(visitor)
+            }
+            Event::SequenceStart(_) => {
+                self.visit_sequence(visitor, mark)
+            }
+            other => {
+                if match other {
+                    Event::Void => true,
+                    Event::Scalar(scalar) => {
+                        scalar.value.is_empty()
+                            && scalar.style == ScalarStyle::Plain
+                    }
+                    _ => false,
+                } {
+                    visitor.visit_seq(SeqAccess {
+                        empty: true,
+                        de: self,
+                        len: 0,
+                    })
+                } else {
+                    Err(invalid_type(
+                        other,
+                        &visitor,
+                        self.resolution(),
+                    ))
+                }
+            }
+        }
+        .map_err(|err| error::fix_mark(err, mark, self.path))
+    }
+
+    fn deserialize_tuple<V>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        let (next, mark) = self.next_event_mark()?;
+        match next {
+            Event::Alias(mut pos) => {
+                self.jump(&mut pos)?.deserialize_map(visitor)
+            }
+            Event::MappingStart(_) => {
+                self.visit_mapping(visitor, mark, true)
+            }
+            other => {
+                if match other {
+                    Event::Void => true,
+                    Event::Scalar(scalar) => {
+                        scalar.value.is_empty()
+                            && scalar.style == ScalarStyle::Plain
+                    }
+                    _ => false,
+                } {
+                    visitor.visit_map(MapAccess {
+                        empty: true,
+                        de: self,
+                        len: 0,
+                        key: None,
+                    })
+                } else {
+                    Err(invalid_type(
+                        other,
+                        &visitor,
+                        self.resolution(),
+                    ))
+                }
+            }
+        }
+        .map_err(|err| error::fix_mark(err, mark, self.path))
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_map(visitor)
+    }
+
+    /// Parses an enum as a single key:value pair where the key identifies the
+    /// variant and the value gives the content. A String will also parse correctly
+    /// to a unit enum value.
+    fn deserialize_enum<V>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        let (next, mark) = self.peek_event_mark()?;
+        #[allow(clippy::never_loop)]
+        loop {
+            if let Some(current_enum) = self.current_enum {
+                if let Event::Scalar(scalar) = next {
+                    if !scalar.value.is_empty() {
+                        break visitor.visit_enum(UnitVariantAccess { de: self });
+                    }
+                }
+                if let Event::MappingStart(_) = next {
+                    if self.is_single_entry_mapping() {
+                        break self.visit_single_entry_enum(visitor, mark);
+                    }
+                }
+                let message = if let Some(name) = current_enum.name {
+                    format!(
+                        "deserializing nested enum in {}::{} from YAML is not supported yet",
+                        name, current_enum.tag,
+                    )
+                } else {
+                    format!(
+                        "deserializing nested enum in !{} from YAML is not supported yet",
+                        current_enum.tag,
+                    )
+                };
+                break Err(error::new(ErrorImpl::Message(message, None)));
+            }
+            break match next {
+                Event::Alias(mut pos) => {
+                    *self.pos += 1;
+                    self.jump(&mut pos)?
+                        .deserialize_enum(name, variants, visitor)
+                }
+                Event::Scalar(scalar) => {
+                    if let Some(tag) = parse_tag(&scalar.tag) {
+                        return visitor.visit_enum(EnumAccess {
+                            de: self,
+                            name: Some(name),
+                            tag,
+                        });
+                    }
+                    visitor.visit_enum(UnitVariantAccess { de: self })
+                }
+                Event::MappingStart(mapping) => {
+                    if let Some(tag) = parse_tag(&mapping.tag) {
+                        return visitor.visit_enum(EnumAccess {
+                            de: self,
+                            name: Some(name),
+                            tag,
+                        });
+                    }
+                    if self.is_single_entry_mapping() {
+                        return self.visit_single_entry_enum(visitor, mark);
+                    }
+                    let err =
+                        de::Error::invalid_type(Unexpected::Map, &"a YAML tag starting with '!'");
+                    Err(error::fix_mark(err, mark, self.path))
+                }
+                Event::SequenceStart(sequence) => {
+                    if let Some(tag) = parse_tag(&sequence.tag) {
+                        return visitor.visit_enum(EnumAccess {
+                            de: self,
+                            name: Some(name),
+                            tag,
+                        });
+                    }
+                    let err =
+                        de::Error::invalid_type(Unexpected::Seq, &"a YAML tag starting with '!'");
+                    Err(error::fix_mark(err, mark, self.path))
+                }
+                Event::SequenceEnd => panic!("unexpected end of sequence"),
+                Event::MappingEnd => panic!("unexpected end of mapping"),
+                Event::Void => Err(error::new(ErrorImpl::EndOfStream)),
+            };
+        }
+        .map_err(|err| error::fix_mark(err, mark, self.path))
+    }
+
+    fn deserialize_identifier<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.ignore_any()?;
+        visitor.visit_unit()
+    }
+}
+
+/// The document of a reader, parsed one event at a time as it is
+/// deserialized.
+///
+/// Sequences and mappings are deserialized straight from the events as
+/// they are parsed. Every other node is loaded into `store` first and
+/// deserialized from there by a [`DeserializerFromEvents`], and dropped
+/// from it afterwards unless it has an anchor that an alias may refer to
+/// later.
+struct EventStream<'de> {
+    loader: Loader<'de>,
+    state: DocumentState,
+    peeked: Option<(Event<'de>, Mark, Option<usize>)>,
+    store: Document<'de>,
+    /// The version in the `%YAML` directive of the document, if it has
+    /// one.
+    version: Option<YamlVersion>,
+    jumpcount: usize,
+    started: bool,
+    document_end: bool,
+    stream_end: bool,
+}
+
+impl<'de> EventStream<'de> {
+    fn new(loader: Loader<'de>) -> Self {
+        EventStream {
+            loader,
+            state: DocumentState::default(),
+            peeked: None,
+            store: Document {
+                events: Vec::new(),
+                error: None,
+                anchor_event_map: BTreeMap::new(),
+            },
+            version: None,
+            jumpcount: 0,
+            started: false,
+            document_end: false,
+            stream_end: false,
+        }
+    }
+
+    /// Returns the next event without consuming it, along with the id of
+    /// its anchor.
+    fn peek(&mut self) -> Result<(&Event<'de>, Mark, Option<usize>)> {
+        if self.peeked.is_none() {
+            self.peeked = Some(self.parse_next()?);
+        }
+        match &self.peeked {
+            Some((event, mark, anchor)) => Ok((event, *mark, *anchor)),
+            None => unreachable!(),
+        }
+    }
+
+    fn next(&mut self) -> Result<(Event<'de>, Mark, Option<usize>)> {
+        match self.peeked.take() {
+            Some(next) => Ok(next),
+            None => self.parse_next(),
+        }
+    }
+
+    fn parse_next(
+        &mut self,
+    ) -> Result<(Event<'de>, Mark, Option<usize>)> {
+        loop {
+            let step = match self.loader.next_step(&mut self.state) {
+                Some(step) => step?,
+                None => return Err(error::new(ErrorImpl::EndOfStream)),
+            };
+            match step {
+                Step::DocumentStart(version) => {
+                    self.started = true;
+                    self.version = version;
+                }
+                Step::Event(event, mark, anchor) => {
+                    return Ok((event, mark, anchor));
+                }
+                Step::DocumentEnd => {
+                    self.document_end = true;
+                    return Err(error::new(ErrorImpl::EndOfStream));
+                }
+                Step::StreamEnd(mark) => {
+                    self.stream_end = true;
+                    if self.started {
+                        return Err(error::new(ErrorImpl::EndOfStream));
+                    }
+                    // A stream without documents is read as one empty
+                    // document.
+                    self.started = true;
+                    return Ok((Event::Void, mark, None));
+                }
+            }
+        }
+    }
+
+    /// Loads the events of the next node into the store and returns the
+    /// position of the first one.
+    fn load_node(&mut self) -> Result<usize> {
+        let start = self.store.events.len();
+        let mut depth = 0;
+        loop {
+            let (event, mark, anchor) = self.next()?;
+            if let Some(id) = anchor {
+                self.store
+                    .anchor_event_map
+                    .insert(id, self.store.events.len());
+            }
+            match event {
+                Event::SequenceStart(_) | Event::MappingStart(_) => {
+                    depth += 1;
+                }
+                Event::SequenceEnd | Event::MappingEnd => depth -= 1,
+                Event::Void => depth = 0,
+                Event::Alias(_) | Event::Scalar(_) => {}
+            }
+            self.store.events.push((event, mark));
+            if depth == 0 {
+                return Ok(start);
+            }
+        }
+    }
+
+    /// Checks that the document that has been deserialized is the only
+    /// one in the stream.
+    fn finish(&mut self) -> Result<()> {
+        // What is left of the root node, such as when a `visit_some`
+        // ignores its deserializer, is skipped as it would be in a loaded
+        // document, but errors in it still fail the input.
+        self.peeked = None;
+        while !self.document_end && !self.stream_end {
+            if let Err(err) = self.parse_next() {
+                if !self.document_end && !self.stream_end {
+                    return Err(err);
+                }
+            }
+        }
+        if self.stream_end {
+            return Ok(());
+        }
+        match self.loader.next_step(&mut DocumentState::default()) {
+            Some(Ok(Step::StreamEnd(_))) | None => Ok(()),
+            // The rest of the input is read to find out whether there is
+            // another document, so failing to read it fails the whole
+            // input.
+            Some(Err(err)) if err.is_input_error() => Err(err),
+            Some(_) => Err(error::new(ErrorImpl::MoreThanOneDocument)),
+        }
+    }
+}
+
+/// A sequence or mapping that is deserialized as it is parsed.
+#[derive(Copy, Clone)]
+enum Streamed {
+    Sequence,
+    Mapping,
+}
+
+struct StreamDeserializer<'de, 'stream> {
+    stream: &'stream mut EventStream<'de>,
+    config: &'stream DeserializerConfig,
+    path: Path<'stream>,
+    remaining_depth: usize,
+}
+
+impl<'de> StreamDeserializer<'de, '_> {
+    /// Peeks at the next node, which is streamed if it is a sequence or
+    /// a mapping without a tag or an anchor. Mappings are only streamed
+    /// if the type being deserialized decides about duplicate keys.
+    fn peek_streamed(&mut self) -> Result<(Option<Streamed>, Mark)> {
+        let (event, mark, anchor) = self.stream.peek()?;
+        let streamed = match event {
+            _ if anchor.is_some() => None,
+            Event::SequenceStart(sequence)
+                if sequence.tag.is_none() =>
+            {
+                Some(Streamed::Sequence)
+            }
+            Event::MappingStart(mapping)
+                if mapping.tag.is_none()
+                    && self.config.duplicate_keys
+                        == DuplicateKeys::Target =>
+            {
+                Some(Streamed::Mapping)
+            }
+            _ => None,
+        };
+        Ok((streamed, mark))
+    }
+
+    /// Loads the next node and deserializes it with `f`.
+    fn delegate<T>(
+        &mut self,
+        f: impl for<'document> FnOnce(
+            &mut DeserializerFromEvents<'de, 'document>,
+        ) -> Result<T>,
+    ) -> Result<T> {
+        let store = &self.stream.store;
+        let (start, anchors) =
+            (store.events.len(), store.anchor_event_map.len());
+        // As with a whole document, the events before an error are still
+        // deserialized, and the error is reported where they end.
+        if let Err(err) = self.stream.load_node() {
+            self.stream.store.error = Some(err.shared());
+        }
+        let result = self.with_store(start, f);
+        let store = &mut self.stream.store;
+        if store.anchor_event_map.len() == anchors {
+            store.events.truncate(start);
+        }
+        match store.error.take() {
+            Some(parse_error) => {
+                result.and(Err(error::shared(parse_error)))
+            }
+            None => result,
+        }
+    }
+
+    /// Deserializes with `f` from the events in the store, starting at
+    /// `pos`.
+    fn with_store<T>(
+        &mut self,
+        mut pos: usize,
+        f: impl for<'document> FnOnce(
+            &mut DeserializerFromEvents<'de, 'document>,
+        ) -> Result<T>,
+    ) -> Result<T> {
+        let stream = &mut *self.stream;
+        let mut config = Cow::Borrowed(self.config);
+        // Without a limit of its own, the number of times aliases may be
+        // followed grows with the part of the document parsed so far.
+        if self.config.max_alias_expansions.is_none()
+            && !stream.store.anchor_event_map.is_empty()
+        {
+            config.to_mut().max_alias_expansions =
+                Some(stream.state.events().saturating_mul(100));
+        }
+        if let Some(version) = stream.version {
+            config.to_mut().yaml_version = version;
+        }
+        f(&mut DeserializerFromEvents {
+            document: &stream.store,
+            config: &config,
+            pos: &mut pos,
+            jumpcount: &mut stream.jumpcount,
+            path: self.path,
+            remaining_depth: self.remaining_depth,
+            current_enum: None,
+        })
+    }
+
+    /// Skips the next node. Only the nodes in it that have an anchor are
+    /// loaded, as aliases may refer to them later.
+    fn ignore(&mut self) -> Result<()> {
+        let mut depth = 0;
+        loop {
+            let (event, mark, anchor) = self.stream.peek()?;
+            if self.config.reject_unknown_tags {
+                if let Some(tag) = unknown_tag(event) {
+                    let tag = String::from_utf8_lossy(tag).into_owned();
+                    return Err(error::new(ErrorImpl::UnknownTag(
+                        tag, mark,
+                    )));
+                }
+            }
+            if anchor.is_some() {
+                let start = self.stream.load_node()?;
+                self.with_store(start, |de| de.ignore_any())?;
+            } else {
+                match self.stream.next()?.0 {
+                    Event::SequenceStart(_)
+                    | Event::MappingStart(_) => {
+                        depth += 1;
+                    }
+                    Event::SequenceEnd | Event::MappingEnd => {
+                        depth -= 1
+                    }
+                    Event::Void => return Ok(()),
+                    Event::Alias(_) | Event::Scalar(_) => {}
+                }
+            }
+            if depth == 0 {
+                return Ok(());
+            }
+        }
+    }
+
+    fn visit_sequence<V>(
+        &mut self,
+        visitor: V,
+        mark: Mark,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.stream.next()?;
+        let (value, len) = self.recursion_check(mark, |de| {
+            let mut seq = StreamSeqAccess { de, len: 0 };
+            let value = visitor.visit_seq(&mut seq)?;
+            Ok((value, seq.len))
+        })?;
+        let total = {
+            let mut seq = StreamSeqAccess { de: self, len };
+            while de::SeqAccess::next_element::<IgnoredAny>(&mut seq)?
+                .is_some()
+            {}
+            seq.len
+        };
+        match self.stream.next()?.0 {
+            Event::SequenceEnd | Event::Void => {}
+            _ => panic!("expected a SequenceEnd event"),
+        }
+        if total == len {
+            Ok(value)
+        } else {
+            Err(de::Error::invalid_length(total, &ExpectedSeq(len)))
+        }
+    }
+
+    /// Visits the mapping that starts at the next event. With `merge`,
+    /// the entries of the mappings given under `<<` keys are merged into
+    /// it once its own entries have been visited.
+    fn visit_mapping<V>(
+        &mut self,
+        visitor: V,
+        mark: Mark,
+        merge: bool,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.stream.next()?;
+        let store = &self.stream.store;
+        let (start, anchors) =
+            (store.events.len(), store.anchor_event_map.len());
+        let (value, len, total) = self.recursion_check(mark, |de| {
+            let mut map = StreamMapAccess {
+                de,
+                merge,
+                len: 0,
+                key: None,
+                keys: HashMap::new(),
+                merges: Vec::new(),
+                merged: None,
+                value: 0,
+            };
+            let value = visitor.visit_map(&mut map)?;
+            let len = map.len;
+            while de::MapAccess::next_entry::<IgnoredAny, IgnoredAny>(
+                &mut map,
+            )?
+            .is_some()
+            {}
+            Ok((value, len, map.len))
+        })?;
+        match self.stream.next()?.0 {
+            Event::MappingEnd | Event::Void => {}
+            _ => panic!("expected a MappingEnd event"),
+        }
+        // The values of the `<<` keys are no longer needed.
+        let store = &mut self.stream.store;
+        if store.anchor_event_map.len() == anchors {
+            store.events.truncate(start);
+        }
+        if total == len {
+            Ok(value)
+        } else {
+            Err(de::Error::invalid_length(total, &ExpectedMap(len)))
+        }
+    }
+
+    /// Reads the entries that the values of the `<<` keys at `merges` in
+    /// the store merge into a mapping whose own keys are `keys`.
+    fn merged_entries(
+        &mut self,
+        merges: &[usize],
+        keys: &mut HashMap<Value, usize>,
+    ) -> Result<Vec<MappingEntry>> {
+        self.with_store(0, |de| {
+            let mut entries = Vec::new();
+            for pos in merges {
+                de.add_merged_entries(*pos, &mut entries, keys)?;
+            }
+            Ok(entries)
+        })
+    }
+
+    fn recursion_check<F: FnOnce(&mut Self) -> Result<T>, T>(
+        &mut self,
+        mark: Mark,
+        f: F,
+    ) -> Result<T> {
+        let previous_depth = self.remaining_depth;
+        self.remaining_depth = match previous_depth.checked_sub(1) {
+            Some(depth) => depth,
+            None => {
+                return Err(error::new(
+                    ErrorImpl::RecursionLimitExceeded(mark),
+                ))
+            }
+        };
+        let result = f(self);
+        self.remaining_depth = previous_depth;
+        result
+    }
+}
+
+struct StreamSeqAccess<'de, 'stream, 'seq> {
+    de: &'seq mut StreamDeserializer<'de, 'stream>,
+    len: usize,
+}
+
+impl<'de> de::SeqAccess<'de> for StreamSeqAccess<'de, '_, '_> {
+    type Error = Error;
+
+    fn next_element_seed<T>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        match self.de.stream.peek()?.0 {
+            Event::SequenceEnd | Event::Void => Ok(None),
+            _ => {
+                let mut element_de = StreamDeserializer {
+                    stream: self.de.stream,
+                    config: self.de.config,
+                    path: Path::Seq {
+                        parent: &self.de.path,
+                        index: self.len,
+                    },
+                    remaining_depth: self.de.remaining_depth,
+                };
+                self.len += 1;
+                seed.deserialize(&mut element_de).map(Some)
+            }
+        }
+    }
+}
+
+struct StreamMapAccess<'de, 'stream, 'map> {
+    de: &'map mut StreamDeserializer<'de, 'stream>,
+    merge: bool,
+    len: usize,
+    key: Option<String>,
+    /// The keys of the mapping itself, which take precedence over those
+    /// of the mappings merged into it.
+    keys: HashMap<Value, usize>,
+    /// The positions in the store of the values of the `<<` keys.
+    merges: Vec<usize>,
+    /// The merged entries, once the entries of the mapping itself have
+    /// all been visited.
+    merged: Option<std::vec::IntoIter<MappingEntry>>,
+    /// The position in the store of the value of the current merged
+    /// entry.
+    value: usize,
+}
+
+impl<'de> de::MapAccess<'de> for StreamMapAccess<'de, '_, '_> {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        while self.merged.is_none() {
+            let (event, _mark, _anchor) = self.de.stream.peek()?;
+            let key = match event {
+                Event::MappingEnd | Event::Void => {
+                    let merges = mem::take(&mut self.merges);
+                    let entries = self
+                        .de
+                        .merged_entries(&merges, &mut self.keys)?;
+                    self.merged = Some(entries.into_iter());
+                    break;
+                }
+                Event::Scalar(scalar) => {
+                    if self.merge
+                        && scalar.tag.is_none()
+                        && scalar.style == ScalarStyle::Plain
+                        && *scalar.value == *b"<<"
+                    {
+                        self.de.ignore()?;
+                        let value = self.de.stream.load_node()?;
+                        self.merges.push(value);
+                        continue;
+                    }
+                    str::from_utf8(&scalar.value)
+                        .ok()
+                        .map(str::to_owned)
+                }
+                _ => None,
+            };
+            self.len += 1;
+            self.key = key;
+            let keys = if self.merge {
+                Some(&mut self.keys)
+            } else {
+                None
+            };
+            return self
+                .de
+                .delegate(|de| {
+                    if let Some(keys) = keys {
+                        if let Some(key) = de.key_at(*de.pos) {
+                            keys.entry(key).or_insert(0);
+                        }
+                    }
+                    seed.deserialize(de)
+                })
+                .map(Some);
+        }
+        let entry = match self.merged.as_mut().and_then(Iterator::next)
+        {
+            Some(entry) => entry,
+            None => return Ok(None),
+        };
+        self.len += 1;
+        self.key = match &self.de.stream.store.events[entry.key].0 {
+            Event::Scalar(scalar) => {
+                str::from_utf8(&scalar.value).ok().map(str::to_owned)
+            }
+            _ => None,
+        };
+        self.value = entry.values[0];
+        self.de
+            .with_store(entry.key, |de| seed.deserialize(de))
+            .map(Some)
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let mut value_de = StreamDeserializer {
+            stream: self.de.stream,
+            config: self.de.config,
+            path: match &self.key {
+                Some(key) => Path::Map {
+                    parent: &self.de.path,
+                    key,
+                },
+                None => Path::Unknown {
+                    parent: &self.de.path,
+                },
+            },
+            remaining_depth: self.de.remaining_depth,
+        };
+        if self.merged.is_some() {
+            value_de.with_store(self.value, |de| seed.deserialize(de))
+        } else {
+            seed.deserialize(&mut value_de)
+        }
+    }
+}
+
+impl<'de> de::Deserializer<'de> for &mut StreamDeserializer<'de, '_> {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        let (streamed, mark) = self.peek_streamed()?;
+        match streamed {
+            Some(Streamed::Sequence) => {
+                self.visit_sequence(visitor, mark)
+            }
+            Some(Streamed::Mapping) => {
+                self.visit_mapping(visitor, mark, false)
+            }
+            None => {
+                return self.delegate(|de| de.deserialize_any(visitor))
+            }
+        }
+        .map_err(|err| error::fix_mark(err, mark, self.path))
+    }
+
+    fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.delegate(|de| de.deserialize_bool(visitor))
+    }
+
+    fn deserialize_i8<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.delegate(|de| de.deserialize_i8(visitor))
+    }
+
+    fn deserialize_i16<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.delegate(|de| de.deserialize_i16(visitor))
+    }
+
+    fn deserialize_i32<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.delegate(|de| de.deserialize_i32(visitor))
+    }
+
+    fn deserialize_i64<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.delegate(|de| de.deserialize_i64(visitor))
+    }
+
+    fn deserialize_i128<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.delegate(|de| de.deserialize_i128(visitor))
+    }
+
+    fn deserialize_u8<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.delegate(|de| de.deserialize_u8(visitor))
+    }
+
+    fn deserialize_u16<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.delegate(|de| de.deserialize_u16(visitor))
+    }
+
+    fn deserialize_u32<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.delegate(|de| de.deserialize_u32(visitor))
+    }
+
+    fn deserialize_u64<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.delegate(|de| de.deserialize_u64(visitor))
+    }
+
+    fn deserialize_u128<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.delegate(|de| de.deserialize_u128(visitor))
+    }
+
+    fn deserialize_f32<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.delegate(|de| de.deserialize_f32(visitor))
+    }
+
+    fn deserialize_f64<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.delegate(|de| de.deserialize_f64(visitor))
+    }
+
+    fn deserialize_char<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.delegate(|de| de.deserialize_char(visitor))
     }
 
     fn deserialize_str<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        let (next, mark) = self.next_event_mark()?;
-        match next {
-            Event::Scalar(scalar) => {
-                if let Ok(v) = str::from_utf8(&scalar.value) {
-                    if let Some(borrowed) =
-                        parse_borrowed_str(v, scalar.repr, scalar.style)
-                    {
-                        visitor.visit_borrowed_str(borrowed)
-                    } else {
-                        visitor.visit_str(v)
-                    }
-                } else {
-                    Err(invalid_type(next, &visitor))
-                }
-            }
-            Event::Alias(mut pos) => {
-                self.jump(&mut pos)?.deserialize_str(visitor)
-            }
-            other => Err(invalid_type(other, &visitor)),
-        }
-        .map_err(|err: Error| error::fix_mark(err, mark, self.path))
+        self.delegate(|de| de.deserialize_str(visitor))
     }
 
     fn deserialize_string<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.deserialize_str(visitor)
+        self.delegate(|de| de.deserialize_string(visitor))
     }
 
-    fn deserialize_bytes<V>(self, _visitor: V) -> Result<V::Value>
+    fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        Err(error::new(ErrorImpl::BytesUnsupported))
+        self.delegate(|de| de.deserialize_bytes(visitor))
     }
 
-    fn deserialize_byte_buf<V>(self, _visitor: V) -> Result<V::Value>
+    fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        Err(error::new(ErrorImpl::BytesUnsupported))
+        self.delegate(|de| de.deserialize_byte_buf(visitor))
     }
 
-    /// Parses `null` as None and any other values as `Some(...)`.
     fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        let is_some = match self.peek_event()? {
-            Event::Alias(mut pos) => {
-                *self.pos += 1;
-                return self
-                    .jump(&mut pos)?
-                    .deserialize_option(visitor);
-            }
-            Event::Scalar(scalar) => {
-                let tagged_already = self.current_enum.is_some();
-                if scalar.style != ScalarStyle::Plain {
-                    true
-                } else if let (Some(tag), false) =
-                    (&scalar.tag, tagged_already)
-                {
-                    if tag == Tag::NULL {
-                        if let Some(()) = parse_null(&scalar.value) {
-                            false
-                        } else if let Ok(v) =
-                            str::from_utf8(&scalar.value)
-                        {
-                            return Err(de::Error::invalid_value(
-                                Unexpected::Str(v),
-                                &"null",
-                            ));
-                        } else {
-                            return Err(de::Error::invalid_value(
-                                Unexpected::Bytes(&scalar.value),
-                                &"null",
-                            ));
-                        }
-                    } else {
-                        true
-                    }
-                } else {
-                    !scalar.value.is_empty()
-                        && parse_null(&scalar.value).is_none()
-                }
+        match self.stream.peek()?.0 {
+            Event::SequenceStart(_) | Event::MappingStart(_) => {
+                visitor.visit_some(self)
             }
-            Event::SequenceStart(_) | Event::MappingStart(_) => true,
-            Event::SequenceEnd => panic!("unexpected end of sequence"),
-            Event::MappingEnd => panic!("unexpected end of mapping"),
-            Event::Void => false,
-        };
-        if is_some {
-            visitor.visit_some(self)
-        } else {
-            *self.pos += 1;
-            self.current_enum = None;
-            visitor.visit_none()
+            _ => self.delegate(|de| de.deserialize_option(visitor)),
         }
     }
 
@@ -1987,56 +4556,20 @@ impl<'de> de::Deserializer<'de>
     where
         V: Visitor<'de>,
     {
-        let tagged_already = self.current_enum.is_some();
-        let (next, mark) = self.next_event_mark()?;
-        match next {
-            Event::Scalar(scalar) => {
-                let is_null = if scalar.style != ScalarStyle::Plain {
-                    false
-                } else if let (Some(tag), false) =
-                    (&scalar.tag, tagged_already)
-                {
-                    tag == Tag::NULL
-                        && parse_null(&scalar.value).is_some()
-                } else {
-                    scalar.value.is_empty()
-                        || parse_null(&scalar.value).is_some()
-                };
-                if is_null {
-                    visitor.visit_unit()
-                } else if let Ok(v) = str::from_utf8(&scalar.value) {
-                    Err(de::Error::invalid_value(
-                        Unexpected::Str(v),
-                        &"null",
-                    ))
-                } else {
-                    Err(de::Error::invalid_value(
-                        Unexpected::Bytes(&scalar.value),
-                        &"null",
-                    ))
-                }
-            }
-            Event::Alias(mut pos) => {
-                self.jump(&mut pos)?.deserialize_unit(visitor)
-            }
-            Event::Void => visitor.visit_unit(),
-            other => Err(invalid_type(other, &visitor)),
-        }
-        .map_err(|err| error::fix_mark(err, mark, self.path))
+        self.delegate(|de| de.deserialize_unit(visitor))
     }
 
     fn deserialize_unit_struct<V>(
         self,
-        _name: &'static str,
+        name: &'static str,
         visitor: V,
     ) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.deserialize_unit(visitor)
+        self.delegate(|de| de.deserialize_unit_struct(name, visitor))
     }
 
-    /// Parses a newtype struct as the underlying value.
     fn deserialize_newtype_struct<V>(
         self,
         _name: &'static str,
@@ -2045,7 +4578,7 @@ impl<'de> de::Deserializer<'de>
     where
         V: Visitor<'de>,
     {
-        let (_event, mark) = self.peek_event_mark()?;
+        let mark = self.stream.peek()?.1;
         self.recursion_check(mark, |de| {
             visitor.visit_newtype_struct(de)
         })
@@ -2055,34 +4588,12 @@ impl<'de> de::Deserializer<'de>
     where
         V: Visitor<'de>,
     {
-        let (next, mark) = self.next_event_mark()?;
-        match next {
-            Event::Alias(mut pos) => {
-                self.jump(&mut pos)?.deserialize_seq(visitor)
-            }
-            Event::SequenceStart(_) => {
-                self.visit_sequence(visitor, mark)
-            }
-            other => {
-                if match other {
-                    Event::Void => true,
-                    Event::Scalar(scalar) => {
-                        scalar.value.is_empty()
-                            && scalar.style == ScalarStyle::Plain
-                    }
-                    _ => false,
-                } {
-                    visitor.visit_seq(SeqAccess {
-                        empty: true,
-                        de: self,
-                        len: 0,
-                    })
-                } else {
-                    Err(invalid_type(other, &visitor))
-                }
-            }
+        match self.peek_streamed()? {
+            (Some(Streamed::Sequence), mark) => self
+                .visit_sequence(visitor, mark)
+                .map_err(|err| error::fix_mark(err, mark, self.path)),
+            _ => self.delegate(|de| de.deserialize_seq(visitor)),
         }
-        .map_err(|err| error::fix_mark(err, mark, self.path))
     }
 
     fn deserialize_tuple<V>(
@@ -2112,33 +4623,12 @@ impl<'de> de::Deserializer<'de>
     where
         V: Visitor<'de>,
     {
-        let (next, mark) = self.next_event_mark()?;
-        match next {
-            Event::Alias(mut pos) => {
-                self.jump(&mut pos)?.deserialize_map(visitor)
-            }
-            Event::MappingStart(_) => self.visit_mapping(visitor, mark),
-            other => {
-                if match other {
-                    Event::Void => true,
-                    Event::Scalar(scalar) => {
-                        scalar.value.is_empty()
-                            && scalar.style == ScalarStyle::Plain
-                    }
-                    _ => false,
-                } {
-                    visitor.visit_map(MapAccess {
-                        empty: true,
-                        de: self,
-                        len: 0,
-                        key: None,
-                    })
-                } else {
-                    Err(invalid_type(other, &visitor))
-                }
-            }
+        match self.peek_streamed()? {
+            (Some(Streamed::Mapping), mark) => self
+                .visit_mapping(visitor, mark, true)
+                .map_err(|err| error::fix_mark(err, mark, self.path)),
+            _ => self.delegate(|de| de.deserialize_map(visitor)),
         }
-        .map_err(|err| error::fix_mark(err, mark, self.path))
     }
 
     fn deserialize_struct<V>(
@@ -2153,9 +4643,6 @@ impl<'de> de::Deserializer<'de>
         self.deserialize_map(visitor)
     }
 
-    /// Parses an enum as a single key:value pair where the key identifies the
-    /// variant and the value gives the content. A String will also parse correctly
-    /// to a unit enum value.
     fn deserialize_enum<V>(
         self,
         name: &'static str,
@@ -2165,88 +4652,21 @@ impl<'de> de::Deserializer<'de>
     where
         V: Visitor<'de>,
     {
-        let (next, mark) = self.peek_event_mark()?;
-        #[allow(clippy::never_loop)]
-        loop {
-            if let Some(current_enum) = self.current_enum {
-                if let Event::Scalar(scalar) = next {
-                    if !scalar.value.is_empty() {
-                        break visitor.visit_enum(UnitVariantAccess { de: self });
-                    }
-                }
-                let message = if let Some(name) = current_enum.name {
-                    format!(
-                        "deserializing nested enum in {}::{} from YAML is not supported yet",
-                        name, current_enum.tag,
-                    )
-                } else {
-                    format!(
-                        "deserializing nested enum in !{} from YAML is not supported yet",
-                        current_enum.tag,
-                    )
-                };
-                break Err(error::new(ErrorImpl::Message(message, None)));
-            }
-            break match next {
-                Event::Alias(mut pos) => {
-                    *self.pos += 1;
-                    self.jump(&mut pos)?
-                        .deserialize_enum(name, variants, visitor)
-                }
-                Event::Scalar(scalar) => {
-                    if let Some(tag) = parse_tag(&scalar.tag) {
-                        return visitor.visit_enum(EnumAccess {
-                            de: self,
-                            name: Some(name),
-                            tag,
-                        });
-                    }
-                    visitor.visit_enum(UnitVariantAccess { de: self })
-                }
-                Event::MappingStart(mapping) => {
-                    if let Some(tag) = parse_tag(&mapping.tag) {
-                        return visitor.visit_enum(EnumAccess {
-                            de: self,
-                            name: Some(name),
-                            tag,
-                        });
-                    }
-                    let err =
-                        de::Error::invalid_type(Unexpected::Map, &"a YAML tag starting with '!'");
-                    Err(error::fix_mark(err, mark, self.path))
-                }
-                Event::SequenceStart(sequence) => {
-                    if let Some(tag) = parse_tag(&sequence.tag) {
-                        return visitor.visit_enum(EnumAccess {
-                            de: self,
-                            name: Some(name),
-                            tag,
-                        });
-                    }
-                    let err =
-                        de::Error::invalid_type(Unexpected::Seq, &"a YAML tag starting with '!'");
-                    Err(error::fix_mark(err, mark, self.path))
-                }
-                Event::SequenceEnd => panic!("unexpected end of sequence"),
-                Event::MappingEnd => panic!("unexpected end of mapping"),
-                Event::Void => Err(error::new(ErrorImpl::EndOfStream)),
-            };
-        }
-        .map_err(|err| error::fix_mark(err, mark, self.path))
+        self.delegate(|de| de.deserialize_enum(name, variants, visitor))
     }
 
     fn deserialize_identifier<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.deserialize_str(visitor)
+        self.delegate(|de| de.deserialize_identifier(visitor))
     }
 
     fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.ignore_any()?;
+        self.ignore()?;
         visitor.visit_unit()
     }
 }
@@ -2298,6 +4718,9 @@ where
 /// into an instance of the type `T`. The type must implement the `DeserializeOwned` trait
 /// from Serde, which means it must be able to be deserialized without any borrowed data.
 ///
+/// The stream is read in chunks and deserialized as it is parsed, without reading the whole
+/// input into memory first. See [`Deserializer::from_reader`].
+///
 /// # Errors
 ///
 /// This conversion can fail if the structure of the YAML does not match the structure expected
@@ -2376,3 +4799,52 @@ where
 {
     T::deserialize(Deserializer::from_slice(v))
 }
+
+/// Deserialize an instance of type `T` from a string of YAML text, using
+/// the input settings of `builder`.
+///
+/// # Errors
+///
+/// Fails for the same reasons as [`from_str`].
+pub fn from_str_with<'de, T>(
+    s: &'de str,
+    builder: &DeserializerBuilder,
+) -> Result<T>
+where
+    T: Deserialize<'de>,
+{
+    T::deserialize(builder.from_str(s))
+}
+
+/// Deserialize an instance of type `T` from an IO stream of YAML, using
+/// the input settings of `builder`.
+///
+/// # Errors
+///
+/// Fails for the same reasons as [`from_reader`].
+pub fn from_reader_with<R, T>(
+    rdr: R,
+    builder: &DeserializerBuilder,
+) -> Result<T>
+where
+    R: io::Read,
+    T: DeserializeOwned,
+{
+    T::deserialize(builder.from_reader(rdr))
+}
+
+/// Deserialize an instance of type `T` from bytes of YAML text, using the
+/// input settings of `builder`.
+///
+/// # Errors
+///
+/// Fails for the same reasons as [`from_slice`].
+pub fn from_slice_with<'de, T>(
+    v: &'de [u8],
+    builder: &DeserializerBuilder,
+) -> Result<T>
+where
+    T: Deserialize<'de>,
+{
+    T::deserialize(builder.from_slice(v))
+}
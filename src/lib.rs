@@ -126,9 +126,20 @@ use std::{fs::File, io::Write}; // Import types for file operations
 const LOG_FILE_PATH: &str = "./serde_yml.log";
 
 // Re-export commonly used items from other modules
-pub use crate::de::{from_reader, from_slice, from_str, Deserializer}; // Deserialization functions
+pub use crate::de::{
+    from_reader, from_reader_with, from_slice, from_slice_with,
+    from_str, from_str_with, Deserializer, DeserializerBuilder,
+    DuplicateKeys, Schema,
+}; // Deserialization functions
 pub use crate::modules::error::{Error, Location, Result}; // Error handling types
-pub use crate::ser::{to_string, to_writer, Serializer, State}; // Serialization functions
+pub use crate::ser::{
+    to_string, to_string_with, to_writer, to_writer_documents,
+    to_writer_with, AnchorMode, CollectionStyle, ComplexKeyStyle,
+    DocumentWriter, FloatFormat, LineBreak, MappingWriter,
+    NestedEnumStyle, NonFiniteFloats, NullStyle, QuotingPolicy,
+    SequenceWriter, Serializer, SerializerBuilder, SortKeys, State,
+    YamlVersion,
+}; // Serialization functions
 #[doc(inline)]
 pub use crate::value::{
     from_value, to_value, Index, Number, Sequence, Value,
@@ -184,6 +195,8 @@ mod private {
     impl Sealed for String {}
     impl Sealed for crate::Value {}
     impl<T> Sealed for &T where T: ?Sized + Sealed {}
+    impl<T> Sealed for std::rc::Rc<T> where T: ?Sized {}
+    impl<T> Sealed for std::sync::Arc<T> where T: ?Sized {}
 }
 
 /// Run the Serde YML tool.
@@ -3,23 +3,28 @@
 // SPDX-License-Identifier: Apache-2.0 OR MIT indicates dual licensing under Apache 2.0 or MIT licenses.
 // Copyright © 2024 Serde YML, Seamless YAML Serialization for Rust. All rights reserved.
 
-use crate::libyml::{self, util::Owned};
+use crate::libyml::{self, layout::Layout, util::Owned};
 use ::libyml::api::ScalarEventData;
 use ::libyml::YamlEventT;
 use ::libyml::YamlScalarStyleT::YamlLiteralScalarStyle;
 use ::libyml::{
-    yaml_document_end_event_initialize,
+    yaml_alias_event_initialize, yaml_document_end_event_initialize,
     yaml_document_start_event_initialize, yaml_emitter_delete,
     yaml_emitter_emit, yaml_emitter_flush, yaml_emitter_initialize,
-    yaml_emitter_set_output, yaml_emitter_set_unicode,
-    yaml_emitter_set_width, yaml_mapping_end_event_initialize,
+    yaml_emitter_set_break, yaml_emitter_set_canonical,
+    yaml_emitter_set_indent, yaml_emitter_set_output,
+    yaml_emitter_set_unicode, yaml_emitter_set_width,
+    yaml_mapping_end_event_initialize,
     yaml_mapping_start_event_initialize, yaml_scalar_event_initialize,
     yaml_sequence_end_event_initialize,
     yaml_sequence_start_event_initialize,
     yaml_stream_end_event_initialize,
     yaml_stream_start_event_initialize, YamlAnyMappingStyle,
-    YamlAnySequenceStyle, YamlEmitterT, YamlScalarStyleT,
-    YamlSingleQuotedScalarStyle, YamlUtf8Encoding,
+    YamlAnySequenceStyle, YamlBlockMappingStyle,
+    YamlBlockSequenceStyle, YamlCrBreak, YamlCrlnBreak, YamlEmitterT,
+    YamlFlowMappingStyle, YamlFlowSequenceStyle, YamlLnBreak,
+    YamlScalarStyleT, YamlSingleQuotedScalarStyle, YamlTagDirectiveT,
+    YamlUtf8Encoding, YamlVersionDirectiveT,
 };
 use std::fmt::Debug;
 #[allow(clippy::unsafe_removed_from_name)]
@@ -40,47 +45,174 @@ pub(crate) enum Error {
     Io(io::Error),
 }
 
-/// A YAML emitter.
+/// Output settings applied when an [`Emitter`] is created.
+#[derive(Clone, Debug)]
+pub(crate) struct EmitterConfig {
+    /// Number of spaces per indentation level, between 2 and 9.
+    pub(crate) indent: usize,
+    /// Preferred line width, or `None` to never wrap.
+    pub(crate) width: Option<usize>,
+    /// Whether block sequences that are mapping values are indented
+    /// under their key.
+    pub(crate) indent_sequences: bool,
+    /// Whether every document starts with `---`, including the first.
+    pub(crate) explicit_start: bool,
+    /// Whether every document ends with `...`.
+    pub(crate) explicit_end: bool,
+    /// Minor version for a `%YAML 1.x` directive on every document.
+    pub(crate) version_minor: Option<i32>,
+    /// Handle and prefix of each `%TAG` directive on every document.
+    pub(crate) tag_directives: Vec<(String, String)>,
+    /// Comment written at the top of every document.
+    pub(crate) header_comment: Option<String>,
+    /// Path and text of each comment written before a node.
+    pub(crate) comments: Vec<(String, String)>,
+    /// Whether every non-ASCII character is escaped.
+    pub(crate) ascii: bool,
+    /// Whether output is in libyml's canonical form.
+    pub(crate) canonical: bool,
+    /// The line break written at the end of every line: `\n`, `\r\n` or
+    /// `\r`.
+    pub(crate) line_break: &'static [u8],
+    /// Whether sequences and mappings that are keys of a block mapping
+    /// are written in flow style rather than in block style after `? `.
+    pub(crate) flow_keys: bool,
+}
+
+impl Default for EmitterConfig {
+    fn default() -> Self {
+        EmitterConfig {
+            indent: 2,
+            width: None,
+            indent_sequences: false,
+            explicit_start: false,
+            explicit_end: false,
+            version_minor: None,
+            tag_directives: Vec::new(),
+            header_comment: None,
+            comments: Vec::new(),
+            ascii: false,
+            canonical: false,
+            line_break: b"\n",
+            flow_keys: false,
+        }
+    }
+}
+
+/// Document directives in the form libyml expects.
+struct Directives {
+    version: Option<YamlVersionDirectiveT>,
+    tags: Vec<YamlTagDirectiveT>,
+    /// Nul-terminated handles and prefixes that `tags` points into.
+    _strings: Vec<Vec<u8>>,
+}
+
+impl Directives {
+    fn new(config: &EmitterConfig) -> Self {
+        let version = config.version_minor.map(|minor| {
+            let mut version: YamlVersionDirectiveT =
+                unsafe { mem::zeroed() };
+            version.major = 1;
+            version.minor = minor;
+            version
+        });
+        let mut strings = Vec::new();
+        let mut tags = Vec::new();
+        for (handle, prefix) in &config.tag_directives {
+            let mut handle = handle.clone().into_bytes();
+            let mut prefix = prefix.clone().into_bytes();
+            handle.push(b'\0');
+            prefix.push(b'\0');
+            let mut tag: YamlTagDirectiveT = unsafe { mem::zeroed() };
+            tag.handle = handle.as_mut_ptr();
+            tag.prefix = prefix.as_mut_ptr();
+            tags.push(tag);
+            strings.push(handle);
+            strings.push(prefix);
+        }
+        Directives {
+            version,
+            tags,
+            _strings: strings,
+        }
+    }
+
+    fn version(&mut self) -> *mut YamlVersionDirectiveT {
+        match &mut self.version {
+            Some(version) => version,
+            None => ptr::null_mut(),
+        }
+    }
+
+    fn tags(
+        &mut self,
+    ) -> (*mut YamlTagDirectiveT, *mut YamlTagDirectiveT) {
+        if self.tags.is_empty() {
+            (ptr::null_mut(), ptr::null_mut())
+        } else {
+            let range = self.tags.as_mut_ptr_range();
+            (range.start, range.end)
+        }
+    }
+}
+
+/// A YAML emitter that writes to a `W`.
 #[derive(Debug)]
-pub struct Emitter<'a> {
-    pin: Owned<EmitterPinned<'a>>,
+pub struct Emitter<W> {
+    pin: Owned<EmitterPinned<W>>,
+    config: EmitterConfig,
+    /// The collections that the next event is inside, innermost last.
+    open: Vec<OpenCollection>,
+}
+
+/// A sequence or mapping that has been started but not ended.
+#[derive(Debug)]
+struct OpenCollection {
+    /// Whether the collection, or one that it is inside, is in flow
+    /// style.
+    flow: bool,
+    mapping: bool,
+    /// The number of nodes written in it so far.
+    len: usize,
 }
 
 /// Represents a pinned emitter for YAML serialization.
 ///
 /// The `EmitterPinned` struct contains the necessary state and resources
-/// for emitting YAML documents. It is pinned to a specific lifetime `'a`
-/// to ensure that the `write` field remains valid throughout the lifetime
-/// of the emitter.
+/// for emitting YAML documents. It stays at the same address for as long
+/// as the emitter exists, because libyml keeps a pointer to it for the
+/// write callback.
 ///
 /// # Fields
 ///
 /// - `sys`: An instance of `YamlEmitterT` representing the underlying
 ///   emitter system.
-/// - `write`: A boxed trait object implementing the `io::Write` trait,
-///   used for writing the emitted YAML data. It is pinned to the lifetime
-///   `'a` to ensure it remains valid for the duration of the emitter's
-///   lifetime.
+/// - `write`: The writer that the emitted YAML data goes to. It is only
+///   `None` once `Emitter::into_inner` has taken it.
 /// - `write_error`: An optional `io::Error` used to store any errors that
 ///   occur during the writing process.
-///
-/// # Lifetime
-///
-/// The `EmitterPinned` struct is parameterized by a lifetime `'a`, which
-/// represents the lifetime of the `write` field. This ensures that the
-/// `write` field remains valid for the entire lifetime of the `EmitterPinned`
-/// instance.
-pub struct EmitterPinned<'a> {
+/// - `layout`: The output stage that buffers and rewrites each document,
+///   present only when the configured layout needs it.
+pub struct EmitterPinned<W> {
     sys: YamlEmitterT,
-    write: Box<dyn io::Write + 'a>,
+    write: Option<W>,
     write_error: Option<io::Error>,
+    layout: Option<Layout>,
 }
 
-impl Debug for EmitterPinned<'_> {
+// SAFETY: the pointers in `sys` point only into allocations that the
+// emitter owns: libyml's buffers and queues, and the `EmitterPinned`
+// passed to the write callback. Nothing is shared with another emitter
+// or tied to the current thread, so the emitter can move to another
+// thread whenever its writer can.
+unsafe impl<W> Send for Emitter<W> where W: Send {}
+
+impl<W> Debug for EmitterPinned<W> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("EmitterPinned")
             .field("sys", &self.sys)
             .field("write_error", &self.write_error)
+            .field("layout", &self.layout)
             .finish()
     }
 }
@@ -96,6 +228,8 @@ pub enum Event<'a> {
     DocumentStart,
     /// End of a YAML document.
     DocumentEnd,
+    /// Alias of an earlier node, by the name of its anchor.
+    Alias(String),
     /// Scalar value.
     Scalar(Scalar<'a>),
     /// Start of a sequence.
@@ -111,6 +245,8 @@ pub enum Event<'a> {
 /// Represents a scalar value in YAML.
 #[derive(Debug)]
 pub struct Scalar<'a> {
+    /// Optional anchor for the scalar.
+    pub anchor: Option<String>,
     /// Optional tag for the scalar.
     pub tag: Option<String>,
     /// Value of the scalar.
@@ -120,7 +256,7 @@ pub struct Scalar<'a> {
 }
 
 /// Styles for YAML scalars.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum ScalarStyle {
     /// Any scalar style.
     Any,
@@ -128,28 +264,65 @@ pub enum ScalarStyle {
     Plain,
     /// Single quoted scalar style.
     SingleQuoted,
+    /// Double quoted scalar style.
+    DoubleQuoted,
     /// Literal scalar style.
     Literal,
+    /// Folded scalar style.
+    Folded,
 }
 
 /// Represents a YAML sequence.
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Sequence {
+    /// Optional anchor for the sequence.
+    pub anchor: Option<String>,
     /// Optional tag for the sequence.
     pub tag: Option<String>,
+    /// Style of the sequence.
+    pub style: SequenceStyle,
+}
+
+/// Styles for YAML sequences.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SequenceStyle {
+    /// Any sequence style.
+    Any,
+    /// Block sequence style, one `- ` item per line.
+    Block,
+    /// Flow sequence style, such as `[1, 2]`.
+    Flow,
 }
 
 /// Represents a YAML mapping.
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Mapping {
+    /// Optional anchor for the mapping.
+    pub anchor: Option<String>,
     /// Optional tag for the mapping.
     pub tag: Option<String>,
+    /// Style of the mapping.
+    pub style: MappingStyle,
+}
+
+/// Styles for YAML mappings.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MappingStyle {
+    /// Any mapping style.
+    Any,
+    /// Block mapping style, one `key: value` entry per line.
+    Block,
+    /// Flow mapping style, such as `{x: 1, y: 2}`.
+    Flow,
 }
 
-impl<'a> Emitter<'a> {
-    /// Creates a new YAML emitter.
-    pub(crate) fn new(write: Box<dyn io::Write + 'a>) -> Emitter<'a> {
-        let owned = Owned::<EmitterPinned<'a>>::new_uninit();
+impl<W> Emitter<W>
+where
+    W: io::Write,
+{
+    /// Creates a new YAML emitter with the given output settings.
+    pub(crate) fn new(write: W, config: &EmitterConfig) -> Emitter<W> {
+        let owned = Owned::<EmitterPinned<W>>::new_uninit();
         let pin = unsafe {
             let emitter = addr_of_mut!((*owned.ptr).sys);
             if yaml_emitter_initialize(emitter).fail {
@@ -158,25 +331,122 @@ impl<'a> Emitter<'a> {
                     libyml::Error::emit_error(emitter)
                 );
             }
-            yaml_emitter_set_unicode(emitter, true);
-            yaml_emitter_set_width(emitter, -1);
-            addr_of_mut!((*owned.ptr).write).write(write);
+            yaml_emitter_set_unicode(emitter, !config.ascii);
+            yaml_emitter_set_canonical(emitter, config.canonical);
+            yaml_emitter_set_break(
+                emitter,
+                match config.line_break {
+                    b"\r\n" => YamlCrlnBreak,
+                    b"\r" => YamlCrBreak,
+                    _ => YamlLnBreak,
+                },
+            );
+            yaml_emitter_set_indent(emitter, config.indent as i32);
+            yaml_emitter_set_width(
+                emitter,
+                config.width.map_or(-1, |width| {
+                    width.min(i32::MAX as usize) as i32
+                }),
+            );
+            addr_of_mut!((*owned.ptr).write).write(Some(write));
             addr_of_mut!((*owned.ptr).write_error).write(None);
+            addr_of_mut!((*owned.ptr).layout)
+                .write(Layout::new(config));
             yaml_emitter_set_output(
                 emitter,
-                write_handler,
+                write_handler::<W>,
                 owned.ptr.cast(),
             );
             Owned::assume_init(owned)
         };
-        Emitter { pin }
+        Emitter {
+            pin,
+            config: config.clone(),
+            open: Vec::new(),
+        }
     }
 
     /// Emits a YAML event.
     pub(crate) fn emit(
         &mut self,
-        event: Event<'_>,
+        mut event: Event<'_>,
     ) -> Result<(), Error> {
+        let ends_document =
+            matches!(event, Event::DocumentEnd | Event::StreamEnd);
+
+        // An empty plain scalar is a null, but libyml quotes it where it
+        // cannot be empty: inside flow collections and as a mapping key.
+        // It would then be read back as a string, so write `null`.
+        let parent = self.open.last();
+        if let Event::Scalar(scalar) = &mut event {
+            if scalar.value.is_empty()
+                && scalar.tag.is_none()
+                && scalar.style == ScalarStyle::Plain
+                && parent.map_or(false, |parent| {
+                    parent.flow || parent.mapping && parent.len % 2 == 0
+                })
+            {
+                scalar.value = "null";
+            }
+        }
+
+        // A collection that is the key of a block mapping is written in
+        // the style chosen for keys, whatever style it asks for.
+        let is_block_key = parent.map_or(false, |parent| {
+            !parent.flow && parent.mapping && parent.len % 2 == 0
+        });
+        match &mut event {
+            Event::SequenceStart(sequence) if is_block_key => {
+                sequence.style = if self.config.flow_keys {
+                    SequenceStyle::Flow
+                } else {
+                    SequenceStyle::Block
+                };
+            }
+            Event::MappingStart(mapping) if is_block_key => {
+                mapping.style = if self.config.flow_keys {
+                    MappingStyle::Flow
+                } else {
+                    MappingStyle::Block
+                };
+            }
+            _ => {}
+        }
+
+        match &event {
+            Event::SequenceStart(_) | Event::MappingStart(_) => {
+                let flow = parent.map_or(false, |parent| parent.flow)
+                    || matches!(
+                        &event,
+                        Event::SequenceStart(Sequence {
+                            style: SequenceStyle::Flow,
+                            ..
+                        }) | Event::MappingStart(Mapping {
+                            style: MappingStyle::Flow,
+                            ..
+                        })
+                    );
+                self.open.push(OpenCollection {
+                    flow,
+                    mapping: matches!(event, Event::MappingStart(_)),
+                    len: 0,
+                });
+            }
+            Event::SequenceEnd | Event::MappingEnd => {
+                self.open.pop();
+            }
+            _ => {}
+        }
+        if let Event::Alias(_)
+        | Event::Scalar(_)
+        | Event::SequenceEnd
+        | Event::MappingEnd = event
+        {
+            if let Some(parent) = self.open.last_mut() {
+                parent.len += 1;
+            }
+        }
+
         let mut sys_event = MaybeUninit::<YamlEventT>::uninit();
         let sys_event = sys_event.as_mut_ptr();
         unsafe {
@@ -192,25 +462,35 @@ impl<'a> Emitter<'a> {
                     yaml_stream_end_event_initialize(sys_event)
                 }
                 Event::DocumentStart => {
-                    let version_directive = ptr::null_mut();
-                    let tag_directives_start = ptr::null_mut();
-                    let tag_directives_end = ptr::null_mut();
-                    let implicit = true;
+                    // libyml copies the directives while initializing the
+                    // event, so they only need to live until then.
+                    let mut directives = Directives::new(&self.config);
+                    let implicit = !self.config.explicit_start;
+                    let (tag_directives_start, tag_directives_end) =
+                        directives.tags();
                     yaml_document_start_event_initialize(
                         sys_event,
-                        version_directive,
+                        directives.version(),
                         tag_directives_start,
                         tag_directives_end,
                         implicit,
                     )
                 }
                 Event::DocumentEnd => {
-                    let implicit = true;
+                    let implicit = !self.config.explicit_end;
                     yaml_document_end_event_initialize(
                         sys_event, implicit,
                     )
                 }
+                Event::Alias(mut anchor) => {
+                    anchor.push('\0');
+                    yaml_alias_event_initialize(
+                        sys_event,
+                        anchor.as_ptr(),
+                    )
+                }
                 Event::Scalar(mut scalar) => {
+                    let anchor_ptr = nul_terminated(&mut scalar.anchor);
                     let tag_ptr = scalar.tag.as_mut().map_or_else(
                         ptr::null,
                         |tag| {
@@ -232,10 +512,16 @@ impl<'a> Emitter<'a> {
                         ScalarStyle::SingleQuoted => {
                             YamlSingleQuotedScalarStyle
                         }
+                        ScalarStyle::DoubleQuoted => {
+                            YamlScalarStyleT::YamlDoubleQuotedScalarStyle
+                        }
                         ScalarStyle::Literal => YamlLiteralScalarStyle,
+                        ScalarStyle::Folded => {
+                            YamlScalarStyleT::YamlFoldedScalarStyle
+                        }
                     };
                     let event_data = ScalarEventData {
-                        anchor: ptr::null(),
+                        anchor: anchor_ptr,
                         tag: tag_ptr,
                         value: value_ptr,
                         length,
@@ -247,6 +533,8 @@ impl<'a> Emitter<'a> {
                     yaml_scalar_event_initialize(sys_event, event_data)
                 }
                 Event::SequenceStart(mut sequence) => {
+                    let anchor_ptr =
+                        nul_terminated(&mut sequence.anchor);
                     let tag_ptr = sequence.tag.as_mut().map_or_else(
                         ptr::null,
                         |tag| {
@@ -255,19 +543,21 @@ impl<'a> Emitter<'a> {
                         },
                     );
                     let implicit = tag_ptr.is_null();
-                    let style = YamlAnySequenceStyle;
+                    let style = match sequence.style {
+                        SequenceStyle::Any => YamlAnySequenceStyle,
+                        SequenceStyle::Block => YamlBlockSequenceStyle,
+                        SequenceStyle::Flow => YamlFlowSequenceStyle,
+                    };
                     yaml_sequence_start_event_initialize(
-                        sys_event,
-                        ptr::null(),
-                        tag_ptr,
-                        implicit,
-                        style,
+                        sys_event, anchor_ptr, tag_ptr, implicit, style,
                     )
                 }
                 Event::SequenceEnd => {
                     yaml_sequence_end_event_initialize(sys_event)
                 }
                 Event::MappingStart(mut mapping) => {
+                    let anchor_ptr =
+                        nul_terminated(&mut mapping.anchor);
                     let tag_ptr = mapping.tag.as_mut().map_or_else(
                         ptr::null,
                         |tag| {
@@ -276,13 +566,13 @@ impl<'a> Emitter<'a> {
                         },
                     );
                     let implicit = tag_ptr.is_null();
-                    let style = YamlAnyMappingStyle;
+                    let style = match mapping.style {
+                        MappingStyle::Any => YamlAnyMappingStyle,
+                        MappingStyle::Block => YamlBlockMappingStyle,
+                        MappingStyle::Flow => YamlFlowMappingStyle,
+                    };
                     yaml_mapping_start_event_initialize(
-                        sys_event,
-                        ptr::null(),
-                        tag_ptr,
-                        implicit,
-                        style,
+                        sys_event, anchor_ptr, tag_ptr, implicit, style,
                     )
                 }
                 Event::MappingEnd => {
@@ -298,6 +588,23 @@ impl<'a> Emitter<'a> {
                 return Err(self.error());
             }
         }
+        if ends_document {
+            self.finish_layout()?;
+        }
+        Ok(())
+    }
+
+    /// Passes the buffered document through the output stage and on to
+    /// the writer. libyml flushes at the end of every document, so the
+    /// buffer never holds a partial document at this point.
+    fn finish_layout(&mut self) -> Result<(), Error> {
+        let pinned = unsafe { &mut *self.pin.ptr };
+        if let (Some(layout), Some(write)) =
+            (&mut pinned.layout, &mut pinned.write)
+        {
+            let output = layout.finish();
+            write.write_all(&output).map_err(Error::Io)?;
+        }
         Ok(())
     }
 
@@ -309,14 +616,22 @@ impl<'a> Emitter<'a> {
                 return Err(self.error());
             }
         }
-        Ok(())
+        let pinned = unsafe { &mut *self.pin.ptr };
+        match &mut pinned.write {
+            Some(write) => write.flush().map_err(Error::Io),
+            None => Ok(()),
+        }
     }
 
     /// Retrieves the inner writer from the YAML emitter.
-    #[allow(unused_mut)]
-    pub(crate) fn into_inner(mut self) -> Box<dyn io::Write + 'a> {
-        let sink = Box::new(io::sink());
-        unsafe { mem::replace(&mut (*self.pin.ptr).write, sink) }
+    pub(crate) fn into_inner(self) -> W {
+        let pinned = unsafe { &mut *self.pin.ptr };
+        match pinned.write.take() {
+            Some(write) => write,
+            // Only `into_inner` takes the writer, and it consumes the
+            // emitter.
+            None => unreachable!(),
+        }
     }
 
     /// Retrieves the error from the YAML emitter.
@@ -332,16 +647,36 @@ impl<'a> Emitter<'a> {
     }
 }
 
+/// Appends a nul terminator to an optional string and returns a pointer
+/// to it, or null if there is no string.
+fn nul_terminated(string: &mut Option<String>) -> *const u8 {
+    string.as_mut().map_or_else(ptr::null, |string| {
+        string.push('\0');
+        string.as_ptr()
+    })
+}
+
 /// Writes data to a buffer using a provided callback function.
-unsafe fn write_handler(
+unsafe fn write_handler<W>(
     data: *mut c_void,
     buffer: *mut u8,
     size: u64,
-) -> i32 {
-    let data = data.cast::<EmitterPinned<'_>>();
-    match io::Write::write_all(unsafe { &mut *(*data).write }, unsafe {
-        slice::from_raw_parts(buffer, size as usize)
-    }) {
+) -> i32
+where
+    W: io::Write,
+{
+    let data = data.cast::<EmitterPinned<W>>();
+    let buffer =
+        unsafe { slice::from_raw_parts(buffer, size as usize) };
+    if let Some(layout) = unsafe { &mut (*data).layout } {
+        layout.buffer.extend_from_slice(buffer);
+        return 1;
+    }
+    let write = match unsafe { &mut (*data).write } {
+        Some(write) => write,
+        None => return 0,
+    };
+    match write.write_all(buffer) {
         Ok(()) => 1,
         Err(err) => {
             unsafe {
@@ -352,7 +687,7 @@ unsafe fn write_handler(
     }
 }
 
-impl Drop for EmitterPinned<'_> {
+impl<W> Drop for EmitterPinned<W> {
     /// Drops the YAML emitter, deallocating resources.
     fn drop(&mut self) {
         unsafe { yaml_emitter_delete(&mut self.sys) }
@@ -0,0 +1,478 @@
+// Copyright notice and licensing information.
+// These lines indicate the copyright of the software and its licensing terms.
+// SPDX-License-Identifier: Apache-2.0 OR MIT indicates dual licensing under Apache 2.0 or MIT licenses.
+// Copyright © 2024 Serde YML, Seamless YAML Serialization for Rust. All rights reserved.
+
+use crate::libyml::emitter::EmitterConfig;
+use crate::libyml::parser::{Event, Parser};
+use std::{borrow::Cow, mem};
+
+/// Output stage for layout that libyml's emitter cannot produce.
+///
+/// When enabled, the emitter writes each document into `buffer` instead
+/// of the final writer. Once the document is complete the buffer is
+/// parsed again, which gives the position of every node, and the lines
+/// are rewritten before they reach the writer.
+#[derive(Debug)]
+pub(crate) struct Layout {
+    indent: usize,
+    indent_sequences: bool,
+    flow_keys: bool,
+    line_break: &'static [u8],
+    header_comment: Option<String>,
+    comments: Vec<(Vec<Segment>, String)>,
+    pub(crate) buffer: Vec<u8>,
+}
+
+/// One step of a comment path: a mapping key or a sequence index.
+#[derive(Debug, PartialEq, Eq, Clone)]
+enum Segment {
+    Key(Box<[u8]>),
+    Index(usize),
+}
+
+/// Changes to the lines of a document, found by parsing it.
+#[derive(Default)]
+struct Edits<'a> {
+    /// Ranges of lines, end exclusive, to indent by one more level.
+    shifts: Vec<(usize, usize)>,
+    /// Comments to write before a line, with the number of that line.
+    comments: Vec<(usize, &'a str)>,
+    /// Explicit keys to rewrite as implicit ones.
+    joins: Vec<Join>,
+}
+
+/// A flow collection key written by libyml after `? `, with its value
+/// after `:` on the next line, that fits on one line as an implicit key.
+#[derive(Debug)]
+struct Join {
+    /// The line of the `?`.
+    line: usize,
+    /// The column of the `?`, and of the `:` on the next line.
+    column: usize,
+    /// Whether the value is a block collection whose first entry is on
+    /// the line of the `:`. That entry is moved to a line of its own.
+    block_value: bool,
+}
+
+impl Layout {
+    /// Creates the output stage, or returns `None` if the requested
+    /// layout is exactly what libyaml writes on its own.
+    pub(crate) fn new(config: &EmitterConfig) -> Option<Layout> {
+        if !config.indent_sequences
+            && !config.flow_keys
+            && config.header_comment.is_none()
+            && config.comments.is_empty()
+        {
+            return None;
+        }
+        Some(Layout {
+            indent: config.indent,
+            indent_sequences: config.indent_sequences,
+            flow_keys: config.flow_keys,
+            line_break: config.line_break,
+            header_comment: config.header_comment.clone(),
+            comments: config
+                .comments
+                .iter()
+                .map(|(path, comment)| {
+                    (parse_path(path), comment.clone())
+                })
+                .collect(),
+            buffer: Vec::new(),
+        })
+    }
+
+    /// Takes everything buffered so far and returns it with the layout
+    /// applied.
+    pub(crate) fn finish(&mut self) -> Vec<u8> {
+        let input = mem::take(&mut self.buffer);
+        let mut lines: Vec<Cow<'_, [u8]>> = split_lines(&input)
+            .into_iter()
+            .map(Cow::Borrowed)
+            .collect();
+
+        // A document end marker left open by the previous document is
+        // written at the start of this one, and cannot be parsed on its
+        // own. Pass it through untouched.
+        let skip = lines
+            .iter()
+            .take_while(|line| trim_break(line) == b"...")
+            .count();
+        let offset: usize = lines[..skip].iter().map(|l| l.len()).sum();
+
+        let edits = match self.edits(&input[offset..]) {
+            Some(edits) => edits,
+            None => return input,
+        };
+        let mut shift = vec![0; lines.len()];
+        for (start, end) in edits.shifts {
+            let end = (end + skip).min(lines.len());
+            for shift in &mut shift[start + skip..end] {
+                *shift += self.indent;
+            }
+        }
+        let mut comments = vec![Vec::new(); lines.len()];
+        for (line, comment) in edits.comments {
+            if let Some(comments) = comments.get_mut(line + skip) {
+                comments.push(comment);
+            }
+        }
+        for join in edits.joins {
+            let line = join.line + skip;
+            let moved = mem::take(&mut comments[line + 1]);
+            comments[line].extend(moved);
+            let key = trim_break(&lines[line]);
+            let value = &lines[line + 1];
+            let mut joined = key[..join.column].to_vec();
+            joined.extend_from_slice(&key[join.column + 2..]);
+            joined.push(b':');
+            if join.block_value {
+                joined.extend_from_slice(
+                    &value[trim_break(value).len()..],
+                );
+                let mut value = value.to_vec();
+                value[join.column] = b' ';
+                lines[line + 1] = Cow::Owned(value);
+            } else {
+                joined.extend_from_slice(&value[join.column + 1..]);
+                lines[line + 1] = Cow::Borrowed(&[]);
+            }
+            lines[line] = Cow::Owned(joined);
+        }
+
+        let mut output = Vec::with_capacity(input.len());
+        for ((line, shift), comments) in
+            lines.iter().zip(shift).zip(comments)
+        {
+            let content = trim_break(line);
+            let line_break = match &line[content.len()..] {
+                b"" => self.line_break,
+                line_break => line_break,
+            };
+            let indentation = shift
+                + content.iter().take_while(|&&b| b == b' ').count();
+            for comment in comments {
+                for text in comment.lines() {
+                    output.resize(output.len() + indentation, b' ');
+                    output.push(b'#');
+                    if !text.is_empty() {
+                        output.push(b' ');
+                        output.extend_from_slice(text.as_bytes());
+                    }
+                    output.extend_from_slice(line_break);
+                }
+            }
+            if !content.is_empty() {
+                output.resize(output.len() + shift, b' ');
+            }
+            output.extend_from_slice(line);
+        }
+        output
+    }
+
+    /// Parses the document to find the changes to make to its lines:
+    ///
+    /// - every block sequence that is the value of a mapping entry is
+    ///   shifted, from its first item up to the end of the sequence;
+    /// - the header comment goes before the line where the root node
+    ///   starts;
+    /// - the comment of a mapping value goes before the line of its key,
+    ///   and the comment of any other node before the line where the node
+    ///   starts;
+    /// - with flow keys, every collection key written after `? ` that fits
+    ///   on its line is joined with the `:` of its value.
+    fn edits(&self, input: &[u8]) -> Option<Edits<'_>> {
+        enum Frame {
+            Sequence {
+                path: Option<Vec<Segment>>,
+                map_value: bool,
+                first: bool,
+                start: Option<usize>,
+                index: usize,
+            },
+            Mapping {
+                path: Option<Vec<Segment>>,
+                key: bool,
+                key_line: usize,
+                key_value: Option<Box<[u8]>>,
+                /// The column of a key that is a collection.
+                collection_key: Option<usize>,
+            },
+        }
+
+        let lines = split_lines(input);
+        let mut parser = Parser::new(Cow::Borrowed(input));
+        let mut stack = Vec::new();
+        let mut edits = Edits::default();
+        loop {
+            let (event, mark) = parser.parse_next_event().ok()?;
+            let line = mark.line() as usize;
+            match event {
+                Event::StreamEnd => return Some(edits),
+                Event::StreamStart
+                | Event::DocumentStart
+                | Event::DocumentEnd => continue,
+                Event::SequenceEnd | Event::MappingEnd => {
+                    if let Some(Frame::Sequence {
+                        start: Some(start),
+                        ..
+                    }) = stack.pop()
+                    {
+                        edits.shifts.push((start, line));
+                    }
+                    if let Some(Frame::Mapping { key, .. }) =
+                        stack.last_mut()
+                    {
+                        *key = !*key;
+                    }
+                    continue;
+                }
+                Event::Alias(_)
+                | Event::Scalar(_)
+                | Event::SequenceStart(_)
+                | Event::MappingStart(_) => {}
+            }
+
+            // Where the node is in the document, and the line that its
+            // comment goes before.
+            let mut map_value = false;
+            let (path, comment_line) = match stack.last_mut() {
+                None => {
+                    if let Some(header) = &self.header_comment {
+                        edits.comments.push((line, header));
+                    }
+                    (Some(Vec::new()), line)
+                }
+                Some(Frame::Sequence {
+                    path,
+                    map_value,
+                    first,
+                    start,
+                    index,
+                }) => {
+                    if *map_value && *first {
+                        let column = mark.column() as usize;
+                        let prefix =
+                            lines.get(line).map_or(&[][..], |l| {
+                                &l[..column.min(l.len())]
+                            });
+                        if self.indent_sequences
+                            && prefix
+                                .iter()
+                                .all(|&b| b == b' ' || b == b'-')
+                        {
+                            *start = Some(line);
+                        }
+                    }
+                    *first = false;
+                    let path = path.clone().map(|mut path| {
+                        path.push(Segment::Index(*index));
+                        path
+                    });
+                    *index += 1;
+                    (path, line)
+                }
+                Some(Frame::Mapping {
+                    path,
+                    key,
+                    key_line,
+                    key_value,
+                    collection_key,
+                }) => {
+                    let is_key = *key;
+                    if !matches!(
+                        event,
+                        Event::SequenceStart(_)
+                            | Event::MappingStart(_)
+                    ) {
+                        *key = !*key;
+                    }
+                    if is_key {
+                        *key_line = line;
+                        *collection_key = match event {
+                            Event::SequenceStart(_)
+                            | Event::MappingStart(_) => {
+                                Some(mark.column() as usize)
+                            }
+                            _ => None,
+                        };
+                        *key_value = match &event {
+                            Event::Scalar(scalar) => {
+                                Some(scalar.value.clone())
+                            }
+                            _ => None,
+                        };
+                        // Keys have no path, and neither has anything
+                        // inside them.
+                        (None, line)
+                    } else {
+                        map_value = true;
+                        if let Some(column) = collection_key.take() {
+                            let block_value = match event {
+                                Event::SequenceStart(_)
+                                | Event::MappingStart(_) => {
+                                    Some(mark.column() as usize)
+                                }
+                                _ => None,
+                            };
+                            edits.joins.extend(self.join(
+                                &lines,
+                                *key_line,
+                                column,
+                                (line, block_value),
+                            ));
+                        }
+                        let path = match (path, key_value) {
+                            (Some(path), Some(key_value)) => {
+                                let mut path = path.clone();
+                                path.push(Segment::Key(
+                                    key_value.clone(),
+                                ));
+                                Some(path)
+                            }
+                            _ => None,
+                        };
+                        (path, *key_line)
+                    }
+                }
+            };
+
+            if let Some(path) = &path {
+                for (comment_path, comment) in &self.comments {
+                    if comment_path == path {
+                        edits.comments.push((comment_line, comment));
+                    }
+                }
+            }
+
+            match event {
+                Event::SequenceStart(_) => {
+                    stack.push(Frame::Sequence {
+                        path,
+                        map_value,
+                        first: true,
+                        start: None,
+                        index: 0,
+                    })
+                }
+                Event::MappingStart(_) => stack.push(Frame::Mapping {
+                    path,
+                    key: true,
+                    key_line: line,
+                    key_value: None,
+                    collection_key: None,
+                }),
+                _ => {}
+            }
+        }
+    }
+
+    /// Checks whether a collection key that starts at `column` of
+    /// `key_line` can be rewritten as an implicit key, given the line and
+    /// the column of the value if the value is a collection.
+    fn join(
+        &self,
+        lines: &[&[u8]],
+        key_line: usize,
+        column: usize,
+        (value_line, value_column): (usize, Option<usize>),
+    ) -> Option<Join> {
+        // YAML limits implicit keys to 1024 characters.
+        const MAX_KEY_LEN: usize = 1024;
+
+        if !self.flow_keys || column < 2 {
+            return None;
+        }
+        let key = trim_break(lines.get(key_line)?);
+        let next = trim_break(lines.get(key_line + 1)?);
+        let question = column - 2;
+        let explicit = key.get(question..column) == Some(b"? ")
+            && key.len() - column <= MAX_KEY_LEN
+            && next.get(question) == Some(&b':')
+            && next[..question].iter().all(|&b| b == b' ');
+        if !explicit {
+            return None;
+        }
+        // A block collection value either starts after the `:`, and is
+        // moved to a line of its own, or on a later line after its tag
+        // or anchor, where it can stay.
+        let block_value = value_column.map_or(false, |value_column| {
+            value_line == key_line + 1
+                && !matches!(
+                    next.get(value_column),
+                    Some(b'[' | b'{' | b'!' | b'&')
+                )
+        });
+        Some(Join {
+            line: key_line,
+            column: question,
+            block_value,
+        })
+    }
+}
+
+/// Parses a comment path such as `servers[0].ports`. Keys are separated
+/// by `.` and sequence indices written as `[n]`. An empty path is the
+/// root of the document.
+fn parse_path(path: &str) -> Vec<Segment> {
+    let mut segments = Vec::new();
+    if path.is_empty() {
+        return segments;
+    }
+    for part in path.split('.') {
+        let mut key = part;
+        let mut indices = Vec::new();
+        while let Some(open) = key.rfind('[') {
+            let index = key
+                .strip_suffix(']')
+                .and_then(|rest| rest[open + 1..].parse().ok());
+            match index {
+                Some(index) => {
+                    indices.push(Segment::Index(index));
+                    key = &key[..open];
+                }
+                None => break,
+            }
+        }
+        if !key.is_empty() || indices.is_empty() {
+            segments.push(Segment::Key(key.as_bytes().into()));
+        }
+        segments.extend(indices.into_iter().rev());
+    }
+    segments
+}
+
+/// Splits the input into lines, keeping each line break.
+fn split_lines(input: &[u8]) -> Vec<&[u8]> {
+    let mut lines = Vec::new();
+    let mut start = 0;
+    let mut i = 0;
+    while i < input.len() {
+        match input[i] {
+            b'\r' if input.get(i + 1) == Some(&b'\n') => {
+                lines.push(&input[start..i + 2]);
+                i += 2;
+                start = i;
+            }
+            b'\r' | b'\n' => {
+                lines.push(&input[start..=i]);
+                i += 1;
+                start = i;
+            }
+            _ => i += 1,
+        }
+    }
+    if start < input.len() {
+        lines.push(&input[start..]);
+    }
+    lines
+}
+
+/// Strips the line break from the end of a line.
+fn trim_break(mut line: &[u8]) -> &[u8] {
+    while let [rest @ .., b'\r' | b'\n'] = line {
+        line = rest;
+    }
+    line
+}
@@ -9,6 +9,8 @@ mod cstr;
 pub mod emitter;
 /// Error handling.
 pub mod error;
+/// Output layout post-processing.
+mod layout;
 /// YAML parser.
 pub mod parser;
 /// Tag directive handling.
@@ -13,9 +13,11 @@ use crate::libyml::{
 use libyml as sys;
 use std::{
     borrow::Cow,
+    ffi::c_void,
     fmt::{self, Debug},
+    io,
     mem::MaybeUninit,
-    ptr::{addr_of_mut, NonNull},
+    ptr::{self, addr_of_mut, NonNull},
     slice,
 };
 
@@ -89,6 +91,30 @@ pub struct ParserPinned<'input> {
     /// The `'input` lifetime parameter indicates the lifetime of the borrowed
     /// input data, if any.
     input: Cow<'input, [u8]>,
+
+    /// The reader that the input is read from in chunks as it is parsed,
+    /// if it is not given in full.
+    read: Option<Reader<'input>>,
+
+    /// The number of bytes read from `read` so far.
+    read_len: usize,
+
+    /// The error that reading from `read` failed with, if any.
+    read_error: Option<io::Error>,
+
+    /// The major and minor version in the `%YAML` directive of the
+    /// document that the last `DocumentStart` event started, if it has
+    /// one.
+    version: Option<(u8, u8)>,
+}
+
+/// The reader of a [`Parser`] created by [`Parser::from_reader`].
+struct Reader<'input>(Box<dyn io::Read + 'input>);
+
+impl Debug for Reader<'_> {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("Box<dyn io::Read>")
+    }
 }
 
 /// Represents a YAML event encountered during parsing.
@@ -237,11 +263,71 @@ impl<'input> Parser<'input> {
                 input.len() as u64,
             );
             addr_of_mut!((*owned.ptr).input).write(input);
+            addr_of_mut!((*owned.ptr).read).write(None);
+            addr_of_mut!((*owned.ptr).read_len).write(0);
+            addr_of_mut!((*owned.ptr).read_error).write(None);
+            addr_of_mut!((*owned.ptr).version).write(None);
+            Owned::assume_init(owned)
+        };
+        Parser { pin }
+    }
+
+    /// Creates a new `Parser` instance that reads its input from `read`
+    /// in chunks as it parses, instead of reading it all up front.
+    ///
+    /// The events of such a parser have no `repr`, as the input they
+    /// were parsed from is not kept.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if there is an error initializing the underlying `libyml` parser.
+    pub fn from_reader(
+        read: Box<dyn io::Read + 'input>,
+    ) -> Parser<'input> {
+        let owned = Owned::<ParserPinned<'input>>::new_uninit();
+        let pin = unsafe {
+            let parser = addr_of_mut!((*owned.ptr).sys);
+            if sys::yaml_parser_initialize(parser).fail {
+                panic!("malloc error: {}", Error::parse_error(parser));
+            }
+            sys::yaml_parser_set_encoding(
+                parser,
+                sys::YamlUtf8Encoding,
+            );
+            sys::yaml_parser_set_input(
+                parser,
+                read_handler,
+                owned.ptr.cast(),
+            );
+            addr_of_mut!((*owned.ptr).input).write(Cow::Borrowed(&[]));
+            addr_of_mut!((*owned.ptr).read).write(Some(Reader(read)));
+            addr_of_mut!((*owned.ptr).read_len).write(0);
+            addr_of_mut!((*owned.ptr).read_error).write(None);
+            addr_of_mut!((*owned.ptr).version).write(None);
             Owned::assume_init(owned)
         };
         Parser { pin }
     }
 
+    /// The number of bytes read so far by a parser created by
+    /// [`Parser::from_reader`].
+    pub(crate) fn read_len(&self) -> usize {
+        unsafe { (*self.pin.ptr).read_len }
+    }
+
+    /// Takes the error that reading the input failed with, which is
+    /// what made parsing fail with an input error.
+    pub(crate) fn take_read_error(&mut self) -> Option<io::Error> {
+        unsafe { (*self.pin.ptr).read_error.take() }
+    }
+
+    /// The major and minor version in the `%YAML` directive of the
+    /// document that the last [`Event::DocumentStart`] started, if it
+    /// has one.
+    pub(crate) fn document_version(&self) -> Option<(u8, u8)> {
+        unsafe { (*self.pin.ptr).version }
+    }
+
     /// Parses the next YAML event from the input.
     ///
     /// Returns a `Result` containing the parsed `Event` and its corresponding `Mark` on success,
@@ -259,6 +345,14 @@ impl<'input> Parser<'input> {
             if sys::yaml_parser_parse(parser, event).fail {
                 return Err(Error::parse_error(parser));
             }
+            if (*event).type_ == sys::YamlDocumentStartEvent {
+                let version =
+                    (*event).data.document_start.version_directive;
+                (*self.pin.ptr).version =
+                    version.as_ref().map(|version| {
+                        (version.major as u8, version.minor as u8)
+                    });
+            }
             let ret = convert_event(&*event, &(*self.pin.ptr).input);
             let mark = Mark {
                 sys: (*event).start_mark,
@@ -349,7 +443,45 @@ unsafe fn optional_repr<'input>(
 ) -> Option<&'input [u8]> {
     let start = sys.start_mark.index as usize;
     let end = sys.end_mark.index as usize;
-    Some(&input[start..end])
+    // The input of a parser that reads in chunks is not kept.
+    input.get(start..end)
+}
+
+/// Reads the next chunk of the input of a [`Parser`] created by
+/// [`Parser::from_reader`].
+unsafe fn read_handler(
+    data: *mut c_void,
+    buffer: *mut u8,
+    size: u64,
+    size_read: *mut u64,
+) -> i32 {
+    let data = data.cast::<ParserPinned<'_>>();
+    let read = match unsafe { &mut (*data).read } {
+        Some(read) => read,
+        None => return 0,
+    };
+    let buffer = unsafe {
+        ptr::write_bytes(buffer, 0, size as usize);
+        slice::from_raw_parts_mut(buffer, size as usize)
+    };
+    loop {
+        match read.0.read(buffer) {
+            Ok(len) => {
+                unsafe {
+                    (*data).read_len += len;
+                    *size_read = len as u64;
+                }
+                return 1;
+            }
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+            Err(err) => {
+                unsafe {
+                    (*data).read_error = Some(err);
+                }
+                return 0;
+            }
+        }
+    }
 }
 
 impl Debug for Scalar<'_> {
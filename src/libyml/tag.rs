@@ -27,6 +27,18 @@ impl Tag {
     /// The float tag, representing a floating-point value.
     pub const FLOAT: &'static str = "tag:yaml.org,2002:float";
 
+    /// The binary tag, representing base64-encoded binary data.
+    pub const BINARY: &'static str = "tag:yaml.org,2002:binary";
+
+    /// The str tag, representing a string.
+    pub const STR: &'static str = "tag:yaml.org,2002:str";
+
+    /// The seq tag, representing a sequence.
+    pub const SEQ: &'static str = "tag:yaml.org,2002:seq";
+
+    /// The map tag, representing a mapping.
+    pub const MAP: &'static str = "tag:yaml.org,2002:map";
+
     /// Checks if the tag starts with the given prefix.
     ///
     /// # Arguments
@@ -4,12 +4,13 @@
 // Copyright © 2024 Serde YML, Seamless YAML Serialization for Rust. All rights reserved.
 
 use crate::{
-    de::{Event, Progress},
+    de::{Event, Limits, Progress},
     libyml::{
         error::Mark,
-        parser::{Event as YamlEvent, Parser},
+        parser::{Anchor, Event as YamlEvent, Parser},
     },
     modules::error::{self, Error, ErrorImpl, Result},
+    ser::YamlVersion,
 };
 use std::{borrow::Cow, collections::BTreeMap, io::Read, sync::Arc};
 
@@ -29,6 +30,13 @@ pub struct Loader<'input> {
     ///
     /// This field keeps track of the number of YAML documents encountered during parsing.
     pub parsed_document_count: usize,
+
+    /// The limits on the input that parsing stops at.
+    limits: Limits,
+
+    /// The version in the `%YAML` directive of the document last
+    /// returned by [`Loader::next_document`], if it has one.
+    document_version: Option<YamlVersion>,
 }
 
 /// Represents a YAML document.
@@ -88,27 +96,56 @@ impl<'input> Loader<'input> {
     /// assert!(loader_result.is_ok());
     /// ```
     pub fn new(progress: Progress<'input>) -> Result<Self> {
+        Loader::with_limits(progress, Limits::default())
+    }
+
+    /// Constructs a `Loader` that stops with an error at the first point
+    /// where the input exceeds one of `limits`.
+    ///
+    /// Input from a reader is read in chunks as it is parsed rather than
+    /// all up front.
+    pub(crate) fn with_limits(
+        progress: Progress<'input>,
+        limits: Limits,
+    ) -> Result<Self> {
         let input = match progress {
-            Progress::Str(s) => Cow::Borrowed(s.as_bytes()),
-            Progress::Slice(bytes) => Cow::Borrowed(bytes),
-            Progress::Read(mut rdr) => {
-                let mut buffer = Vec::new();
-                if let Err(io_error) = rdr.read_to_end(&mut buffer) {
-                    return Err(error::new(ErrorImpl::IoError(
-                        io_error,
-                    )));
-                }
-                Cow::Owned(buffer)
+            Progress::Str(s) => s.as_bytes(),
+            Progress::Slice(bytes) => bytes,
+            Progress::Read(rdr) => {
+                let parser = match limits.input_size {
+                    // Reading one byte past the limit is enough to tell
+                    // that the input exceeds it.
+                    Some(limit) => Parser::from_reader(Box::new(
+                        rdr.take((limit as u64).saturating_add(1)),
+                    )),
+                    None => Parser::from_reader(rdr),
+                };
+                return Ok(Loader {
+                    parser: Some(parser),
+                    parsed_document_count: 0,
+                    limits,
+                    document_version: None,
+                });
             }
             Progress::Iterable(_) | Progress::Document(_) => {
                 unreachable!()
             }
             Progress::Fail(err) => return Err(error::shared(err)),
         };
+        if let Some(limit) = limits.input_size {
+            if input.len() > limit {
+                return Err(error::new(
+                    ErrorImpl::InputSizeLimitExceeded(limit),
+                ));
+            }
+        }
+        let parser = Parser::new(Cow::Borrowed(input));
 
         Ok(Loader {
-            parser: Some(Parser::new(input)),
+            parser: Some(parser),
             parsed_document_count: 0,
+            limits,
+            document_version: None,
         })
     }
 
@@ -132,33 +169,42 @@ impl<'input> Loader<'input> {
     /// assert_eq!(document.events.len(), 4);
     /// ```
     pub fn next_document(&mut self) -> Option<Document<'input>> {
-        let parser = match &mut self.parser {
-            Some(parser) => parser,
-            None => return None,
-        };
+        self.parser.as_ref()?;
 
         let first = self.parsed_document_count == 0;
         self.parsed_document_count += 1;
 
-        let mut anchors = BTreeMap::new();
+        let mut state = DocumentState::default();
         let mut document = Document {
             events: Vec::new(),
             error: None,
             anchor_event_map: BTreeMap::new(),
         };
+        self.document_version = None;
 
         loop {
-            let (event, mark) = match parser.parse_next_event() {
-                Ok((event, mark)) => (event, mark),
-                Err(err) => {
-                    document.error = Some(Error::from(err).shared());
+            let step = match self.next_step(&mut state) {
+                Some(Ok(step)) => step,
+                Some(Err(err)) => {
+                    document.error = Some(err.shared());
                     return Some(document);
                 }
+                None => return None,
             };
-            let event = match event {
-                YamlEvent::StreamStart => continue,
-                YamlEvent::StreamEnd => {
-                    self.parser = None;
+            match step {
+                Step::DocumentStart(version) => {
+                    self.document_version = version;
+                }
+                Step::Event(event, mark, anchor) => {
+                    if let Some(id) = anchor {
+                        document
+                            .anchor_event_map
+                            .insert(id, document.events.len());
+                    }
+                    document.events.push((event, mark));
+                }
+                Step::DocumentEnd => return Some(document),
+                Step::StreamEnd(mark) => {
                     return if first {
                         if document.events.is_empty() {
                             document.events.push((Event::Void, mark));
@@ -168,52 +214,173 @@ impl<'input> Loader<'input> {
                         None
                     };
                 }
-                YamlEvent::DocumentStart => continue,
-                YamlEvent::DocumentEnd => return Some(document),
-                YamlEvent::Alias(alias) => match anchors.get(&alias) {
-                    Some(id) => Event::Alias(*id),
-                    None => {
-                        document.error = Some(
-                            error::new(ErrorImpl::UnknownAnchor(mark))
-                                .shared(),
-                        );
-                        return Some(document);
+            }
+        }
+    }
+
+    /// The version in the `%YAML` directive of the document last
+    /// returned by [`Loader::next_document`], if it has one.
+    pub(crate) fn document_version(&self) -> Option<YamlVersion> {
+        self.document_version
+    }
+
+    /// Parses the next event of the document described by `state`, so
+    /// that a document can be deserialized as it is parsed instead of
+    /// from the events of a whole [`Document`].
+    ///
+    /// Returns `None` once the stream has ended, either at its end or at
+    /// an input that exceeds one of the limits of the loader.
+    pub(crate) fn next_step(
+        &mut self,
+        state: &mut DocumentState,
+    ) -> Option<Result<Step<'input>>> {
+        let limits = self.limits;
+        loop {
+            let parser = self.parser.as_mut()?;
+            let result = parser.parse_next_event();
+            if let Some(limit) = limits.input_size {
+                if parser.read_len() > limit {
+                    return self.exceeded(
+                        ErrorImpl::InputSizeLimitExceeded(limit),
+                    );
+                }
+            }
+            let (event, mark) = match result {
+                Ok((event, mark)) => (event, mark),
+                Err(err) => {
+                    return Some(Err(match parser.take_read_error() {
+                        Some(io_error) => {
+                            error::new(ErrorImpl::IoError(io_error))
+                        }
+                        None => Error::from(err),
+                    }));
+                }
+            };
+            let mut anchor = None;
+            let event = match event {
+                YamlEvent::StreamStart => continue,
+                YamlEvent::StreamEnd => {
+                    self.parser = None;
+                    return Some(Ok(Step::StreamEnd(mark)));
+                }
+                YamlEvent::DocumentStart => {
+                    let version = match parser.document_version() {
+                        Some((1, 1)) => Some(YamlVersion::V1_1),
+                        Some((1, 2)) => Some(YamlVersion::V1_2),
+                        _ => None,
+                    };
+                    return Some(Ok(Step::DocumentStart(version)));
+                }
+                YamlEvent::DocumentEnd => {
+                    return Some(Ok(Step::DocumentEnd));
+                }
+                YamlEvent::Alias(alias) => {
+                    match state.anchors.get(&alias) {
+                        Some(id) => Event::Alias(*id),
+                        None => {
+                            return Some(Err(error::new(
+                                ErrorImpl::UnknownAnchor(mark),
+                            )));
+                        }
                     }
-                },
+                }
                 YamlEvent::Scalar(mut scalar) => {
-                    if let Some(anchor) = scalar.anchor.take() {
-                        let id = anchors.len();
-                        anchors.insert(anchor, id);
-                        document
-                            .anchor_event_map
-                            .insert(id, document.events.len());
-                    }
+                    anchor = scalar.anchor.take();
                     Event::Scalar(scalar)
                 }
                 YamlEvent::SequenceStart(mut sequence_start) => {
-                    if let Some(anchor) = sequence_start.anchor.take() {
-                        let id = anchors.len();
-                        anchors.insert(anchor, id);
-                        document
-                            .anchor_event_map
-                            .insert(id, document.events.len());
-                    }
+                    anchor = sequence_start.anchor.take();
                     Event::SequenceStart(sequence_start)
                 }
                 YamlEvent::SequenceEnd => Event::SequenceEnd,
                 YamlEvent::MappingStart(mut mapping_start) => {
-                    if let Some(anchor) = mapping_start.anchor.take() {
-                        let id = anchors.len();
-                        anchors.insert(anchor, id);
-                        document
-                            .anchor_event_map
-                            .insert(id, document.events.len());
-                    }
+                    anchor = mapping_start.anchor.take();
                     Event::MappingStart(mapping_start)
                 }
                 YamlEvent::MappingEnd => Event::MappingEnd,
             };
-            document.events.push((event, mark));
+            if let Event::Alias(_) = event {
+                state.aliases += 1;
+            }
+            let exceeded = match &event {
+                Event::Alias(_) => limits
+                    .aliases
+                    .filter(|limit| state.aliases > *limit)
+                    .map(|limit| {
+                        ErrorImpl::AliasLimitExceeded(limit, mark)
+                    }),
+                Event::Scalar(scalar) => limits
+                    .scalar_length
+                    .filter(|limit| scalar.value.len() > *limit)
+                    .map(|limit| {
+                        ErrorImpl::ScalarLengthLimitExceeded(
+                            limit, mark,
+                        )
+                    }),
+                _ => None,
+            }
+            .or_else(|| {
+                limits
+                    .events
+                    .filter(|limit| state.events >= *limit)
+                    .map(|limit| {
+                        ErrorImpl::EventLimitExceeded(limit, mark)
+                    })
+            });
+            if let Some(error) = exceeded {
+                return self.exceeded(error);
+            }
+            state.events += 1;
+            let anchor = anchor.map(|anchor| {
+                let id = state.anchors.len();
+                state.anchors.insert(anchor, id);
+                id
+            });
+            return Some(Ok(Step::Event(event, mark, anchor)));
         }
     }
+
+    /// Ends the stream at an input that exceeds one of the limits of the
+    /// loader.
+    fn exceeded(
+        &mut self,
+        error: ErrorImpl,
+    ) -> Option<Result<Step<'input>>> {
+        self.parser = None;
+        Some(Err(error::new(error)))
+    }
+}
+
+/// The state of the document that a [`Loader`] is parsing the events
+/// of one at a time with [`Loader::next_step`].
+#[derive(Debug, Default)]
+pub(crate) struct DocumentState {
+    /// Map from anchor name to the id that aliases refer to it by.
+    anchors: BTreeMap<Anchor, usize>,
+    /// The number of events of the document parsed so far.
+    events: usize,
+    /// The number of aliases of the document parsed so far.
+    aliases: usize,
+}
+
+impl DocumentState {
+    /// The number of events of the document parsed so far.
+    pub(crate) fn events(&self) -> usize {
+        self.events
+    }
+}
+
+/// A step of parsing a document one event at a time.
+#[derive(Debug)]
+pub(crate) enum Step<'input> {
+    /// The start of a document, with the version in its `%YAML`
+    /// directive.
+    DocumentStart(Option<YamlVersion>),
+    /// An event of the document, with the id of its anchor if it has
+    /// one.
+    Event(Event<'input>, Mark, Option<usize>),
+    /// The end of a document.
+    DocumentEnd,
+    /// The end of the stream.
+    StreamEnd(Mark),
 }
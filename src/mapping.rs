@@ -183,6 +183,25 @@ impl Mapping {
         self.map.retain(keep);
     }
 
+    /// Sorts the entries of this mapping by key, and those of every
+    /// mapping inside its keys and values.
+    ///
+    /// Keys are compared in the order of the [`PartialOrd`] implementation
+    /// of [`Value`], extended to a total order in which NaN comes after
+    /// every other float. This is also the order given by
+    /// [`SortKeys`](crate::SortKeys) when serializing.
+    pub fn sort_keys_recursive(&mut self) {
+        self.map = mem::take(&mut self.map)
+            .into_iter()
+            .map(|(mut k, mut v)| {
+                k.sort_keys_recursive();
+                v.sort_keys_recursive();
+                (k, v)
+            })
+            .collect();
+        self.map.sort_by(|a, _, b, _| total_cmp(a, b));
+    }
+
     /// Returns the maximum number of key-value pairs the map can hold without
     /// reallocating.
     #[inline]
@@ -305,6 +324,7 @@ impl indexmap::Equivalent<Value> for HashLikeValue<'_> {
     fn equivalent(&self, key: &Value) -> bool {
         match key {
             Value::String(string) => self.0 == string,
+            Value::Styled(styled) => self.equivalent(&styled.value),
             _ => false,
         }
     }
@@ -474,80 +494,6 @@ impl PartialOrd for Mapping {
         let mut self_entries = Vec::from_iter(self);
         let mut other_entries = Vec::from_iter(other);
 
-        // Sort in an arbitrary order that is consistent with Value's PartialOrd
-        // impl.
-        fn total_cmp(a: &Value, b: &Value) -> Ordering {
-            match (a, b) {
-                (Value::Null, Value::Null) => Ordering::Equal,
-                (Value::Null, _) => Ordering::Less,
-                (_, Value::Null) => Ordering::Greater,
-
-                (Value::Bool(a), Value::Bool(b)) => a.cmp(b),
-                (Value::Bool(_), _) => Ordering::Less,
-                (_, Value::Bool(_)) => Ordering::Greater,
-
-                (Value::Number(a), Value::Number(b)) => a.total_cmp(b),
-                (Value::Number(_), _) => Ordering::Less,
-                (_, Value::Number(_)) => Ordering::Greater,
-
-                (Value::String(a), Value::String(b)) => a.cmp(b),
-                (Value::String(_), _) => Ordering::Less,
-                (_, Value::String(_)) => Ordering::Greater,
-
-                (Value::Sequence(a), Value::Sequence(b)) => {
-                    iter_cmp_by(a, b, total_cmp)
-                }
-                (Value::Sequence(_), _) => Ordering::Less,
-                (_, Value::Sequence(_)) => Ordering::Greater,
-
-                (Value::Mapping(a), Value::Mapping(b)) => {
-                    iter_cmp_by(a, b, |(ak, av), (bk, bv)| {
-                        total_cmp(ak, bk)
-                            .then_with(|| total_cmp(av, bv))
-                    })
-                }
-                (Value::Mapping(_), _) => Ordering::Less,
-                (_, Value::Mapping(_)) => Ordering::Greater,
-
-                (Value::Tagged(a), Value::Tagged(b)) => a
-                    .tag
-                    .cmp(&b.tag)
-                    .then_with(|| total_cmp(&a.value, &b.value)),
-            }
-        }
-
-        fn iter_cmp_by<I, F>(this: I, other: I, mut cmp: F) -> Ordering
-        where
-            I: IntoIterator,
-            F: FnMut(I::Item, I::Item) -> Ordering,
-        {
-            let mut this = this.into_iter();
-            let mut other = other.into_iter();
-
-            loop {
-                let x = match this.next() {
-                    None => {
-                        if other.next().is_none() {
-                            return Ordering::Equal;
-                        } else {
-                            return Ordering::Less;
-                        }
-                    }
-                    Some(val) => val,
-                };
-
-                let y = match other.next() {
-                    None => return Ordering::Greater,
-                    Some(val) => val,
-                };
-
-                match cmp(x, y) {
-                    Ordering::Equal => {}
-                    non_eq => return non_eq,
-                }
-            }
-        }
-
         // While sorting by map key, we get to assume that no two keys are
         // equal, otherwise they wouldn't both be in the map. This is not a safe
         // assumption outside of this situation.
@@ -941,8 +887,24 @@ struct DuplicateKeyError<'a> {
 
 impl Display for DuplicateKeyError<'_> {
     fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
-        formatter.write_str("duplicate entry ")?;
-        match self.entry.key() {
+        write!(
+            formatter,
+            "duplicate entry {}",
+            EntryKey(self.entry.key())
+        )
+    }
+}
+
+/// Describes the key of a mapping entry in an error message.
+pub(crate) struct EntryKey<'a>(pub(crate) &'a Value);
+
+impl Display for EntryKey<'_> {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let key = match self.0 {
+            Value::Styled(styled) => &styled.value,
+            key => key,
+        };
+        match key {
             Value::Null => formatter.write_str("with null key"),
             Value::Bool(boolean) => {
                 write!(formatter, "with key `{}`", boolean)
@@ -955,7 +917,90 @@ impl Display for DuplicateKeyError<'_> {
             }
             Value::Sequence(_)
             | Value::Mapping(_)
-            | Value::Tagged(_) => formatter.write_str("in YAML map"),
+            | Value::Tagged(_)
+            | Value::Binary(_)
+            | Value::Styled(_) => formatter.write_str("in YAML map"),
+        }
+    }
+}
+
+/// Compares values in an arbitrary total order that is consistent with the
+/// [`PartialOrd`] implementation of [`Value`], where it has one. NaN sorts
+/// after every other float.
+pub(crate) fn total_cmp(a: &Value, b: &Value) -> Ordering {
+    match (a, b) {
+        (Value::Styled(a), b) => total_cmp(&a.value, b),
+        (a, Value::Styled(b)) => total_cmp(a, &b.value),
+
+        (Value::Null, Value::Null) => Ordering::Equal,
+        (Value::Null, _) => Ordering::Less,
+        (_, Value::Null) => Ordering::Greater,
+
+        (Value::Bool(a), Value::Bool(b)) => a.cmp(b),
+        (Value::Bool(_), _) => Ordering::Less,
+        (_, Value::Bool(_)) => Ordering::Greater,
+
+        (Value::Number(a), Value::Number(b)) => a.total_cmp(b),
+        (Value::Number(_), _) => Ordering::Less,
+        (_, Value::Number(_)) => Ordering::Greater,
+
+        (Value::String(a), Value::String(b)) => a.cmp(b),
+        (Value::String(_), _) => Ordering::Less,
+        (_, Value::String(_)) => Ordering::Greater,
+
+        (Value::Sequence(a), Value::Sequence(b)) => {
+            iter_cmp_by(a, b, total_cmp)
+        }
+        (Value::Sequence(_), _) => Ordering::Less,
+        (_, Value::Sequence(_)) => Ordering::Greater,
+
+        (Value::Mapping(a), Value::Mapping(b)) => {
+            iter_cmp_by(a, b, |(ak, av), (bk, bv)| {
+                total_cmp(ak, bk).then_with(|| total_cmp(av, bv))
+            })
+        }
+        (Value::Mapping(_), _) => Ordering::Less,
+        (_, Value::Mapping(_)) => Ordering::Greater,
+
+        (Value::Tagged(a), Value::Tagged(b)) => a
+            .tag
+            .cmp(&b.tag)
+            .then_with(|| total_cmp(&a.value, &b.value)),
+        (Value::Tagged(_), _) => Ordering::Less,
+        (_, Value::Tagged(_)) => Ordering::Greater,
+
+        (Value::Binary(a), Value::Binary(b)) => a.cmp(b),
+    }
+}
+
+fn iter_cmp_by<I, F>(this: I, other: I, mut cmp: F) -> Ordering
+where
+    I: IntoIterator,
+    F: FnMut(I::Item, I::Item) -> Ordering,
+{
+    let mut this = this.into_iter();
+    let mut other = other.into_iter();
+
+    loop {
+        let x = match this.next() {
+            None => {
+                if other.next().is_none() {
+                    return Ordering::Equal;
+                } else {
+                    return Ordering::Less;
+                }
+            }
+            Some(val) => val,
+        };
+
+        let y = match other.next() {
+            None => return Ordering::Greater,
+            Some(val) => val,
+        };
+
+        match cmp(x, y) {
+            Ordering::Equal => {}
+            non_eq => return non_eq,
         }
     }
 }
@@ -0,0 +1,74 @@
+// Copyright notice and licensing information.
+// These lines indicate the copyright of the software and its licensing terms.
+// SPDX-License-Identifier: Apache-2.0 OR MIT indicates dual licensing under Apache 2.0 or MIT licenses.
+// Copyright © 2024 Serde YML, Seamless YAML Serialization for Rust. All rights reserved.
+
+//! Base64 encoding of the content of `!!binary` scalars, using the
+//! standard alphabet with padding.
+
+const ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Encodes `bytes` as base64 without line breaks.
+pub(crate) fn encode(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity((bytes.len() + 2) / 3 * 4);
+    for chunk in bytes.chunks(3) {
+        let group =
+            chunk.iter().enumerate().fold(0u32, |group, (i, &b)| {
+                group | u32::from(b) << (16 - 8 * i)
+            });
+        for i in 0..4 {
+            if i <= chunk.len() {
+                let index = (group >> (18 - 6 * i)) & 0x3f;
+                encoded.push(char::from(ALPHABET[index as usize]));
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+/// Decodes base64, ignoring whitespace such as the line breaks of a
+/// block scalar. Padding is optional. Returns `None` if the input is not
+/// valid base64.
+pub(crate) fn decode(text: &[u8]) -> Option<Vec<u8>> {
+    let mut decoded = Vec::with_capacity(text.len() / 4 * 3);
+    let mut group = 0u32;
+    let mut len = 0;
+    let mut padding = 0;
+    for &b in text {
+        let value = match b {
+            b'A'..=b'Z' => b - b'A',
+            b'a'..=b'z' => b - b'a' + 26,
+            b'0'..=b'9' => b - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            b'=' => {
+                padding += 1;
+                continue;
+            }
+            b' ' | b'\t' | b'\r' | b'\n' => continue,
+            _ => return None,
+        };
+        if padding > 0 {
+            return None;
+        }
+        group = group << 6 | u32::from(value);
+        len += 1;
+        if len == 4 {
+            decoded.extend_from_slice(&group.to_be_bytes()[1..]);
+            group = 0;
+            len = 0;
+        }
+    }
+    match (len, padding) {
+        (0, 0) => {}
+        (2, 0 | 2) => decoded.push((group >> 4) as u8),
+        (3, 0 | 1) => {
+            decoded.extend_from_slice(&(group >> 2).to_be_bytes()[2..]);
+        }
+        _ => return None,
+    }
+    Some(decoded)
+}
@@ -94,12 +94,34 @@ pub enum ErrorImpl {
     RecursionLimitExceeded(libyml::Mark),
     /// An error indicating that the repetition limit was exceeded.
     RepetitionLimitExceeded,
+    /// An error indicating that the input is larger than the limit set
+    /// on the deserializer.
+    InputSizeLimitExceeded(usize),
+    /// An error indicating that a scalar is longer than the limit set on
+    /// the deserializer.
+    ScalarLengthLimitExceeded(usize, libyml::Mark),
+    /// An error indicating that a document has more events than the
+    /// limit set on the deserializer.
+    EventLimitExceeded(usize, libyml::Mark),
+    /// An error indicating that a document has more aliases than the
+    /// limit set on the deserializer.
+    AliasLimitExceeded(usize, libyml::Mark),
+    /// An error indicating that a tag was encountered while the
+    /// deserializer rejects tags it does not know.
+    UnknownTag(String, libyml::Mark),
+    /// An error indicating that a mapping has the same key more than
+    /// once, with the description of the key and the positions of its
+    /// first and its repeated occurrence.
+    DuplicateKey(String, libyml::Mark, libyml::Mark),
     /// An error indicating that byte-based YAML is unsupported.
     BytesUnsupported,
     /// An error indicating that an unknown anchor was encountered.
     UnknownAnchor(libyml::Mark),
     /// An error indicating that serializing a nested enum is not supported.
     SerializeNestedEnum,
+    /// An error indicating that an infinite or NaN float was serialized
+    /// while the serializer settings do not allow them.
+    SerializeNonFiniteFloat,
     /// An error indicating that a scalar value was encountered in a merge operation.
     ScalarInMerge,
     /// An error indicating that a tagged value was encountered in a merge operation.
@@ -127,9 +149,16 @@ impl Display for ErrorImpl {
             ErrorImpl::MoreThanOneDocument => write!(f, "Multiple YAML Documents Error: Deserializing from YAML containing more than one document is not supported"),
             ErrorImpl::RecursionLimitExceeded(_) => write!(f, "Recursion Limit Exceeded: The recursive depth limit was exceeded while parsing the YAML"),
             ErrorImpl::RepetitionLimitExceeded => write!(f, "Repetition Limit Exceeded: The repetition limit was exceeded while parsing the YAML"),
+            ErrorImpl::InputSizeLimitExceeded(limit) => write!(f, "Input Size Limit Exceeded: The YAML input is larger than the limit of {} bytes", limit),
+            ErrorImpl::ScalarLengthLimitExceeded(limit, _) => write!(f, "Scalar Length Limit Exceeded: A scalar in the YAML is longer than the limit of {} bytes", limit),
+            ErrorImpl::EventLimitExceeded(limit, _) => write!(f, "Event Limit Exceeded: A YAML document has more than the limit of {} events", limit),
+            ErrorImpl::AliasLimitExceeded(limit, _) => write!(f, "Alias Limit Exceeded: A YAML document has more than the limit of {} aliases", limit),
+            ErrorImpl::UnknownTag(tag, _) => write!(f, "Unknown Tag Error: The tag {} is not allowed by the deserializer settings", tag),
+            ErrorImpl::DuplicateKey(key, _, _) => write!(f, "Duplicate Key Error: A YAML mapping has more than one entry {}", key),
             ErrorImpl::BytesUnsupported => write!(f, "Unsupported Bytes Error: Serialization and deserialization of bytes in YAML is not implemented"),
             ErrorImpl::UnknownAnchor(_) => write!(f, "Unknown Anchor Error: An unknown anchor was encountered in the YAML"),
             ErrorImpl::SerializeNestedEnum => write!(f, "Nested Enum Serialization Error: Serializing nested enums in YAML is not supported"),
+            ErrorImpl::SerializeNonFiniteFloat => write!(f, "Non-Finite Float Serialization Error: Infinite and NaN floats are not allowed by the serializer settings"),
             ErrorImpl::ScalarInMerge => write!(f, "Invalid Merge Error: Expected a mapping or list of mappings for merging, but found a scalar value"),
             ErrorImpl::TaggedInMerge => write!(f, "Invalid Merge Error: Unexpected tagged value encountered in a merge operation"),
             ErrorImpl::ScalarInMergeElement => write!(f, "Invalid Merge Element Error: Expected a mapping for merging, but found a scalar value"),
@@ -151,6 +180,16 @@ impl Error {
         }
     }
 
+    /// Whether the input could not be read, or was too large to read,
+    /// rather than being invalid.
+    pub(crate) fn is_input_error(&self) -> bool {
+        matches!(
+            &*self.0,
+            ErrorImpl::IoError(_)
+                | ErrorImpl::InputSizeLimitExceeded(_)
+        )
+    }
+
     /// Returns the location where the error occurred, if available.
     pub fn location(&self) -> Option<Location> {
         self.0.location()
@@ -259,6 +298,11 @@ impl ErrorImpl {
         match self {
             ErrorImpl::Message(_, Some(Pos { mark, path: _ }))
             | ErrorImpl::RecursionLimitExceeded(mark)
+            | ErrorImpl::ScalarLengthLimitExceeded(_, mark)
+            | ErrorImpl::EventLimitExceeded(_, mark)
+            | ErrorImpl::AliasLimitExceeded(_, mark)
+            | ErrorImpl::UnknownTag(_, mark)
+            | ErrorImpl::DuplicateKey(_, _, mark)
             | ErrorImpl::UnknownAnchor(mark) => Some(*mark),
             ErrorImpl::Libyaml(err) => Some(err.mark()),
             ErrorImpl::Shared(err) => err.mark(),
@@ -288,6 +332,32 @@ impl ErrorImpl {
             ErrorImpl::RepetitionLimitExceeded => {
                 f.write_str("repetition limit exceeded")
             }
+            ErrorImpl::InputSizeLimitExceeded(limit) => write!(
+                f,
+                "input is larger than the limit of {} bytes",
+                limit,
+            ),
+            ErrorImpl::ScalarLengthLimitExceeded(limit, _mark) => write!(
+                f,
+                "scalar is longer than the limit of {} bytes",
+                limit,
+            ),
+            ErrorImpl::EventLimitExceeded(limit, _mark) => write!(
+                f,
+                "number of events exceeds the limit of {}",
+                limit,
+            ),
+            ErrorImpl::AliasLimitExceeded(limit, _mark) => write!(
+                f,
+                "number of aliases exceeds the limit of {}",
+                limit,
+            ),
+            ErrorImpl::UnknownTag(tag, _mark) => {
+                write!(f, "unknown tag {}", tag)
+            }
+            ErrorImpl::DuplicateKey(key, _first, _mark) => {
+                write!(f, "duplicate entry {}", key)
+            }
             ErrorImpl::BytesUnsupported => {
                 f.write_str("serialization and deserialization of bytes in YAML is not implemented")
             }
@@ -295,6 +365,9 @@ impl ErrorImpl {
             ErrorImpl::SerializeNestedEnum => {
                 f.write_str("serializing nested enums in YAML is not supported yet")
             }
+            ErrorImpl::SerializeNonFiniteFloat => {
+                f.write_str("serializing an infinite or NaN float is not allowed")
+            }
             ErrorImpl::ScalarInMerge => {
                 f.write_str("expected a mapping or list of mappings for merging, but found scalar")
             }
@@ -326,6 +399,14 @@ impl ErrorImpl {
                         write!(f, " at {}", location)?;
                     }
                 }
+                if let ErrorImpl::DuplicateKey(_, first, _) = self {
+                    write!(
+                        f,
+                        ", first defined at line {} column {}",
+                        first.line() + 1,
+                        first.column() + 1,
+                    )?;
+                }
                 Ok(())
             }
         }
@@ -1,3 +1,5 @@
+/// The `base64` module contains the encoding used for `!!binary` scalars.
+pub(crate) mod base64;
 /// The `error` module contains the `Error` struct and its implementation.
 pub mod error;
 /// The `path` module contains the `Path` struct and its implementation.
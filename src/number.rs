@@ -4,7 +4,7 @@
 // Copyright © 2024 Serde YML, Seamless YAML Serialization for Rust. All rights reserved.
 
 use crate::{
-    de,
+    de::{self, Resolution},
     modules::error::{self, Error, ErrorImpl},
 };
 use serde::{
@@ -166,11 +166,13 @@ impl FromStr for Number {
     type Err = Error;
 
     fn from_str(repr: &str) -> Result<Self, Self::Err> {
-        if let Ok(result) = de::visit_int(NumberVisitor, repr) {
+        if let Ok(result) =
+            de::visit_int(NumberVisitor, repr, Resolution::Core)
+        {
             return result;
         }
         if !de::digits_but_not_number(repr) {
-            if let Some(float) = de::parse_f64(repr) {
+            if let Some(float) = de::parse_f64(repr, Resolution::Core) {
                 return Ok(float.into());
             }
         }
@@ -9,25 +9,118 @@
 
 use crate::libyml;
 use crate::libyml::emitter::{
-    Emitter, Event, Mapping, Scalar, ScalarStyle, Sequence,
+    Emitter, EmitterConfig, Event, Mapping, MappingStyle, Scalar,
+    ScalarStyle, Sequence, SequenceStyle,
 };
+use crate::libyml::tag::Tag;
 use crate::{
-    modules::error::{self, Error, ErrorImpl},
-    value::tagged::{self, MaybeTag},
+    mapping,
+    modules::{
+        base64,
+        error::{self, Error, ErrorImpl},
+    },
+    value::{
+        self,
+        styled::{self, STYLED},
+        tagged::{self, MaybeTag},
+        Value,
+    },
 };
 use serde::{
     de::Visitor,
     ser::{self, Serializer as _},
 };
 use std::{
+    cell::Cell,
+    cmp::Ordering,
+    collections::HashMap,
     fmt::{self, Display},
-    io,
-    marker::PhantomData,
-    mem, num, str,
+    io, mem, num, str,
+    thread::LocalKey,
 };
 
 type Result<T, E = Error> = std::result::Result<T, E>;
 
+/// Name of the newtype struct that [`with::flow`](crate::with::flow) uses
+/// to ask for a flow style collection.
+pub(crate) const FLOW: &str = "$serde_yml::private::Flow";
+
+/// Name of the newtype struct that [`with::block`](crate::with::block)
+/// uses to ask for a block style collection.
+pub(crate) const BLOCK: &str = "$serde_yml::private::Block";
+
+/// Name of the newtype struct that [`with::plain`](crate::with::plain)
+/// uses to ask for a plain string.
+pub(crate) const PLAIN: &str = "$serde_yml::private::Plain";
+
+/// Name of the newtype struct that
+/// [`with::single_quoted`](crate::with::single_quoted) uses to ask for a
+/// single-quoted string.
+pub(crate) const SINGLE_QUOTED: &str =
+    "$serde_yml::private::SingleQuoted";
+
+/// Name of the newtype struct that
+/// [`with::double_quoted`](crate::with::double_quoted) uses to ask for a
+/// double-quoted string.
+pub(crate) const DOUBLE_QUOTED: &str =
+    "$serde_yml::private::DoubleQuoted";
+
+/// Name of the newtype struct that [`with::literal`](crate::with::literal)
+/// uses to ask for a literal block string.
+pub(crate) const LITERAL: &str = "$serde_yml::private::Literal";
+
+/// Name of the newtype struct that [`with::folded`](crate::with::folded)
+/// uses to ask for a folded block string.
+pub(crate) const FOLDED: &str = "$serde_yml::private::Folded";
+
+/// Number of base64 characters on each line of a long `!!binary` scalar.
+const BINARY_LINE_WIDTH: usize = 76;
+
+/// Name of the newtype struct that [`with::shared`](crate::with::shared)
+/// uses to pass on the address of a shared value.
+pub(crate) const SHARED: &str = "$serde_yml::private::Shared";
+
+thread_local! {
+    /// Set while a null [`Value`] is serialized, so that this crate's
+    /// serializer treats it like `None` while any other serializer only
+    /// sees a unit.
+    static NULL_VALUE: Cell<bool> = const { Cell::new(false) };
+
+    /// Set while [`with::singleton_map`](crate::with::singleton_map)
+    /// serializes the content of a variant as the value of a map entry,
+    /// so that this crate's serializer never leaves it out while any
+    /// other serializer only sees the map entry.
+    static VARIANT_CONTENT: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Calls `f` with `flag` set, and restores it afterwards.
+fn with_flag<R>(
+    flag: &'static LocalKey<Cell<bool>>,
+    f: impl FnOnce() -> R,
+) -> R {
+    struct Restore(&'static LocalKey<Cell<bool>>, bool);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            self.0.with(|flag| flag.set(self.1));
+        }
+    }
+
+    let _restore = Restore(flag, flag.with(|flag| flag.replace(true)));
+    f()
+}
+
+/// Serializes a null [`Value`] with `f`, see [`NULL_VALUE`].
+pub(crate) fn serialize_null_value<R>(f: impl FnOnce() -> R) -> R {
+    with_flag(&NULL_VALUE, f)
+}
+
+/// Serializes the content of a variant with `f`, see
+/// [`VARIANT_CONTENT`].
+pub(crate) fn serialize_variant_content<R>(f: impl FnOnce() -> R) -> R {
+    with_flag(&VARIANT_CONTENT, f)
+}
+
 /// A structure for serializing Rust values into YAML.
 ///
 /// # Example
@@ -38,8 +131,7 @@ type Result<T, E = Error> = std::result::Result<T, E>;
 /// use std::collections::BTreeMap;
 ///
 /// fn main() -> Result<()> {
-///     let mut buffer = Vec::new();
-///     let mut ser = serde_yml::Serializer::new(&mut buffer);
+///     let mut ser = serde_yml::Serializer::new(Vec::new());
 ///
 ///     let mut object = BTreeMap::new();
 ///     object.insert("k", 107);
@@ -48,6 +140,7 @@ type Result<T, E = Error> = std::result::Result<T, E>;
 ///     object.insert("J", 74);
 ///     object.serialize(&mut ser)?;
 ///
+///     let buffer = ser.into_inner()?;
 ///     assert_eq!(buffer, b"k: 107\n---\nJ: 74\nk: 107\n");
 ///     Ok(())
 /// }
@@ -58,10 +151,805 @@ pub struct Serializer<W> {
     pub depth: usize,
     /// The current state of the serializer.
     pub state: State,
-    /// The YAML emitter.
-    pub emitter: Emitter<'static>,
-    /// The underlying writer.
-    pub writer: PhantomData<W>,
+    /// The YAML emitter, which owns the underlying writer.
+    pub emitter: Emitter<W>,
+    /// Output settings that are not handled by the emitter.
+    config: SerializerConfig,
+    /// Style requested for the next collection by `with::flow` or
+    /// `with::block`: `Some(true)` for flow, `Some(false)` for block.
+    forced_flow: Option<bool>,
+    /// Style requested for the next string by one of the scalar style
+    /// modules in [`with`](crate::with).
+    forced_scalar_style: Option<ScalarStyle>,
+    /// Collection held back by [`CollectionStyle::FlowLeaves`].
+    lookahead: Option<Lookahead>,
+    /// Address passed by [`with::shared`](crate::with::shared) for the
+    /// next node.
+    shared: Option<usize>,
+    /// The next node as a [`Value`], if it is the key of a mapping whose
+    /// entries are sorted.
+    key: Option<Value>,
+    /// Whether the next node is a null written for `None`.
+    none: bool,
+    /// Whether the next node is the content of a variant, see
+    /// [`VARIANT_CONTENT`].
+    variant: bool,
+    /// Events of the current document, held back until its end when
+    /// anchors are enabled.
+    document: Option<Document>,
+    /// Mapping entries of the current document that may be omitted.
+    omit: Option<Omit>,
+    /// Tag of a single-entry map inside a tagged value, held back until
+    /// its key shows whether the map is itself a tagged value.
+    held_tag: Option<String>,
+    /// Depths at which the mappings around nested enum variants were
+    /// started, see [`NestedEnumStyle`].
+    nested_ends: Vec<usize>,
+}
+
+/// The style of sequences and mappings that don't ask for one with
+/// [`with::flow`](crate::with::flow) or [`with::block`](crate::with::block).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CollectionStyle {
+    /// Block style, with one entry per line.
+    Block,
+    /// Flow style, such as `[80, 443]` or `{x: 1, y: 2}`.
+    Flow,
+    /// Flow style for nested collections whose entries are all scalars,
+    /// block style for everything else. The root of a document is always
+    /// written in block style.
+    ///
+    /// Entries are held in memory until the end of each collection, or
+    /// until a nested collection shows that block style is needed.
+    FlowLeaves,
+}
+
+impl Default for CollectionStyle {
+    fn default() -> Self {
+        CollectionStyle::Block
+    }
+}
+
+/// Which repeated nodes are written once with an anchor, such as
+/// `&id001`, and referred to by an alias, such as `*id001`, afterwards.
+///
+/// Anchors are numbered in the order their nodes appear in the document,
+/// so the same data always gives the same output.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AnchorMode {
+    /// Never write anchors. Repeated data is written out in full.
+    Off,
+    /// Anchor values that are serialized through
+    /// [`with::shared`](crate::with::shared), and alias them wherever the
+    /// same `Rc` or `Arc` allocation comes up again.
+    Shared,
+    /// Like [`AnchorMode::Shared`], and also alias every non-empty
+    /// sequence or mapping that is identical to an earlier one, such as
+    /// the repeated subtrees of a [`Value`].
+    Repeated,
+}
+
+impl Default for AnchorMode {
+    fn default() -> Self {
+        AnchorMode::Off
+    }
+}
+
+/// Which mappings have their entries sorted by key.
+///
+/// Keys are compared as [`Value`]s in the order of its [`PartialOrd`]
+/// implementation, so numbers sort numerically and come before strings.
+/// NaN comes after every other float, and keys that cannot be represented
+/// as a [`Value`] come last in their order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SortKeys {
+    /// Keep entries in the order they are serialized.
+    Off,
+    /// Sort maps, such as a `HashMap` or a [`Mapping`](crate::Mapping),
+    /// but keep the fields of structs in declaration order.
+    Maps,
+    /// Sort every mapping, including the fields of structs.
+    All,
+}
+
+impl Default for SortKeys {
+    fn default() -> Self {
+        SortKeys::Off
+    }
+}
+
+/// How an enum variant is written when it is the direct content of
+/// another variant, such as `Outer::A(Inner::B(1))`. A YAML node can only
+/// have one tag, so one of the two variants is written as a mapping with
+/// a single entry instead.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NestedEnumStyle {
+    /// The outer variant is the tag of a mapping whose key is the inner
+    /// variant: `!A {B: 1}`.
+    SingletonMap,
+    /// The outer variant is the key of a mapping whose value is tagged
+    /// with the inner variant: `{A: !B 1}`.
+    OuterSingletonMap,
+    /// Fail with an error.
+    Error,
+}
+
+impl Default for NestedEnumStyle {
+    fn default() -> Self {
+        NestedEnumStyle::SingletonMap
+    }
+}
+
+/// When strings are written in quotes.
+///
+/// Whatever the policy, a string that cannot be written as a plain scalar,
+/// such as one that starts with `[` or ends with a space, is quoted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QuotingPolicy {
+    /// Quote only the strings that a YAML 1.2 core schema parser would
+    /// read as something else, such as `true`, `null`, `1.5` or `0123`.
+    Minimal,
+    /// Also quote the strings that a YAML 1.1 parser might read as
+    /// something else: `yes`, `no`, `on`, `off`, `y` and `n` in any case,
+    /// anything that starts with a digit, `-`, `.` or `+`, which covers
+    /// sexagesimal numbers like `1:30`, prefixed numbers like `0x1F` or
+    /// `0o17` and numbers with underscores like `1_000`, and the `<<` and
+    /// `=` keys.
+    Yaml11Safe,
+    /// Quote every string with single quotes, or double quotes where the
+    /// string needs escapes.
+    Always,
+    /// Quote every string with double quotes.
+    AlwaysDouble,
+}
+
+impl Default for QuotingPolicy {
+    fn default() -> Self {
+        QuotingPolicy::Yaml11Safe
+    }
+}
+
+/// How `()`, `None` and unit structs are written.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NullStyle {
+    /// `null`
+    Null,
+    /// `~`
+    Tilde,
+    /// Nothing at all, such as `key:` or `-`. A mapping key or an entry of
+    /// a flow collection cannot be empty, and is written as `null`
+    /// instead.
+    Empty,
+}
+
+impl Default for NullStyle {
+    fn default() -> Self {
+        NullStyle::Null
+    }
+}
+
+/// The line break written at the end of every line.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LineBreak {
+    /// `\n`, as on Unix.
+    Lf,
+    /// `\r\n`, as on Windows.
+    CrLf,
+    /// `\r`, as on classic Mac OS.
+    Cr,
+}
+
+impl Default for LineBreak {
+    fn default() -> Self {
+        LineBreak::Lf
+    }
+}
+
+/// How mapping keys that are sequences or mappings are written in a
+/// block mapping.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ComplexKeyStyle {
+    /// In block style after an explicit `? `, with the value after `: `
+    /// on the line below. Empty collections are written as `[]` and
+    /// `{}`.
+    Explicit,
+    /// In flow style, such as `[1, 2]: a`. A key that is wrapped over
+    /// several lines, see [`SerializerBuilder::width`], or is longer than
+    /// the 1024 characters YAML allows for an implicit key, is still
+    /// written after an explicit `? `.
+    Flow,
+}
+
+impl Default for ComplexKeyStyle {
+    fn default() -> Self {
+        ComplexKeyStyle::Explicit
+    }
+}
+
+/// How finite `f32` and `f64` values are written.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FloatFormat {
+    /// The shortest digits that read back as the same number, with an
+    /// exponent for very large or very small numbers, such as `0.1` or
+    /// `1e100`.
+    Shortest,
+    /// The shortest digits that read back as the same number, never with
+    /// an exponent, such as `0.1` or `0.0000001`.
+    NoExponent,
+    /// Exactly this many digits after the decimal point, rounded, and
+    /// never an exponent, such as `0.10` for two digits.
+    Fixed(usize),
+}
+
+impl Default for FloatFormat {
+    fn default() -> Self {
+        FloatFormat::Shortest
+    }
+}
+
+/// How infinite and NaN `f32` and `f64` values are written.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NonFiniteFloats {
+    /// `.inf`, `-.inf` and `.nan`, which a YAML parser reads as floats.
+    Emit,
+    /// `'.inf'`, `'-.inf'` and `'.nan'`, which a YAML parser reads as
+    /// strings.
+    Quote,
+    /// Serialization fails.
+    Error,
+}
+
+impl Default for NonFiniteFloats {
+    fn default() -> Self {
+        NonFiniteFloats::Emit
+    }
+}
+
+/// Serializer settings that are applied above the emitter.
+#[derive(Clone, Debug, Default)]
+struct SerializerConfig {
+    collection_style: CollectionStyle,
+    anchors: AnchorMode,
+    nested_enums: NestedEnumStyle,
+    sort_keys: SortKeys,
+    quoting: QuotingPolicy,
+    null_style: NullStyle,
+    float_format: FloatFormat,
+    float_decimal_point: bool,
+    non_finite_floats: NonFiniteFloats,
+    /// Whether nodes without a tag are given the tag they resolve to, as
+    /// canonical output requires.
+    canonical: bool,
+    /// Whether mapping entries whose value is `None` are left out.
+    omit_nulls: bool,
+    /// Whether mapping entries whose value is an empty collection are
+    /// left out.
+    omit_empty_collections: bool,
+    /// Prefixes of the `%TAG` directives, which tags are matched against
+    /// so they can be written in shorthand form.
+    tag_prefixes: Vec<String>,
+}
+
+/// The YAML version written in a `%YAML` directive.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum YamlVersion {
+    /// `%YAML 1.1`
+    V1_1,
+    /// `%YAML 1.2`
+    V1_2,
+}
+
+/// The start of a collection that is held back, together with its scalar
+/// entries, until it is known whether any entry is a collection.
+#[derive(Debug)]
+struct Lookahead {
+    /// The start event followed by the entries.
+    events: Vec<(OwnedEvent, NodeInfo)>,
+}
+
+/// What the serializer knows about the node that an event starts, beyond
+/// what is written.
+#[derive(Debug, Default)]
+struct NodeInfo {
+    /// The address passed by [`with::shared`](crate::with::shared), if
+    /// any.
+    shared: Option<usize>,
+    /// The node as a [`Value`], if it is the key of a mapping whose
+    /// entries are sorted.
+    key: Option<Value>,
+    /// Whether the node is a null written for `None`.
+    none: bool,
+    /// Whether the node is the content of a variant, see
+    /// [`VARIANT_CONTENT`].
+    variant: bool,
+}
+
+/// An event inside a document, with the data it borrows owned so that it
+/// can be held back from the emitter.
+#[derive(Debug, PartialEq, Eq, Hash)]
+enum OwnedEvent {
+    Alias(String),
+    Scalar {
+        anchor: Option<String>,
+        tag: Option<String>,
+        value: String,
+        style: ScalarStyle,
+    },
+    SequenceStart(Sequence),
+    SequenceEnd,
+    MappingStart(Mapping),
+    MappingEnd,
+}
+
+impl OwnedEvent {
+    fn new(event: Event<'_>) -> Self {
+        match event {
+            Event::Alias(anchor) => OwnedEvent::Alias(anchor),
+            Event::Scalar(scalar) => OwnedEvent::Scalar {
+                anchor: scalar.anchor,
+                tag: scalar.tag,
+                value: scalar.value.to_owned(),
+                style: scalar.style,
+            },
+            Event::SequenceStart(sequence) => {
+                OwnedEvent::SequenceStart(sequence)
+            }
+            Event::SequenceEnd => OwnedEvent::SequenceEnd,
+            Event::MappingStart(mapping) => {
+                OwnedEvent::MappingStart(mapping)
+            }
+            Event::MappingEnd => OwnedEvent::MappingEnd,
+            Event::StreamStart
+            | Event::StreamEnd
+            | Event::DocumentStart
+            | Event::DocumentEnd => {
+                unreachable!(
+                    "only events inside a document are held back"
+                )
+            }
+        }
+    }
+
+    fn to_event(&self) -> Event<'_> {
+        match self {
+            OwnedEvent::Alias(anchor) => Event::Alias(anchor.clone()),
+            OwnedEvent::Scalar {
+                anchor,
+                tag,
+                value,
+                style,
+            } => Event::Scalar(Scalar {
+                anchor: anchor.clone(),
+                tag: tag.clone(),
+                value,
+                style: *style,
+            }),
+            OwnedEvent::SequenceStart(sequence) => {
+                Event::SequenceStart(sequence.clone())
+            }
+            OwnedEvent::SequenceEnd => Event::SequenceEnd,
+            OwnedEvent::MappingStart(mapping) => {
+                Event::MappingStart(mapping.clone())
+            }
+            OwnedEvent::MappingEnd => Event::MappingEnd,
+        }
+    }
+
+    /// Returns the anchor of the node this event starts, if it starts one.
+    fn anchor_mut(&mut self) -> Option<&mut Option<String>> {
+        match self {
+            OwnedEvent::Scalar { anchor, .. }
+            | OwnedEvent::SequenceStart(Sequence { anchor, .. })
+            | OwnedEvent::MappingStart(Mapping { anchor, .. }) => {
+                Some(anchor)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// The events of a document, held back until its end so that mapping
+/// entries can be sorted and repeated nodes replaced with aliases.
+#[derive(Debug, Default)]
+struct Document {
+    events: Vec<OwnedEvent>,
+    /// What is known about the node that each event starts.
+    infos: Vec<NodeInfo>,
+}
+
+/// Returns the index just past the end of the node that starts at each
+/// index, or zero for the end of a collection.
+fn node_ends(events: &[OwnedEvent]) -> Vec<usize> {
+    let mut ends = vec![0; events.len()];
+    let mut starts = Vec::new();
+    for (i, event) in events.iter().enumerate() {
+        match event {
+            OwnedEvent::SequenceStart(_)
+            | OwnedEvent::MappingStart(_) => {
+                starts.push(i);
+            }
+            OwnedEvent::SequenceEnd | OwnedEvent::MappingEnd => {
+                if let Some(start) = starts.pop() {
+                    ends[start] = i + 1;
+                }
+            }
+            OwnedEvent::Alias(_) | OwnedEvent::Scalar { .. } => {
+                ends[i] = i + 1;
+            }
+        }
+    }
+    ends
+}
+
+impl Document {
+    fn push(&mut self, event: Event<'_>, info: NodeInfo) {
+        self.events.push(OwnedEvent::new(event));
+        self.infos.push(info);
+    }
+
+    /// Sorts the entries of every mapping whose keys were given a
+    /// [`Value`] by the serializer.
+    fn sort_keys(&mut self) {
+        fn sort_node(
+            i: usize,
+            ends: &[usize],
+            items: &mut [Option<(OwnedEvent, NodeInfo)>],
+            sorted: &mut Vec<(OwnedEvent, NodeInfo)>,
+        ) {
+            let end = ends[i];
+            let mut children = Vec::new();
+            let mut child = i + 1;
+            while child + 1 < end {
+                children.push(child);
+                child = ends[child];
+            }
+            let is_mapping = matches!(
+                items[i],
+                Some((OwnedEvent::MappingStart(_), _))
+            );
+            let mut entries: Vec<&[usize]> = if is_mapping {
+                children.chunks(2).collect()
+            } else {
+                children.chunks(1).collect()
+            };
+            if is_mapping {
+                let key = |entry: &[usize]| {
+                    items[entry[0]]
+                        .as_ref()
+                        .and_then(|(_, info)| info.key.as_ref())
+                };
+                // Keys that are not available as values go last, in
+                // their order.
+                entries.sort_by(|a, b| match (key(a), key(b)) {
+                    (Some(a), Some(b)) => mapping::total_cmp(a, b),
+                    (Some(_), None) => Ordering::Less,
+                    (None, Some(_)) => Ordering::Greater,
+                    (None, None) => Ordering::Equal,
+                });
+            }
+            sorted.extend(items[i].take());
+            for entry in entries {
+                for &child in entry {
+                    sort_node(child, ends, items, sorted);
+                }
+            }
+            if end > i + 1 {
+                sorted.extend(items[end - 1].take());
+            }
+        }
+
+        let ends = node_ends(&self.events);
+        let mut items: Vec<_> = self
+            .events
+            .drain(..)
+            .zip(self.infos.drain(..))
+            .map(Some)
+            .collect();
+        let mut sorted = Vec::with_capacity(items.len());
+        let mut i = 0;
+        while i < items.len() {
+            sort_node(i, &ends, &mut items, &mut sorted);
+            i = ends[i].max(i + 1);
+        }
+        for (event, info) in sorted {
+            self.events.push(event);
+            self.infos.push(info);
+        }
+    }
+
+    /// Replaces every node that repeats an earlier one with an alias, and
+    /// gives the earlier node an anchor. Nodes repeat when they were
+    /// serialized from the same shared address or, if `repeated` is set,
+    /// when they are identical non-empty collections.
+    ///
+    /// An allocation that is freed during the document, such as that of
+    /// a temporary `Rc`, may be reused for another value at the same
+    /// address, so nodes from the same address are only aliased if they
+    /// are also written the same.
+    fn resolve(self, repeated: bool) -> Vec<OwnedEvent> {
+        let Document { events, infos } = self;
+        let ends = node_ends(&events);
+        let shared: Vec<_> =
+            infos.iter().map(|info| info.shared).collect();
+
+        let mut by_address = HashMap::new();
+        let mut by_content = HashMap::new();
+        let mut targets = vec![None; events.len()];
+        let mut anchored = vec![false; events.len()];
+        let mut i = 0;
+        while i < events.len() {
+            let end = ends[i];
+            if end <= i {
+                i += 1;
+                continue;
+            }
+            let content = &events[i..end];
+            let comparable = repeated
+                && end - i > 2
+                && matches!(
+                    events[i],
+                    OwnedEvent::SequenceStart(_)
+                        | OwnedEvent::MappingStart(_)
+                );
+            let target = shared[i]
+                .and_then(|address| by_address.get(&address).copied())
+                .filter(|&target| {
+                    events[target..ends[target]] == *content
+                })
+                .or_else(|| {
+                    if comparable {
+                        by_content.get(content).copied()
+                    } else {
+                        None
+                    }
+                });
+            match target {
+                Some(target) => {
+                    targets[i] = Some(target);
+                    anchored[target] = true;
+                    if let Some(address) = shared[i] {
+                        by_address.entry(address).or_insert(target);
+                    }
+                    // Nothing inside an aliased node is written, so
+                    // nothing inside can be aliased either.
+                    i = end;
+                }
+                None => {
+                    if let Some(address) = shared[i] {
+                        by_address.insert(address, i);
+                    }
+                    if comparable {
+                        by_content.insert(content, i);
+                    }
+                    i += 1;
+                }
+            }
+        }
+
+        let mut names = vec![None; events.len()];
+        for (number, i) in
+            (1..).zip((0..events.len()).filter(|&i| anchored[i]))
+        {
+            names[i] = Some(format!("id{:03}", number));
+        }
+
+        let mut resolved = Vec::with_capacity(events.len());
+        let mut skip_until = 0;
+        for (i, mut event) in events.into_iter().enumerate() {
+            if i < skip_until {
+                continue;
+            }
+            if let Some(target) = targets[i] {
+                let name = names[target].clone().unwrap_or_default();
+                resolved.push(OwnedEvent::Alias(name));
+                skip_until = ends[i];
+                continue;
+            }
+            if let (Some(anchor), Some(name)) =
+                (event.anchor_mut(), &names[i])
+            {
+                *anchor = Some(name.clone());
+            }
+            resolved.push(event);
+        }
+        resolved
+    }
+}
+
+/// Mapping entries held back until it is known whether they are omitted,
+/// see [`SerializerBuilder::omit_nulls`] and
+/// [`SerializerBuilder::omit_empty_collections`].
+///
+/// Events are passed on as soon as nothing before them can be dropped, so
+/// only the entries that are still undecided are held in memory.
+#[derive(Debug)]
+struct Omit {
+    nulls: bool,
+    empty: bool,
+    /// Events that may still be dropped, and the events after them.
+    held: Vec<(OwnedEvent, NodeInfo)>,
+    /// The number of events passed on so far, which is the position of
+    /// the first held event.
+    passed: usize,
+    /// The collections that the next event is inside, innermost last.
+    open: Vec<OmitFrame>,
+}
+
+/// A collection seen by [`Omit`] that has not ended yet.
+#[derive(Debug)]
+struct OmitFrame {
+    mapping: bool,
+    /// Whether the collection is the key of a mapping entry.
+    is_key: bool,
+    /// The number of complete nodes in the collection so far, keys and
+    /// values counted separately.
+    len: usize,
+    /// The position of the key of the current entry of a mapping.
+    entry: usize,
+    /// Whether the current entry of a mapping is known to be kept.
+    entry_kept: bool,
+    /// The position of the entry that is dropped if the collection turns
+    /// out to be empty, or `None` if it is kept anyway.
+    drop_if_empty: Option<usize>,
+    /// Whether nothing in the collection is known to be kept.
+    empty: bool,
+}
+
+impl Omit {
+    fn new(nulls: bool, empty: bool) -> Self {
+        Omit {
+            nulls,
+            empty,
+            held: Vec::new(),
+            passed: 0,
+            open: Vec::new(),
+        }
+    }
+
+    /// Takes the next event, and returns the events that are now known
+    /// to be kept.
+    fn push(
+        &mut self,
+        event: OwnedEvent,
+        info: NodeInfo,
+    ) -> Vec<(OwnedEvent, NodeInfo)> {
+        let position = self.passed + self.held.len();
+        match &event {
+            OwnedEvent::SequenceEnd | OwnedEvent::MappingEnd => {
+                let frame = self.open.pop();
+                match frame {
+                    Some(OmitFrame {
+                        drop_if_empty: Some(entry),
+                        empty: true,
+                        ..
+                    }) => self.held.truncate(entry - self.passed),
+                    _ => self.held.push((event, info)),
+                }
+                self.node_end();
+            }
+            _ => {
+                // Nothing inside a mapping key is left out, and neither
+                // is the content of a variant.
+                let in_key = self.open.iter().any(|frame| frame.is_key);
+                let parent = self.open.last_mut();
+                let is_key = parent.as_ref().map_or(false, |parent| {
+                    parent.mapping && parent.len % 2 == 0
+                });
+                let is_value = !in_key
+                    && !info.variant
+                    && parent.as_ref().map_or(false, |parent| {
+                        parent.mapping && parent.len % 2 == 1
+                    });
+                if let Some(parent) = parent {
+                    if is_key {
+                        parent.entry = position;
+                        parent.entry_kept = false;
+                    }
+                }
+                let drop_if_empty = match &event {
+                    OwnedEvent::Scalar { tag, .. }
+                        if is_value
+                            && self.nulls
+                            && info.none
+                            && core_tag(tag, Tag::NULL) =>
+                    {
+                        let entry =
+                            self.open.last().map_or(0, |p| p.entry);
+                        self.held.truncate(entry - self.passed);
+                        self.node_end();
+                        return self.ready();
+                    }
+                    OwnedEvent::SequenceStart(Sequence {
+                        tag, ..
+                    }) if is_value
+                        && self.empty
+                        && core_tag(tag, Tag::SEQ) =>
+                    {
+                        self.open.last().map(|parent| parent.entry)
+                    }
+                    OwnedEvent::MappingStart(Mapping {
+                        tag, ..
+                    }) if is_value
+                        && self.empty
+                        && core_tag(tag, Tag::MAP) =>
+                    {
+                        self.open.last().map(|parent| parent.entry)
+                    }
+                    _ => None,
+                };
+                if !is_key && drop_if_empty.is_none() {
+                    self.keep();
+                }
+                let mapping = match &event {
+                    OwnedEvent::SequenceStart(_) => Some(false),
+                    OwnedEvent::MappingStart(_) => Some(true),
+                    _ => None,
+                };
+                self.held.push((event, info));
+                match mapping {
+                    Some(mapping) => self.open.push(OmitFrame {
+                        mapping,
+                        is_key,
+                        len: 0,
+                        entry: 0,
+                        entry_kept: false,
+                        drop_if_empty,
+                        empty: true,
+                    }),
+                    None => self.node_end(),
+                }
+            }
+        }
+        self.ready()
+    }
+
+    /// Records that a node in the innermost collection is kept, and so
+    /// is everything around it up to the first mapping key.
+    fn keep(&mut self) {
+        for frame in self.open.iter_mut().rev() {
+            frame.empty = false;
+            frame.entry_kept = true;
+            if frame.is_key {
+                break;
+            }
+        }
+    }
+
+    /// Counts a complete node in the innermost collection.
+    fn node_end(&mut self) {
+        if let Some(parent) = self.open.last_mut() {
+            parent.len += 1;
+        }
+    }
+
+    /// Takes the held events before the first entry that may still be
+    /// dropped.
+    fn ready(&mut self) -> Vec<(OwnedEvent, NodeInfo)> {
+        let innermost = self.open.len().saturating_sub(1);
+        let undecided = self
+            .open
+            .iter()
+            .enumerate()
+            .filter(|(i, frame)| {
+                frame.mapping
+                    && !frame.entry_kept
+                    && (*i < innermost || frame.len % 2 == 1)
+            })
+            .map(|(_, frame)| frame.entry)
+            .min();
+        let end = match undecided {
+            Some(position) => position - self.passed,
+            None => self.held.len(),
+        };
+        self.passed += end;
+        self.held.drain(..end).collect()
+    }
+}
+
+/// Whether a tag is absent or the given core schema tag, which canonical
+/// output writes on every node.
+fn core_tag(tag: &Option<String>, core: &str) -> bool {
+    tag.as_deref().map_or(true, |tag| tag == core)
 }
 
 /// The state of the serializer.
@@ -79,28 +967,672 @@ pub enum State {
     AlreadyTagged,
 }
 
-impl<W> Serializer<W>
-where
-    W: io::Write,
-{
-    /// Creates a new YAML serializer.
-    pub fn new(writer: W) -> Self {
-        let mut emitter = Emitter::new({
-            let writer = Box::new(writer);
-            unsafe {
-                mem::transmute::<Box<dyn io::Write>, Box<dyn io::Write>>(
-                    writer,
-                )
-            }
+/// A builder for a [`Serializer`] with custom output settings.
+///
+/// The same builder can be used to create any number of serializers, or
+/// passed to [`to_string_with`] and [`to_writer_with`].
+///
+/// # Example
+///
+/// ```
+/// use anyhow::Result;
+/// use serde_yml::{SerializerBuilder, Value};
+///
+/// fn main() -> Result<()> {
+///     let value: Value = serde_yml::from_str("hosts: [web, db]")?;
+///
+///     let builder = SerializerBuilder::new()
+///         .indent(4)
+///         .indent_sequences(true);
+///     let yaml = serde_yml::to_string_with(&value, &builder)?;
+///
+///     assert_eq!(yaml, "hosts:\n    - web\n    - db\n");
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct SerializerBuilder {
+    emitter: EmitterConfig,
+    config: SerializerConfig,
+}
+
+impl SerializerBuilder {
+    /// Creates a builder with the default settings: 2-space indentation,
+    /// no line wrapping and indentless sequences in mappings.
+    pub fn new() -> Self {
+        SerializerBuilder::default()
+    }
+
+    /// Sets the number of spaces used for each level of indentation.
+    ///
+    /// libyml supports between 2 and 9 spaces; other values are clamped
+    /// to that range.
+    pub fn indent(mut self, indent: usize) -> Self {
+        self.emitter.indent = indent.clamp(2, 9);
+        self
+    }
+
+    /// Sets the preferred line width. Long plain and folded scalars are
+    /// wrapped at the first space after a line passes this width. `None`,
+    /// the default, never wraps.
+    ///
+    /// A width that is not more than twice the indentation is replaced
+    /// with libyml's default of 80.
+    pub fn width(mut self, width: Option<usize>) -> Self {
+        self.emitter.width = width;
+        self
+    }
+
+    /// Chooses whether a block sequence that is the value of a mapping
+    /// entry has its `- ` items indented under the key.
+    ///
+    /// By default such sequences are written indentless:
+    ///
+    /// ```yaml
+    /// hosts:
+    /// - web
+    /// ```
+    ///
+    /// With this option enabled they are written as:
+    ///
+    /// ```yaml
+    /// hosts:
+    ///   - web
+    /// ```
+    pub fn indent_sequences(mut self, indent: bool) -> Self {
+        self.emitter.indent_sequences = indent;
+        self
+    }
+
+    /// Sets the style of sequences and mappings that don't ask for one
+    /// with [`with::flow`](crate::with::flow) or
+    /// [`with::block`](crate::with::block). The default is
+    /// [`CollectionStyle::Block`].
+    ///
+    /// Collections nested inside a flow collection are always written in
+    /// flow style.
+    ///
+    /// ```
+    /// use serde_yml::{CollectionStyle, SerializerBuilder, Value};
+    ///
+    /// let value: Value =
+    ///     serde_yml::from_str("name: web\nports: [80, 443]").unwrap();
+    /// let builder = SerializerBuilder::new()
+    ///     .collection_style(CollectionStyle::FlowLeaves);
+    /// let yaml = serde_yml::to_string_with(&value, &builder).unwrap();
+    /// assert_eq!(yaml, "name: web\nports: [80, 443]\n");
+    /// ```
+    pub fn collection_style(mut self, style: CollectionStyle) -> Self {
+        self.config.collection_style = style;
+        self
+    }
+
+    /// Chooses how mapping keys that are sequences or mappings, such as
+    /// the tuple keys of a `BTreeMap<(u32, u32), T>`, are written in a
+    /// block mapping. The default is [`ComplexKeyStyle::Explicit`].
+    ///
+    /// This applies to keys regardless of the
+    /// [`collection_style`](Self::collection_style). Keys inside a flow
+    /// mapping are always written in flow style.
+    ///
+    /// ```
+    /// use serde_yml::{ComplexKeyStyle, SerializerBuilder};
+    /// use std::collections::BTreeMap;
+    ///
+    /// let mut map = BTreeMap::new();
+    /// map.insert((1, 2), "a");
+    ///
+    /// let yaml = serde_yml::to_string(&map).unwrap();
+    /// assert_eq!(yaml, "? - 1\n  - 2\n: a\n");
+    ///
+    /// let builder =
+    ///     SerializerBuilder::new().complex_keys(ComplexKeyStyle::Flow);
+    /// let yaml = serde_yml::to_string_with(&map, &builder).unwrap();
+    /// assert_eq!(yaml, "[1, 2]: a\n");
+    ///
+    /// let back: BTreeMap<(u32, u32), String> =
+    ///     serde_yml::from_str(&yaml).unwrap();
+    /// assert_eq!(back[&(1, 2)], "a");
+    /// ```
+    pub fn complex_keys(mut self, style: ComplexKeyStyle) -> Self {
+        self.emitter.flow_keys = style == ComplexKeyStyle::Flow;
+        self
+    }
+
+    /// Chooses which repeated nodes are written once with an anchor and
+    /// referred to by an alias afterwards. The default is
+    /// [`AnchorMode::Off`].
+    ///
+    /// With anchors enabled, each document is held in memory until it is
+    /// complete.
+    ///
+    /// ```
+    /// use serde::Serialize;
+    /// use serde_yml::{AnchorMode, SerializerBuilder};
+    /// use std::rc::Rc;
+    ///
+    /// #[derive(Serialize)]
+    /// struct Service {
+    ///     name: &'static str,
+    ///     #[serde(with = "serde_yml::with::shared")]
+    ///     limits: Rc<Vec<u32>>,
+    /// }
+    ///
+    /// let limits = Rc::new(vec![512, 1024]);
+    /// let services = vec![
+    ///     Service { name: "web", limits: Rc::clone(&limits) },
+    ///     Service { name: "db", limits },
+    /// ];
+    ///
+    /// let builder = SerializerBuilder::new().anchors(AnchorMode::Shared);
+    /// let yaml = serde_yml::to_string_with(&services, &builder).unwrap();
+    /// assert_eq!(
+    ///     yaml,
+    ///     "- name: web\n  limits: &id001\n  - 512\n  - 1024\n- name: db\n  limits: *id001\n",
+    /// );
+    /// ```
+    pub fn anchors(mut self, mode: AnchorMode) -> Self {
+        self.config.anchors = mode;
+        self
+    }
+
+    /// Chooses how an enum variant that is the direct content of another
+    /// variant is written. The default is
+    /// [`NestedEnumStyle::SingletonMap`].
+    ///
+    /// Both mapping forms are accepted by the deserializer.
+    ///
+    /// ```
+    /// use serde::{Deserialize, Serialize};
+    /// use serde_yml::{NestedEnumStyle, SerializerBuilder};
+    ///
+    /// #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    /// enum Shape {
+    ///     Circle(Size),
+    /// }
+    ///
+    /// #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    /// enum Size {
+    ///     Radius(u32),
+    /// }
+    ///
+    /// let shape = Shape::Circle(Size::Radius(2));
+    /// let yaml = serde_yml::to_string(&shape).unwrap();
+    /// assert_eq!(yaml, "!Circle\nRadius: 2\n");
+    /// assert_eq!(serde_yml::from_str::<Shape>(&yaml).unwrap(), shape);
+    ///
+    /// let builder = SerializerBuilder::new()
+    ///     .nested_enums(NestedEnumStyle::OuterSingletonMap);
+    /// let yaml = serde_yml::to_string_with(&shape, &builder).unwrap();
+    /// assert_eq!(yaml, "Circle: !Radius 2\n");
+    /// assert_eq!(serde_yml::from_str::<Shape>(&yaml).unwrap(), shape);
+    /// ```
+    pub fn nested_enums(mut self, style: NestedEnumStyle) -> Self {
+        self.config.nested_enums = style;
+        self
+    }
+
+    /// Chooses which mappings have their entries sorted by key, so that
+    /// maps with no fixed order, such as a `HashMap`, give the same output
+    /// every time. The default is [`SortKeys::Off`].
+    ///
+    /// With sorting enabled, each document is held in memory until it is
+    /// complete.
+    ///
+    /// ```
+    /// use serde::Serialize;
+    /// use serde_yml::{SerializerBuilder, SortKeys};
+    /// use std::collections::HashMap;
+    ///
+    /// #[derive(Serialize)]
+    /// struct Config {
+    ///     name: &'static str,
+    ///     limits: HashMap<&'static str, u32>,
+    /// }
+    ///
+    /// let config = Config {
+    ///     name: "web",
+    ///     limits: HashMap::from([("memory", 512), ("cpu", 2)]),
+    /// };
+    ///
+    /// let builder = SerializerBuilder::new().sort_keys(SortKeys::Maps);
+    /// let yaml = serde_yml::to_string_with(&config, &builder).unwrap();
+    /// assert_eq!(yaml, "name: web\nlimits:\n  cpu: 2\n  memory: 512\n");
+    ///
+    /// let builder = SerializerBuilder::new().sort_keys(SortKeys::All);
+    /// let yaml = serde_yml::to_string_with(&config, &builder).unwrap();
+    /// assert_eq!(yaml, "limits:\n  cpu: 2\n  memory: 512\nname: web\n");
+    /// ```
+    pub fn sort_keys(mut self, sort: SortKeys) -> Self {
+        self.config.sort_keys = sort;
+        self
+    }
+
+    /// Leaves out mapping entries whose value is `None`, in structs, maps
+    /// and [`Value`]s alike, where [`Value::Null`] counts as `None`. Nulls
+    /// in sequences, nulls with a tag and other values written as null,
+    /// such as `()`, are kept. So is everything inside a mapping key and
+    /// the content of a variant written by
+    /// [`with::singleton_map`](crate::with::singleton_map).
+    ///
+    /// ```
+    /// use serde::Serialize;
+    /// use serde_yml::SerializerBuilder;
+    ///
+    /// #[derive(Serialize)]
+    /// struct Container {
+    ///     image: &'static str,
+    ///     command: Option<&'static str>,
+    ///     args: Vec<&'static str>,
+    /// }
+    ///
+    /// let container = Container {
+    ///     image: "nginx",
+    ///     command: None,
+    ///     args: vec![],
+    /// };
+    ///
+    /// let builder = SerializerBuilder::new().omit_nulls(true);
+    /// let yaml = serde_yml::to_string_with(&container, &builder).unwrap();
+    /// assert_eq!(yaml, "image: nginx\nargs: []\n");
+    ///
+    /// let builder = builder.omit_empty_collections(true);
+    /// let yaml = serde_yml::to_string_with(&container, &builder).unwrap();
+    /// assert_eq!(yaml, "image: nginx\n");
+    /// ```
+    pub fn omit_nulls(mut self, omit: bool) -> Self {
+        self.config.omit_nulls = omit;
+        self
+    }
+
+    /// Leaves out mapping entries whose value is an empty sequence or an
+    /// empty mapping. A collection whose entries are all left out counts
+    /// as empty, so it is left out as well. Empty collections in
+    /// sequences and empty collections with a tag, such as a struct
+    /// variant without fields, are kept, as are empty collections inside
+    /// a mapping key or as the content of a variant.
+    ///
+    /// Each entry is held in memory until it is known to be kept, which
+    /// for a collection is when its first entry is kept.
+    pub fn omit_empty_collections(mut self, omit: bool) -> Self {
+        self.config.omit_empty_collections = omit;
+        self
+    }
+
+    /// Chooses when strings are written in quotes. The default is
+    /// [`QuotingPolicy::Yaml11Safe`], which keeps the output readable by
+    /// YAML 1.1 parsers.
+    ///
+    /// Fields serialized with [`with::plain`](crate::with::plain) are
+    /// still written without quotes where that is safe.
+    ///
+    /// ```
+    /// use serde_yml::{QuotingPolicy, SerializerBuilder};
+    ///
+    /// let words = vec!["yes", "1:30", "true"];
+    ///
+    /// let builder =
+    ///     SerializerBuilder::new().quoting(QuotingPolicy::Minimal);
+    /// let yaml = serde_yml::to_string_with(&words, &builder).unwrap();
+    /// assert_eq!(yaml, "- yes\n- 1:30\n- 'true'\n");
+    ///
+    /// let builder =
+    ///     SerializerBuilder::new().quoting(QuotingPolicy::AlwaysDouble);
+    /// let yaml = serde_yml::to_string_with(&words, &builder).unwrap();
+    /// assert_eq!(yaml, "- \"yes\"\n- \"1:30\"\n- \"true\"\n");
+    /// ```
+    pub fn quoting(mut self, policy: QuotingPolicy) -> Self {
+        self.config.quoting = policy;
+        self
+    }
+
+    /// Chooses how `()`, `None` and unit structs are written. The default
+    /// is [`NullStyle::Null`].
+    ///
+    /// ```
+    /// use serde_yml::{NullStyle, SerializerBuilder};
+    ///
+    /// let values = vec![Some(1), None];
+    ///
+    /// let builder = SerializerBuilder::new().null_style(NullStyle::Tilde);
+    /// let yaml = serde_yml::to_string_with(&values, &builder).unwrap();
+    /// assert_eq!(yaml, "- 1\n- ~\n");
+    ///
+    /// let builder = SerializerBuilder::new().null_style(NullStyle::Empty);
+    /// let yaml = serde_yml::to_string_with(&values, &builder).unwrap();
+    /// assert_eq!(yaml, "- 1\n-\n");
+    /// ```
+    pub fn null_style(mut self, style: NullStyle) -> Self {
+        self.config.null_style = style;
+        self
+    }
+
+    /// Chooses how finite `f32` and `f64` values are written. The default
+    /// is [`FloatFormat::Shortest`].
+    ///
+    /// ```
+    /// use serde_yml::{FloatFormat, SerializerBuilder};
+    ///
+    /// let values = vec![0.1, 2.0 / 3.0, 1e-7];
+    ///
+    /// let yaml = serde_yml::to_string(&values).unwrap();
+    /// assert_eq!(yaml, "- 0.1\n- 0.6666666666666666\n- 1e-7\n");
+    ///
+    /// let builder =
+    ///     SerializerBuilder::new().float_format(FloatFormat::NoExponent);
+    /// let yaml = serde_yml::to_string_with(&values, &builder).unwrap();
+    /// assert_eq!(yaml, "- 0.1\n- 0.6666666666666666\n- 0.0000001\n");
+    ///
+    /// let builder =
+    ///     SerializerBuilder::new().float_format(FloatFormat::Fixed(2));
+    /// let yaml = serde_yml::to_string_with(&values, &builder).unwrap();
+    /// assert_eq!(yaml, "- 0.10\n- 0.67\n- 0.00\n");
+    /// ```
+    pub fn float_format(mut self, format: FloatFormat) -> Self {
+        self.config.float_format = format;
+        self
+    }
+
+    /// Writes a decimal point in every finite `f32` and `f64` value, so
+    /// that whole numbers are written as `1.0` rather than `1` and are
+    /// not read back as integers. Off by default.
+    ///
+    /// ```
+    /// use serde_yml::{FloatFormat, SerializerBuilder};
+    ///
+    /// let values = vec![1.0, 1e20];
+    ///
+    /// let builder = SerializerBuilder::new().float_decimal_point(true);
+    /// let yaml = serde_yml::to_string_with(&values, &builder).unwrap();
+    /// assert_eq!(yaml, "- 1.0\n- 1.0e20\n");
+    ///
+    /// let builder = SerializerBuilder::new()
+    ///     .float_format(FloatFormat::NoExponent)
+    ///     .float_decimal_point(true);
+    /// let yaml = serde_yml::to_string_with(&values, &builder).unwrap();
+    /// assert_eq!(yaml, "- 1.0\n- 100000000000000000000.0\n");
+    /// ```
+    pub fn float_decimal_point(mut self, decimal_point: bool) -> Self {
+        self.config.float_decimal_point = decimal_point;
+        self
+    }
+
+    /// Chooses how infinite and NaN `f32` and `f64` values are written.
+    /// The default is [`NonFiniteFloats::Emit`].
+    ///
+    /// ```
+    /// use serde_yml::{NonFiniteFloats, SerializerBuilder};
+    ///
+    /// let values = vec![f64::INFINITY, f64::NAN];
+    ///
+    /// let builder =
+    ///     SerializerBuilder::new().non_finite_floats(NonFiniteFloats::Quote);
+    /// let yaml = serde_yml::to_string_with(&values, &builder).unwrap();
+    /// assert_eq!(yaml, "- '.inf'\n- '.nan'\n");
+    ///
+    /// let builder =
+    ///     SerializerBuilder::new().non_finite_floats(NonFiniteFloats::Error);
+    /// assert!(serde_yml::to_string_with(&values, &builder).is_err());
+    /// ```
+    pub fn non_finite_floats(
+        mut self,
+        policy: NonFiniteFloats,
+    ) -> Self {
+        self.config.non_finite_floats = policy;
+        self
+    }
+
+    /// Writes the `---` marker at the start of every document, including
+    /// the first one.
+    ///
+    /// ```
+    /// use serde_yml::SerializerBuilder;
+    ///
+    /// let builder = SerializerBuilder::new().explicit_start(true);
+    /// let yaml = serde_yml::to_string_with(&vec![1], &builder).unwrap();
+    /// assert_eq!(yaml, "---\n- 1\n");
+    /// ```
+    pub fn explicit_start(mut self, explicit: bool) -> Self {
+        self.emitter.explicit_start = explicit;
+        self
+    }
+
+    /// Writes the `...` marker at the end of every document.
+    pub fn explicit_end(mut self, explicit: bool) -> Self {
+        self.emitter.explicit_end = explicit;
+        self
+    }
+
+    /// Sets the line break written at the end of every line. The default
+    /// is [`LineBreak::Lf`].
+    ///
+    /// Lines inside block scalars end with the same break. A YAML parser
+    /// reads every line break in a block scalar as `\n`, so a string
+    /// written as a block scalar reads back unchanged whatever the
+    /// break. Strings that contain `\r` are written in double quotes
+    /// with the `\r` escaped, which keeps them unchanged as well.
+    ///
+    /// ```
+    /// use serde_yml::{LineBreak, SerializerBuilder};
+    /// use std::collections::BTreeMap;
+    ///
+    /// let builder = SerializerBuilder::new().line_break(LineBreak::CrLf);
+    /// let map = BTreeMap::from([("hosts", vec!["web", "db"])]);
+    /// let yaml = serde_yml::to_string_with(&map, &builder).unwrap();
+    /// assert_eq!(yaml, "hosts:\r\n- web\r\n- db\r\n");
+    /// ```
+    pub fn line_break(mut self, line_break: LineBreak) -> Self {
+        self.emitter.line_break = match line_break {
+            LineBreak::Lf => b"\n",
+            LineBreak::CrLf => b"\r\n",
+            LineBreak::Cr => b"\r",
+        };
+        self
+    }
+
+    /// Escapes every character outside of ASCII, so that the output can
+    /// be read by systems that only handle ASCII. Strings with such
+    /// characters are written in double quotes, such as `"caf\xE9"`.
+    ///
+    /// ```
+    /// use serde_yml::SerializerBuilder;
+    ///
+    /// let builder = SerializerBuilder::new().ascii_only(true);
+    /// let yaml = serde_yml::to_string_with(&"café", &builder).unwrap();
+    /// assert_eq!(yaml, "\"caf\\xE9\"\n");
+    /// assert_eq!(serde_yml::from_str::<String>(&yaml).unwrap(), "café");
+    /// ```
+    pub fn ascii_only(mut self, ascii: bool) -> Self {
+        self.emitter.ascii = ascii;
+        self
+    }
+
+    /// Writes the canonical form of every document: it starts with `---`,
+    /// every node has an explicit tag, collections are in flow style and
+    /// scalars in double quotes. Canonical output takes more space but
+    /// leaves nothing to the reader's interpretation, which is useful to
+    /// compare documents or to debug how they are read.
+    ///
+    /// Since quoted scalars cannot also carry the tag of an enum variant,
+    /// newtype variants such as `Shape::Circle(3)` are written as a mapping
+    /// with a single entry, which deserializes back to the same variant.
+    ///
+    /// ```
+    /// use serde_yml::SerializerBuilder;
+    /// use std::collections::BTreeMap;
+    ///
+    /// let builder = SerializerBuilder::new().canonical(true);
+    /// let map = BTreeMap::from([("retries", 3)]);
+    /// let yaml = serde_yml::to_string_with(&map, &builder).unwrap();
+    /// assert_eq!(
+    ///     yaml,
+    ///     "---\n!!map {\n  ? !!str \"retries\"\n  : !!int \"3\",\n}\n",
+    /// );
+    /// ```
+    pub fn canonical(mut self, canonical: bool) -> Self {
+        self.config.canonical = canonical;
+        self.emitter.canonical = canonical;
+        self
+    }
+
+    /// Writes a `%YAML` directive at the start of every document. A
+    /// document with a directive always starts with `---`.
+    pub fn version_directive(mut self, version: YamlVersion) -> Self {
+        self.emitter.version_minor = Some(match version {
+            YamlVersion::V1_1 => 1,
+            YamlVersion::V1_2 => 2,
         });
+        self
+    }
+
+    /// Adds a `%TAG` directive to every document, declaring `handle` as
+    /// shorthand for tags that start with `prefix`. A document with a
+    /// directive always starts with `---`.
+    ///
+    /// Tags of enum variants and [`Value::Tagged`]
+    /// values that start with a declared prefix are written in shorthand
+    /// form. The handle must look like `!name!`, and the prefix must not
+    /// be empty; otherwise serialization fails.
+    ///
+    /// ```
+    /// use serde_yml::value::{Tag, TaggedValue};
+    /// use serde_yml::{SerializerBuilder, Value};
+    ///
+    /// let value = Value::Tagged(Box::new(TaggedValue {
+    ///     tag: Tag::new("tag:example.com,2024:widget"),
+    ///     value: Value::from(1),
+    /// }));
+    ///
+    /// let builder = SerializerBuilder::new()
+    ///     .tag_directive("!app!", "tag:example.com,2024:");
+    /// let yaml = serde_yml::to_string_with(&value, &builder).unwrap();
+    /// assert_eq!(
+    ///     yaml,
+    ///     "%TAG !app! tag:example.com,2024:\n--- !app!widget 1\n",
+    /// );
+    ///
+    /// let deserialized: Value = serde_yml::from_str(&yaml).unwrap();
+    /// assert_eq!(deserialized, value);
+    /// ```
+    pub fn tag_directive(
+        mut self,
+        handle: impl Into<String>,
+        prefix: impl Into<String>,
+    ) -> Self {
+        let prefix = prefix.into();
+        self.config.tag_prefixes.push(prefix.clone());
+        self.emitter.tag_directives.push((handle.into(), prefix));
+        self
+    }
+
+    /// Writes `comment` at the top of every document, before the first
+    /// line of its content. Each line of the text becomes a `#` comment.
+    ///
+    /// ```
+    /// use serde_yml::SerializerBuilder;
+    /// use std::collections::BTreeMap;
+    ///
+    /// let builder = SerializerBuilder::new()
+    ///     .header_comment("Generated file.\nDo not edit.");
+    /// let map = BTreeMap::from([("retries", 3)]);
+    /// let yaml = serde_yml::to_string_with(&map, &builder).unwrap();
+    /// assert_eq!(yaml, "# Generated file.\n# Do not edit.\nretries: 3\n");
+    /// ```
+    pub fn header_comment(
+        mut self,
+        comment: impl Into<String>,
+    ) -> Self {
+        self.emitter.header_comment = Some(comment.into());
+        self
+    }
+
+    /// Writes `comment` on the lines before the node at `path`, in every
+    /// document that has one. Each line of the text becomes a `#`
+    /// comment, indented like the line it comes before.
+    ///
+    /// A path lists mapping keys separated by `.`, and sequence indices
+    /// as `[n]`, such as `servers[0].port`. The comment of a mapping
+    /// value is written before its key. Anything else is written before
+    /// the line where the node starts, which for an entry of a flow
+    /// collection is the line where the collection starts.
+    ///
+    /// ```
+    /// use serde::Serialize;
+    /// use serde_yml::SerializerBuilder;
+    ///
+    /// #[derive(Serialize)]
+    /// struct Server {
+    ///     host: &'static str,
+    ///     port: u16,
+    /// }
+    ///
+    /// #[derive(Serialize)]
+    /// struct Config {
+    ///     servers: Vec<Server>,
+    /// }
+    ///
+    /// let config = Config {
+    ///     servers: vec![Server { host: "web", port: 80 }],
+    /// };
+    /// let builder = SerializerBuilder::new()
+    ///     .comment("servers[0]", "Primary")
+    ///     .comment("servers[0].port", "Must be open in the firewall");
+    /// let yaml = serde_yml::to_string_with(&config, &builder).unwrap();
+    /// assert_eq!(
+    ///     yaml,
+    ///     "servers:\n# Primary\n- host: web\n  # Must be open in the firewall\n  port: 80\n",
+    /// );
+    /// ```
+    pub fn comment(
+        mut self,
+        path: impl Into<String>,
+        comment: impl Into<String>,
+    ) -> Self {
+        self.emitter.comments.push((path.into(), comment.into()));
+        self
+    }
+
+    /// Creates a serializer with these settings that writes to `writer`.
+    pub fn build<W>(&self, writer: W) -> Serializer<W>
+    where
+        W: io::Write,
+    {
+        let mut emitter = Emitter::new(writer, &self.emitter);
         emitter.emit(Event::StreamStart).unwrap();
         Serializer {
             depth: 0,
             state: State::NothingInParticular,
             emitter,
-            writer: PhantomData,
+            config: self.config.clone(),
+            forced_flow: None,
+            forced_scalar_style: None,
+            lookahead: None,
+            shared: None,
+            key: None,
+            none: false,
+            variant: false,
+            document: None,
+            omit: None,
+            held_tag: None,
+            nested_ends: Vec::new(),
         }
     }
+}
+
+impl<W> Serializer<W>
+where
+    W: io::Write,
+{
+    /// Creates a new YAML serializer.
+    pub fn new(writer: W) -> Self {
+        SerializerBuilder::new().build(writer)
+    }
+
+    /// Creates a [`SerializerBuilder`] for a serializer with custom
+    /// output settings.
+    pub fn builder() -> SerializerBuilder {
+        SerializerBuilder::new()
+    }
 
     /// Calls [`.flush()`](io::Write::flush) on the underlying `io::Write`
     /// object.
@@ -113,8 +1645,7 @@ where
     pub fn into_inner(mut self) -> Result<W> {
         self.emitter.emit(Event::StreamEnd)?;
         self.emitter.flush()?;
-        let writer = self.emitter.into_inner();
-        Ok(*unsafe { Box::from_raw(Box::into_raw(writer).cast::<W>()) })
+        Ok(self.emitter.into_inner())
     }
 
     /// Emit a scalar value.
@@ -123,11 +1654,22 @@ where
         mut scalar: Scalar<'_>,
     ) -> Result<()> {
         self.flush_mapping_start()?;
+        self.forced_flow = None;
+        self.forced_scalar_style = None;
+        let info = self.take_node_info();
         if let Some(tag) = self.take_tag() {
             scalar.tag = Some(tag);
+        } else if self.config.canonical {
+            scalar.tag = Some(resolved_tag(scalar.value, scalar.style));
         }
         self.value_start()?;
-        self.emitter.emit(Event::Scalar(scalar))?;
+        if let Some(lookahead) = &mut self.lookahead {
+            lookahead
+                .events
+                .push((OwnedEvent::new(Event::Scalar(scalar)), info));
+        } else {
+            self.emit_in_document(Event::Scalar(scalar), info)?;
+        }
         self.value_end()
     }
 
@@ -135,14 +1677,36 @@ where
     pub fn emit_sequence_start(&mut self) -> Result<()> {
         self.flush_mapping_start()?;
         self.value_start()?;
-        let tag = self.take_tag();
-        self.emitter.emit(Event::SequenceStart(Sequence { tag }))?;
+        let info = self.take_node_info();
+        let tag = self.take_tag().or_else(|| {
+            self.config.canonical.then(|| Tag::SEQ.to_owned())
+        });
+        let start = |flow| {
+            Event::SequenceStart(Sequence {
+                anchor: None,
+                tag,
+                style: if flow {
+                    SequenceStyle::Flow
+                } else {
+                    SequenceStyle::Any
+                },
+            })
+        };
+        match self.collection_flow()? {
+            Some(flow) => self.emit_in_document(start(flow), info)?,
+            None => {
+                self.lookahead = Some(Lookahead {
+                    events: vec![(OwnedEvent::new(start(true)), info)],
+                });
+            }
+        }
         Ok(())
     }
 
     /// Emit a sequence end.
     pub fn emit_sequence_end(&mut self) -> Result<()> {
-        self.emitter.emit(Event::SequenceEnd)?;
+        self.flush_lookahead(true)?;
+        self.emit_in_document(Event::SequenceEnd, NodeInfo::default())?;
         self.value_end()
     }
 
@@ -150,21 +1714,156 @@ where
     pub fn emit_mapping_start(&mut self) -> Result<()> {
         self.flush_mapping_start()?;
         self.value_start()?;
-        let tag = self.take_tag();
-        self.emitter.emit(Event::MappingStart(Mapping { tag }))?;
+        let info = self.take_node_info();
+        let tag = self.take_tag().or_else(|| {
+            self.config.canonical.then(|| Tag::MAP.to_owned())
+        });
+        let start = |flow| {
+            Event::MappingStart(Mapping {
+                anchor: None,
+                tag,
+                style: if flow {
+                    MappingStyle::Flow
+                } else {
+                    MappingStyle::Any
+                },
+            })
+        };
+        match self.collection_flow()? {
+            Some(flow) => self.emit_in_document(start(flow), info)?,
+            None => {
+                self.lookahead = Some(Lookahead {
+                    events: vec![(OwnedEvent::new(start(true)), info)],
+                });
+            }
+        }
         Ok(())
     }
 
     /// Emit a mapping end.
     pub fn emit_mapping_end(&mut self) -> Result<()> {
-        self.emitter.emit(Event::MappingEnd)?;
+        self.flush_lookahead(true)?;
+        self.emit_in_document(Event::MappingEnd, NodeInfo::default())?;
         self.value_end()
     }
 
+    /// Decides whether a collection that is about to start is written in
+    /// flow style, or returns `None` if that depends on its entries.
+    fn collection_flow(&mut self) -> Result<Option<bool>> {
+        // Any collection still held back contains this one, so it needs
+        // block style.
+        self.flush_lookahead(false)?;
+        self.forced_scalar_style = None;
+        if let Some(flow) = self.forced_flow.take() {
+            return Ok(Some(flow));
+        }
+        Ok(match self.config.collection_style {
+            CollectionStyle::Block => Some(false),
+            CollectionStyle::Flow => Some(true),
+            CollectionStyle::FlowLeaves if self.depth > 1 => None,
+            CollectionStyle::FlowLeaves => Some(false),
+        })
+    }
+
+    /// Writes out the collection held back by
+    /// [`CollectionStyle::FlowLeaves`], if any, in the given style.
+    fn flush_lookahead(&mut self, flow: bool) -> Result<()> {
+        if let Some(Lookahead { mut events }) = self.lookahead.take() {
+            match &mut events[0].0 {
+                OwnedEvent::SequenceStart(sequence) if !flow => {
+                    sequence.style = SequenceStyle::Any;
+                }
+                OwnedEvent::MappingStart(mapping) if !flow => {
+                    mapping.style = MappingStyle::Any;
+                }
+                _ => {}
+            }
+            for (event, info) in events {
+                self.emit_in_document(event.to_event(), info)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Takes what is known about the node that is about to start.
+    fn take_node_info(&mut self) -> NodeInfo {
+        NodeInfo {
+            shared: self.shared.take(),
+            key: self.key.take(),
+            none: mem::take(&mut self.none),
+            variant: mem::take(&mut self.variant),
+        }
+    }
+
+    /// Passes an event inside a document on to the emitter, or holds it
+    /// back until the end of the document when anchors or sorted keys
+    /// are enabled.
+    fn emit_in_document(
+        &mut self,
+        event: Event<'_>,
+        info: NodeInfo,
+    ) -> Result<()> {
+        if let Some(omit) = &mut self.omit {
+            for (event, info) in omit.push(OwnedEvent::new(event), info)
+            {
+                self.emit_kept(event.to_event(), info)?;
+            }
+            return Ok(());
+        }
+        self.emit_kept(event, info)
+    }
+
+    /// Passes on an event that is not omitted.
+    fn emit_kept(
+        &mut self,
+        event: Event<'_>,
+        info: NodeInfo,
+    ) -> Result<()> {
+        match &mut self.document {
+            Some(document) => document.push(event, info),
+            None => self.emitter.emit(event)?,
+        }
+        Ok(())
+    }
+
+    /// Remembers `key` as the key of the next mapping entry, if the
+    /// entries of the current mapping are sorted.
+    fn sort_key<T>(&mut self, key: &T, is_struct: bool)
+    where
+        T: ?Sized + ser::Serialize,
+    {
+        // A map that may still turn out to be a tagged value has a single
+        // entry, which needs no sorting, and its own node has not started
+        // yet.
+        if let State::CheckForTag | State::CheckForDuplicateTag =
+            self.state
+        {
+            return;
+        }
+        self.key = match self.config.sort_keys {
+            SortKeys::Maps if !is_struct => value::to_value(key).ok(),
+            SortKeys::All => value::to_value(key).ok(),
+            _ => None,
+        };
+    }
+
     /// Emit a value start.
     pub fn value_start(&mut self) -> Result<()> {
         if self.depth == 0 {
             self.emitter.emit(Event::DocumentStart)?;
+            if self.config.anchors != AnchorMode::Off
+                || self.config.sort_keys != SortKeys::Off
+            {
+                self.document = Some(Document::default());
+            }
+            if self.config.omit_nulls
+                || self.config.omit_empty_collections
+            {
+                self.omit = Some(Omit::new(
+                    self.config.omit_nulls,
+                    self.config.omit_empty_collections,
+                ));
+            }
         }
         self.depth += 1;
         Ok(())
@@ -174,6 +1873,17 @@ where
     pub fn value_end(&mut self) -> Result<()> {
         self.depth -= 1;
         if self.depth == 0 {
+            self.omit = None;
+            if let Some(mut document) = self.document.take() {
+                if self.config.sort_keys != SortKeys::Off {
+                    document.sort_keys();
+                }
+                let repeated =
+                    self.config.anchors == AnchorMode::Repeated;
+                for event in document.resolve(repeated) {
+                    self.emitter.emit(event.to_event())?;
+                }
+            }
             self.emitter.emit(Event::DocumentEnd)?;
         }
         Ok(())
@@ -184,7 +1894,17 @@ where
         let state =
             mem::replace(&mut self.state, State::NothingInParticular);
         if let State::FoundTag(mut tag) = state {
-            if !tag.starts_with('!') {
+            // A tag under a declared `%TAG` prefix is passed through as is
+            // so that the emitter can write it in shorthand form.
+            let declared =
+                self.config.tag_prefixes.iter().any(|prefix| {
+                    !prefix.starts_with('!')
+                        && tagged::nobang(&tag)
+                            .starts_with(prefix.as_str())
+                });
+            if declared {
+                tag = tagged::nobang(&tag).to_owned();
+            } else if !tag.starts_with('!') {
                 tag.insert(0, '!');
             }
             Some(tag)
@@ -200,7 +1920,56 @@ where
             self.state = State::NothingInParticular;
             self.emit_mapping_start()?;
         } else if let State::CheckForDuplicateTag = self.state {
-            self.state = State::NothingInParticular;
+            self.state = match self.held_tag.take() {
+                Some(tag) => State::FoundTag(tag),
+                None => State::NothingInParticular,
+            };
+            self.emit_mapping_start()?;
+        }
+        Ok(())
+    }
+
+    /// Starts the single-entry mapping for an enum variant that comes
+    /// while the tag of another one is pending, as chosen by
+    /// [`NestedEnumStyle`]. The mapping is ended by
+    /// [`nested_variant_end`](Self::nested_variant_end).
+    fn nested_variant_start(&mut self, variant: &str) -> Result<()> {
+        let outer = match mem::replace(
+            &mut self.state,
+            State::NothingInParticular,
+        ) {
+            State::FoundTag(tag) => tag,
+            state => {
+                self.state = state;
+                return Ok(());
+            }
+        };
+        match self.config.nested_enums {
+            NestedEnumStyle::SingletonMap => {
+                self.state = State::FoundTag(outer);
+                self.emit_mapping_start()?;
+                self.serialize_str(variant)?;
+            }
+            NestedEnumStyle::OuterSingletonMap => {
+                self.emit_mapping_start()?;
+                self.serialize_str(tagged::nobang(&outer))?;
+                self.state = State::FoundTag(variant.to_owned());
+            }
+            NestedEnumStyle::Error => {
+                return Err(error::new(ErrorImpl::SerializeNestedEnum));
+            }
+        }
+        self.nested_ends.push(self.depth);
+        Ok(())
+    }
+
+    /// Ends the mapping started by
+    /// [`nested_variant_start`](Self::nested_variant_start) once its
+    /// value is complete.
+    fn nested_variant_end(&mut self) -> Result<()> {
+        if self.nested_ends.last() == Some(&self.depth) {
+            self.nested_ends.pop();
+            self.emit_mapping_end()?;
         }
         Ok(())
     }
@@ -223,6 +1992,7 @@ where
 
     fn serialize_bool(self, v: bool) -> Result<()> {
         self.emit_scalar(Scalar {
+            anchor: None,
             tag: None,
             value: if v { "true" } else { "false" },
             style: ScalarStyle::Plain,
@@ -231,6 +2001,7 @@ where
 
     fn serialize_i8(self, v: i8) -> Result<()> {
         self.emit_scalar(Scalar {
+            anchor: None,
             tag: None,
             value: itoa::Buffer::new().format(v),
             style: ScalarStyle::Plain,
@@ -239,6 +2010,7 @@ where
 
     fn serialize_i16(self, v: i16) -> Result<()> {
         self.emit_scalar(Scalar {
+            anchor: None,
             tag: None,
             value: itoa::Buffer::new().format(v),
             style: ScalarStyle::Plain,
@@ -247,6 +2019,7 @@ where
 
     fn serialize_i32(self, v: i32) -> Result<()> {
         self.emit_scalar(Scalar {
+            anchor: None,
             tag: None,
             value: itoa::Buffer::new().format(v),
             style: ScalarStyle::Plain,
@@ -255,6 +2028,7 @@ where
 
     fn serialize_i64(self, v: i64) -> Result<()> {
         self.emit_scalar(Scalar {
+            anchor: None,
             tag: None,
             value: itoa::Buffer::new().format(v),
             style: ScalarStyle::Plain,
@@ -263,6 +2037,7 @@ where
 
     fn serialize_i128(self, v: i128) -> Result<()> {
         self.emit_scalar(Scalar {
+            anchor: None,
             tag: None,
             value: itoa::Buffer::new().format(v),
             style: ScalarStyle::Plain,
@@ -271,6 +2046,7 @@ where
 
     fn serialize_u8(self, v: u8) -> Result<()> {
         self.emit_scalar(Scalar {
+            anchor: None,
             tag: None,
             value: itoa::Buffer::new().format(v),
             style: ScalarStyle::Plain,
@@ -279,6 +2055,7 @@ where
 
     fn serialize_u16(self, v: u16) -> Result<()> {
         self.emit_scalar(Scalar {
+            anchor: None,
             tag: None,
             value: itoa::Buffer::new().format(v),
             style: ScalarStyle::Plain,
@@ -287,6 +2064,7 @@ where
 
     fn serialize_u32(self, v: u32) -> Result<()> {
         self.emit_scalar(Scalar {
+            anchor: None,
             tag: None,
             value: itoa::Buffer::new().format(v),
             style: ScalarStyle::Plain,
@@ -295,6 +2073,7 @@ where
 
     fn serialize_u64(self, v: u64) -> Result<()> {
         self.emit_scalar(Scalar {
+            anchor: None,
             tag: None,
             value: itoa::Buffer::new().format(v),
             style: ScalarStyle::Plain,
@@ -303,6 +2082,7 @@ where
 
     fn serialize_u128(self, v: u128) -> Result<()> {
         self.emit_scalar(Scalar {
+            anchor: None,
             tag: None,
             value: itoa::Buffer::new().format(v),
             style: ScalarStyle::Plain,
@@ -310,39 +2090,28 @@ where
     }
 
     fn serialize_f32(self, v: f32) -> Result<()> {
-        let mut buffer = ryu::Buffer::new();
+        let (value, style) = format_float(v, &self.config)?;
         self.emit_scalar(Scalar {
+            anchor: None,
             tag: None,
-            value: match v.classify() {
-                num::FpCategory::Infinite if v.is_sign_positive() => {
-                    ".inf"
-                }
-                num::FpCategory::Infinite => "-.inf",
-                num::FpCategory::Nan => ".nan",
-                _ => buffer.format_finite(v),
-            },
-            style: ScalarStyle::Plain,
+            value: &value,
+            style,
         })
     }
 
     fn serialize_f64(self, v: f64) -> Result<()> {
-        let mut buffer = ryu::Buffer::new();
+        let (value, style) = format_float(v, &self.config)?;
         self.emit_scalar(Scalar {
+            anchor: None,
             tag: None,
-            value: match v.classify() {
-                num::FpCategory::Infinite if v.is_sign_positive() => {
-                    ".inf"
-                }
-                num::FpCategory::Infinite => "-.inf",
-                num::FpCategory::Nan => ".nan",
-                _ => buffer.format_finite(v),
-            },
-            style: ScalarStyle::Plain,
+            value: &value,
+            style,
         })
     }
 
     fn serialize_char(self, value: char) -> Result<()> {
         self.emit_scalar(Scalar {
+            anchor: None,
             tag: None,
             value: value.encode_utf8(&mut [0u8; 4]),
             style: ScalarStyle::SingleQuoted,
@@ -350,7 +2119,9 @@ where
     }
 
     fn serialize_str(self, value: &str) -> Result<()> {
-        struct InferScalarStyle;
+        struct InferScalarStyle {
+            policy: QuotingPolicy,
+        }
 
         impl Visitor<'_> for InferScalarStyle {
             type Value = ScalarStyle;
@@ -387,7 +2158,17 @@ where
             }
 
             fn visit_str<E>(self, v: &str) -> Result<Self::Value, E> {
-                if crate::de::ambiguous_string(v) {
+                let ambiguous = match self.policy {
+                    QuotingPolicy::Minimal => {
+                        crate::de::digits_but_not_number(v)
+                    }
+                    _ => {
+                        crate::de::ambiguous_string(v)
+                            || v == "<<"
+                            || v == "="
+                    }
+                };
+                if ambiguous {
                     Ok(ScalarStyle::SingleQuoted)
                 } else {
                     Ok(ScalarStyle::Any)
@@ -399,33 +2180,83 @@ where
             }
         }
 
-        let style = if value.contains('\n') {
-            ScalarStyle::Literal
-        } else {
-            let result = crate::de::visit_untagged_scalar(
-                InferScalarStyle,
-                value,
-                None,
-                libyml::parser::ScalarStyle::Plain,
-            );
-            result.unwrap_or(ScalarStyle::Any)
+        let infer = |policy| {
+            if value.contains('\n') {
+                ScalarStyle::Literal
+            } else {
+                let result = crate::de::visit_untagged_scalar(
+                    InferScalarStyle { policy },
+                    value,
+                    None,
+                    libyml::parser::ScalarStyle::Plain,
+                    crate::de::Resolution::Core,
+                );
+                result.unwrap_or(ScalarStyle::Any)
+            }
+        };
+
+        // A plain scalar is only safe if it would be read back as a
+        // string. The emitter itself falls back to a quoted style when
+        // the other styles can't represent the value.
+        let policy = self.config.quoting;
+        let style = match (self.forced_scalar_style.take(), policy) {
+            (Some(ScalarStyle::Plain), QuotingPolicy::Minimal) => {
+                infer(QuotingPolicy::Minimal)
+            }
+            (Some(ScalarStyle::Plain), _) => {
+                infer(QuotingPolicy::Yaml11Safe)
+            }
+            (Some(forced), _) => forced,
+            (None, QuotingPolicy::Always) => ScalarStyle::SingleQuoted,
+            (None, QuotingPolicy::AlwaysDouble) => {
+                ScalarStyle::DoubleQuoted
+            }
+            (None, policy) => infer(policy),
         };
 
         self.emit_scalar(Scalar {
+            anchor: None,
             tag: None,
             value,
             style,
         })
     }
 
-    fn serialize_bytes(self, _value: &[u8]) -> Result<()> {
-        Err(error::new(ErrorImpl::BytesUnsupported))
+    fn serialize_bytes(self, value: &[u8]) -> Result<()> {
+        // Long data is split into lines of 76 characters, as in MIME, and
+        // written as a literal block scalar.
+        let encoded = base64::encode(value);
+        let (value, style) = if encoded.len() > BINARY_LINE_WIDTH {
+            let lines: Vec<&str> = encoded
+                .as_bytes()
+                .chunks(BINARY_LINE_WIDTH)
+                .map(|line| str::from_utf8(line).unwrap_or_default())
+                .collect();
+            (lines.join("\n"), ScalarStyle::Literal)
+        } else {
+            (encoded, ScalarStyle::Plain)
+        };
+        self.emit_scalar(Scalar {
+            anchor: None,
+            tag: Some(Tag::BINARY.to_owned()),
+            value: &value,
+            style,
+        })
     }
 
     fn serialize_unit(self) -> Result<()> {
+        if NULL_VALUE.with(|flag| flag.replace(false)) {
+            self.none = true;
+        }
+        let value = match self.config.null_style {
+            NullStyle::Null => "null",
+            NullStyle::Tilde => "~",
+            NullStyle::Empty => "",
+        };
         self.emit_scalar(Scalar {
+            anchor: None,
             tag: None,
-            value: "null",
+            value,
             style: ScalarStyle::Plain,
         })
     }
@@ -445,12 +2276,57 @@ where
 
     fn serialize_newtype_struct<T>(
         self,
-        _name: &'static str,
+        name: &'static str,
         value: &T,
     ) -> Result<()>
     where
         T: ?Sized + ser::Serialize,
     {
+        match name {
+            FLOW => self.forced_flow = Some(true),
+            BLOCK => self.forced_flow = Some(false),
+            PLAIN => {
+                self.forced_scalar_style = Some(ScalarStyle::Plain)
+            }
+            SINGLE_QUOTED => {
+                self.forced_scalar_style =
+                    Some(ScalarStyle::SingleQuoted);
+            }
+            DOUBLE_QUOTED => {
+                self.forced_scalar_style =
+                    Some(ScalarStyle::DoubleQuoted);
+            }
+            LITERAL => {
+                self.forced_scalar_style = Some(ScalarStyle::Literal);
+            }
+            FOLDED => {
+                self.forced_scalar_style = Some(ScalarStyle::Folded)
+            }
+            SHARED if self.config.anchors != AnchorMode::Off => {
+                let address: *const T = value;
+                self.shared = Some(address.cast::<()>() as usize);
+            }
+            STYLED => {
+                let presentation = styled::with_serializing(|styled| {
+                    let (text, style) = styled.presentation()?;
+                    Some((
+                        text.to_owned(),
+                        style,
+                        styled.value.is_null(),
+                    ))
+                });
+                if let Some((text, style, null)) = presentation {
+                    self.none = null;
+                    return self.emit_scalar(Scalar {
+                        anchor: None,
+                        tag: None,
+                        value: &text,
+                        style,
+                    });
+                }
+            }
+            _ => {}
+        }
         value.serialize(self)
     }
 
@@ -464,14 +2340,29 @@ where
     where
         T: ?Sized + ser::Serialize,
     {
+        // Like a map, a variant that is the first key of a map still
+        // checked for a tag shows that the outer map is not a tag.
+        self.flush_mapping_start()?;
         if let State::FoundTag(_) = self.state {
-            return Err(error::new(ErrorImpl::SerializeNestedEnum));
+            self.nested_variant_start(variant)?;
+            value.serialize(&mut *self)?;
+            return self.nested_variant_end();
+        }
+        if self.config.canonical {
+            // Canonical scalars are quoted, so a scalar tagged with the
+            // variant would be read back as a string. Write the variant
+            // as a single-entry mapping to keep the tag of the value.
+            self.emit_mapping_start()?;
+            self.serialize_str(variant)?;
+            value.serialize(&mut *self)?;
+            return self.emit_mapping_end();
         }
         self.state = State::FoundTag(variant.to_owned());
         value.serialize(&mut *self)
     }
 
     fn serialize_none(self) -> Result<()> {
+        self.none = true;
         self.serialize_unit()
     }
 
@@ -514,10 +2405,12 @@ where
         variant: &'static str,
         _len: usize,
     ) -> Result<Self::SerializeTupleVariant> {
+        self.flush_mapping_start()?;
         if let State::FoundTag(_) = self.state {
-            return Err(error::new(ErrorImpl::SerializeNestedEnum));
+            self.nested_variant_start(variant)?;
+        } else {
+            self.state = State::FoundTag(variant.to_owned());
         }
-        self.state = State::FoundTag(variant.to_owned());
         self.emit_sequence_start()?;
         Ok(self)
     }
@@ -527,11 +2420,18 @@ where
         len: Option<usize>,
     ) -> Result<Self::SerializeMap> {
         if len == Some(1) {
-            self.state = if let State::FoundTag(_) = self.state {
-                self.emit_mapping_start()?;
-                State::CheckForDuplicateTag
-            } else {
-                State::CheckForTag
+            // A map that is the first key of a map still checked for a
+            // tag shows that the outer map is not a tag.
+            self.flush_mapping_start()?;
+            self.state = match mem::replace(
+                &mut self.state,
+                State::NothingInParticular,
+            ) {
+                State::FoundTag(tag) => {
+                    self.held_tag = Some(tag);
+                    State::CheckForDuplicateTag
+                }
+                _ => State::CheckForTag,
             };
         } else {
             self.emit_mapping_start()?;
@@ -555,10 +2455,12 @@ where
         variant: &'static str,
         _len: usize,
     ) -> Result<Self::SerializeStructVariant> {
+        self.flush_mapping_start()?;
         if let State::FoundTag(_) = self.state {
-            return Err(error::new(ErrorImpl::SerializeNestedEnum));
+            self.nested_variant_start(variant)?;
+        } else {
+            self.state = State::FoundTag(variant.to_owned());
         }
-        self.state = State::FoundTag(variant.to_owned());
         self.emit_mapping_start()?;
         Ok(self)
     }
@@ -573,14 +2475,16 @@ where
             match tagged::check_for_tag(value) {
                 MaybeTag::NotTag(string) => string,
                 MaybeTag::Tag(string) => {
-                    return if let State::CheckForDuplicateTag =
-                        self.state
-                    {
-                        Err(error::new(ErrorImpl::SerializeNestedEnum))
-                    } else {
-                        self.state = State::FoundTag(string);
-                        Ok(())
-                    };
+                    if let State::CheckForDuplicateTag = self.state {
+                        if let Some(tag) = self.held_tag.take() {
+                            self.state = State::FoundTag(tag);
+                        }
+                        return self.nested_variant_start(
+                            tagged::nobang(&string),
+                        );
+                    }
+                    self.state = State::FoundTag(string);
+                    return Ok(());
                 }
             }
         } else {
@@ -663,7 +2567,8 @@ where
     }
 
     fn end(self) -> Result<()> {
-        self.emit_sequence_end()
+        self.emit_sequence_end()?;
+        self.nested_variant_end()
     }
 }
 
@@ -679,6 +2584,7 @@ where
         T: ?Sized + ser::Serialize,
     {
         self.flush_mapping_start()?;
+        self.sort_key(key, false);
         key.serialize(&mut **self)
     }
 
@@ -686,6 +2592,7 @@ where
     where
         T: ?Sized + ser::Serialize,
     {
+        self.variant = VARIANT_CONTENT.with(|flag| flag.replace(false));
         value.serialize(&mut **self)
     }
 
@@ -698,8 +2605,13 @@ where
         K: ?Sized + ser::Serialize,
         V: ?Sized + ser::Serialize,
     {
+        self.sort_key(key, false);
         key.serialize(&mut **self)?;
-        let tagged = matches!(self.state, State::FoundTag(_));
+        // The map is written as a tag, or the key started the mapping
+        // around a nested tagged value, which ends it.
+        let tagged = matches!(self.state, State::FoundTag(_))
+            || self.nested_ends.last() == Some(&self.depth);
+        self.variant = VARIANT_CONTENT.with(|flag| flag.replace(false));
         value.serialize(&mut **self)?;
         if tagged {
             self.state = State::AlreadyTagged;
@@ -708,14 +2620,12 @@ where
     }
 
     fn end(self) -> Result<()> {
-        if let State::CheckForTag = self.state {
-            self.emit_mapping_start()?;
-        }
+        self.flush_mapping_start()?;
         if !matches!(self.state, State::AlreadyTagged) {
             self.emit_mapping_end()?;
         }
         self.state = State::NothingInParticular;
-        Ok(())
+        self.nested_variant_end()
     }
 }
 
@@ -734,6 +2644,7 @@ where
     where
         V: ?Sized + ser::Serialize,
     {
+        self.sort_key(key, true);
         self.serialize_str(key)?;
         value.serialize(&mut **self)
     }
@@ -758,12 +2669,358 @@ where
     where
         V: ?Sized + ser::Serialize,
     {
+        self.sort_key(field, true);
         self.serialize_str(field)?;
         v.serialize(&mut **self)
     }
 
     fn end(self) -> Result<()> {
-        self.emit_mapping_end()
+        self.emit_mapping_end()?;
+        self.nested_variant_end()
+    }
+}
+
+/// A float type that [`format_float`] can write.
+trait Float: ryu::Float + Display {
+    fn classify(self) -> num::FpCategory;
+    fn is_sign_positive(self) -> bool;
+}
+
+impl Float for f32 {
+    fn classify(self) -> num::FpCategory {
+        f32::classify(self)
+    }
+
+    fn is_sign_positive(self) -> bool {
+        f32::is_sign_positive(self)
+    }
+}
+
+impl Float for f64 {
+    fn classify(self) -> num::FpCategory {
+        f64::classify(self)
+    }
+
+    fn is_sign_positive(self) -> bool {
+        f64::is_sign_positive(self)
+    }
+}
+
+/// Formats a float as the serializer settings ask, returning the text
+/// and the style to write it in.
+fn format_float<F>(
+    v: F,
+    config: &SerializerConfig,
+) -> Result<(String, ScalarStyle)>
+where
+    F: Float,
+{
+    let non_finite = match v.classify() {
+        num::FpCategory::Infinite if v.is_sign_positive() => {
+            Some(".inf")
+        }
+        num::FpCategory::Infinite => Some("-.inf"),
+        num::FpCategory::Nan => Some(".nan"),
+        _ => None,
+    };
+    if let Some(value) = non_finite {
+        let style = match config.non_finite_floats {
+            NonFiniteFloats::Emit => ScalarStyle::Plain,
+            NonFiniteFloats::Quote => ScalarStyle::SingleQuoted,
+            NonFiniteFloats::Error => {
+                return Err(error::new(
+                    ErrorImpl::SerializeNonFiniteFloat,
+                ));
+            }
+        };
+        return Ok((value.to_owned(), style));
+    }
+
+    // `Display` writes the shortest digits that read back as the same
+    // number, like `ryu`, but never with an exponent.
+    let mut value = match config.float_format {
+        FloatFormat::Shortest => {
+            ryu::Buffer::new().format_finite(v).to_owned()
+        }
+        FloatFormat::NoExponent => v.to_string(),
+        FloatFormat::Fixed(digits) => format!("{:.*}", digits, v),
+    };
+    if config.float_decimal_point && !value.contains('.') {
+        let end = value.find('e').unwrap_or(value.len());
+        value.insert_str(end, ".0");
+    }
+    Ok((value, ScalarStyle::Plain))
+}
+
+/// Returns the tag that a scalar without one is read with: the tag of
+/// the value it is parsed as if it is plain, or `!!str` if it is quoted.
+fn resolved_tag(value: &str, style: ScalarStyle) -> String {
+    struct ResolveTag;
+
+    impl Visitor<'_> for ResolveTag {
+        type Value = &'static str;
+
+        fn expecting(
+            &self,
+            formatter: &mut fmt::Formatter<'_>,
+        ) -> fmt::Result {
+            formatter.write_str("a scalar")
+        }
+
+        fn visit_bool<E>(self, _v: bool) -> Result<Self::Value, E> {
+            Ok(Tag::BOOL)
+        }
+
+        fn visit_i64<E>(self, _v: i64) -> Result<Self::Value, E> {
+            Ok(Tag::INT)
+        }
+
+        fn visit_i128<E>(self, _v: i128) -> Result<Self::Value, E> {
+            Ok(Tag::INT)
+        }
+
+        fn visit_u64<E>(self, _v: u64) -> Result<Self::Value, E> {
+            Ok(Tag::INT)
+        }
+
+        fn visit_u128<E>(self, _v: u128) -> Result<Self::Value, E> {
+            Ok(Tag::INT)
+        }
+
+        fn visit_f64<E>(self, _v: f64) -> Result<Self::Value, E> {
+            Ok(Tag::FLOAT)
+        }
+
+        fn visit_str<E>(self, _v: &str) -> Result<Self::Value, E> {
+            Ok(Tag::STR)
+        }
+
+        fn visit_unit<E>(self) -> Result<Self::Value, E> {
+            Ok(Tag::NULL)
+        }
+    }
+
+    let tag = match style {
+        ScalarStyle::Any | ScalarStyle::Plain => {
+            crate::de::visit_untagged_scalar(
+                ResolveTag,
+                value,
+                None,
+                libyml::parser::ScalarStyle::Plain,
+                crate::de::Resolution::Core,
+            )
+            .unwrap_or(Tag::STR)
+        }
+        _ => Tag::STR,
+    };
+    tag.to_owned()
+}
+
+/// Writes a stream of YAML documents, one for each value, to an
+/// `io::Write`.
+///
+/// Every document starts with `---` and is flushed to the writer as soon
+/// as it is complete, so the stream can be followed while it is written,
+/// and a stream that was finished can be continued by a new
+/// `DocumentWriter` that appends to the same file.
+///
+/// ```
+/// use serde_yml::DocumentWriter;
+/// use std::collections::BTreeMap;
+///
+/// let mut writer = DocumentWriter::new(Vec::new());
+/// writer.write(&BTreeMap::from([("kind", "Service")])).unwrap();
+/// writer.write(&BTreeMap::from([("kind", "Deployment")])).unwrap();
+/// let yaml = writer.finish().unwrap();
+/// assert_eq!(yaml, b"---\nkind: Service\n---\nkind: Deployment\n");
+/// ```
+#[derive(Debug)]
+pub struct DocumentWriter<W>
+where
+    W: io::Write,
+{
+    serializer: Serializer<W>,
+}
+
+impl<W> DocumentWriter<W>
+where
+    W: io::Write,
+{
+    /// Creates a writer with the default output settings.
+    pub fn new(writer: W) -> Self {
+        DocumentWriter::with_builder(writer, &SerializerBuilder::new())
+    }
+
+    /// Creates a writer with the output settings of `builder`. Every
+    /// document starts with `---`, whatever
+    /// [`explicit_start`](SerializerBuilder::explicit_start) is set to.
+    pub fn with_builder(
+        writer: W,
+        builder: &SerializerBuilder,
+    ) -> Self {
+        let builder = builder.clone().explicit_start(true);
+        DocumentWriter {
+            serializer: builder.build(writer),
+        }
+    }
+
+    /// Writes `value` as the next document and flushes it to the writer.
+    ///
+    /// If serialization fails, part of the document may have been
+    /// written, and the writer should not be used for more documents.
+    pub fn write<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + ser::Serialize,
+    {
+        value.serialize(&mut self.serializer)?;
+        self.serializer.flush()
+    }
+
+    /// Ends the stream and returns the underlying writer.
+    pub fn finish(self) -> Result<W> {
+        self.serializer.into_inner()
+    }
+}
+
+/// Writes a document whose root is a sequence, one element at a time.
+///
+/// Each element is passed on to the writer as soon as it is serialized,
+/// so the memory used stays the same however many elements are written.
+/// The exceptions are the settings that need the whole document before
+/// any of it can be written: [`anchors`](SerializerBuilder::anchors),
+/// [`sort_keys`](SerializerBuilder::sort_keys),
+/// [`indent_sequences`](SerializerBuilder::indent_sequences),
+/// [`complex_keys`](SerializerBuilder::complex_keys) with
+/// [`ComplexKeyStyle::Flow`],
+/// [`header_comment`](SerializerBuilder::header_comment) and
+/// [`comment`](SerializerBuilder::comment).
+///
+/// ```
+/// use serde_yml::SequenceWriter;
+///
+/// let mut writer = SequenceWriter::new(Vec::new()).unwrap();
+/// for id in 1..=3 {
+///     writer.write(&id).unwrap();
+/// }
+/// let yaml = writer.finish().unwrap();
+/// assert_eq!(yaml, b"- 1\n- 2\n- 3\n");
+/// ```
+#[derive(Debug)]
+pub struct SequenceWriter<W>
+where
+    W: io::Write,
+{
+    serializer: Serializer<W>,
+}
+
+impl<W> SequenceWriter<W>
+where
+    W: io::Write,
+{
+    /// Starts a sequence with the default output settings.
+    pub fn new(writer: W) -> Result<Self> {
+        SequenceWriter::with_builder(writer, &SerializerBuilder::new())
+    }
+
+    /// Starts a sequence with the output settings of `builder`.
+    pub fn with_builder(
+        writer: W,
+        builder: &SerializerBuilder,
+    ) -> Result<Self> {
+        let mut serializer = builder.build(writer);
+        serializer.emit_sequence_start()?;
+        Ok(SequenceWriter { serializer })
+    }
+
+    /// Writes `value` as the next element of the sequence.
+    pub fn write<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + ser::Serialize,
+    {
+        value.serialize(&mut self.serializer)
+    }
+
+    /// Flushes what has been written so far to the writer.
+    pub fn flush(&mut self) -> Result<()> {
+        self.serializer.flush()
+    }
+
+    /// Ends the sequence and the document, and returns the underlying
+    /// writer.
+    pub fn finish(mut self) -> Result<W> {
+        self.serializer.emit_sequence_end()?;
+        self.serializer.into_inner()
+    }
+}
+
+/// Writes a document whose root is a mapping, one entry at a time.
+///
+/// Like [`SequenceWriter`], each entry is passed on to the writer as soon
+/// as it is serialized.
+///
+/// ```
+/// use serde_yml::MappingWriter;
+///
+/// let mut writer = MappingWriter::new(Vec::new()).unwrap();
+/// writer.write_entry("web", &80).unwrap();
+/// writer.write_entry("db", &5432).unwrap();
+/// let yaml = writer.finish().unwrap();
+/// assert_eq!(yaml, b"web: 80\ndb: 5432\n");
+/// ```
+#[derive(Debug)]
+pub struct MappingWriter<W>
+where
+    W: io::Write,
+{
+    serializer: Serializer<W>,
+}
+
+impl<W> MappingWriter<W>
+where
+    W: io::Write,
+{
+    /// Starts a mapping with the default output settings.
+    pub fn new(writer: W) -> Result<Self> {
+        MappingWriter::with_builder(writer, &SerializerBuilder::new())
+    }
+
+    /// Starts a mapping with the output settings of `builder`.
+    pub fn with_builder(
+        writer: W,
+        builder: &SerializerBuilder,
+    ) -> Result<Self> {
+        let mut serializer = builder.build(writer);
+        serializer.emit_mapping_start()?;
+        Ok(MappingWriter { serializer })
+    }
+
+    /// Writes the next entry of the mapping.
+    pub fn write_entry<K, V>(
+        &mut self,
+        key: &K,
+        value: &V,
+    ) -> Result<()>
+    where
+        K: ?Sized + ser::Serialize,
+        V: ?Sized + ser::Serialize,
+    {
+        ser::SerializeMap::serialize_entry(
+            &mut &mut self.serializer,
+            key,
+            value,
+        )
+    }
+
+    /// Flushes what has been written so far to the writer.
+    pub fn flush(&mut self) -> Result<()> {
+        self.serializer.flush()
+    }
+
+    /// Ends the mapping and the document, and returns the underlying
+    /// writer.
+    pub fn finish(mut self) -> Result<W> {
+        self.serializer.emit_mapping_end()?;
+        self.serializer.into_inner()
     }
 }
 
@@ -793,3 +3050,68 @@ where
     String::from_utf8(vec)
         .map_err(|error| error::new(ErrorImpl::FromUtf8(error)))
 }
+
+/// Serialize the given data structure as YAML into the IO stream, using
+/// the output settings of `builder`.
+///
+/// Serialization can fail if `T`'s implementation of `Serialize` decides to
+/// return an error.
+pub fn to_writer_with<W, T>(
+    writer: W,
+    value: &T,
+    builder: &SerializerBuilder,
+) -> Result<()>
+where
+    W: io::Write,
+    T: ?Sized + ser::Serialize,
+{
+    let mut serializer = builder.build(writer);
+    value.serialize(&mut serializer)
+}
+
+/// Serialize each value of `values` as a YAML document into the IO
+/// stream, with the documents separated by `---`.
+///
+/// Each document is flushed as soon as it is written. See
+/// [`DocumentWriter`] to add documents one at a time or with custom
+/// output settings.
+///
+/// ```
+/// let yaml = {
+///     let mut buffer = Vec::new();
+///     serde_yml::to_writer_documents(&mut buffer, [1, 2]).unwrap();
+///     buffer
+/// };
+/// assert_eq!(yaml, b"--- 1\n--- 2\n");
+/// ```
+pub fn to_writer_documents<W, I>(writer: W, values: I) -> Result<()>
+where
+    W: io::Write,
+    I: IntoIterator,
+    I::Item: ser::Serialize,
+{
+    let mut writer = DocumentWriter::new(writer);
+    for value in values {
+        writer.write(&value)?;
+    }
+    writer.finish()?;
+    Ok(())
+}
+
+/// Serialize the given data structure as a String of YAML, using the
+/// output settings of `builder`.
+///
+/// Serialization can fail if `T`'s implementation of `Serialize` decides to
+/// return an error.
+pub fn to_string_with<T>(
+    value: &T,
+    builder: &SerializerBuilder,
+) -> Result<String>
+where
+    T: ?Sized + ser::Serialize,
+{
+    let mut vec = Vec::with_capacity(128);
+    to_writer_with(&mut vec, value, builder)?;
+    String::from_utf8(vec)
+        .map_err(|error| error::new(ErrorImpl::FromUtf8(error)))
+}
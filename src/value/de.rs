@@ -3,6 +3,7 @@
 // SPDX-License-Identifier: Apache-2.0 OR MIT indicates dual licensing under Apache 2.0 or MIT licenses.
 // Copyright © 2024 Serde YML, Seamless YAML Serialization for Rust. All rights reserved.
 
+use crate::value::styled;
 use crate::value::tagged::{self, TagStringVisitor};
 use crate::value::TaggedValue;
 use crate::{number, Error, Mapping, Sequence, Value};
@@ -71,6 +72,20 @@ impl<'de> Deserialize<'de> for Value {
                 Ok(Value::String(s.to_owned()))
             }
 
+            fn visit_bytes<E>(self, v: &[u8]) -> Result<Value, E>
+            where
+                E: de::Error,
+            {
+                Ok(Value::Binary(v.to_owned()))
+            }
+
+            fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<Value, E>
+            where
+                E: de::Error,
+            {
+                Ok(Value::Binary(v))
+            }
+
             fn visit_string<E>(self, s: String) -> Result<Value, E>
             where
                 E: de::Error,
@@ -132,6 +147,17 @@ impl<'de> Deserialize<'de> for Value {
                 let value = contents.newtype_variant()?;
                 Ok(Value::Tagged(Box::new(TaggedValue { tag, value })))
             }
+
+            fn visit_newtype_struct<D>(
+                self,
+                deserializer: D,
+            ) -> Result<Value, D::Error>
+            where
+                D: Deserializer<'de>,
+            {
+                let styled = styled::deserialize(deserializer)?;
+                Ok(Value::Styled(Box::new(styled)))
+            }
         }
 
         deserializer.deserialize_any(ValueVisitor)
@@ -240,6 +266,10 @@ impl<'de> Deserializer<'de> for Value {
             Value::Sequence(v) => visit_sequence(v, visitor),
             Value::Mapping(v) => visit_mapping(v, visitor),
             Value::Tagged(tagged) => visitor.visit_enum(*tagged),
+            Value::Binary(v) => visitor.visit_byte_buf(v),
+            Value::Styled(styled) => {
+                styled.value.deserialize_any(visitor)
+            }
         }
     }
 
@@ -381,6 +411,7 @@ impl<'de> Deserializer<'de> for Value {
         match self.untag() {
             Value::String(v) => visitor.visit_string(v),
             Value::Sequence(v) => visit_sequence(v, visitor),
+            Value::Binary(v) => visitor.visit_byte_buf(v),
             other => Err(other.invalid_type(&visitor)),
         }
     }
@@ -392,9 +423,9 @@ impl<'de> Deserializer<'de> for Value {
     where
         V: Visitor<'de>,
     {
-        match self {
+        match self.unstyle() {
             Value::Null => visitor.visit_none(),
-            _ => visitor.visit_some(self),
+            other => visitor.visit_some(other),
         }
     }
 
@@ -402,9 +433,9 @@ impl<'de> Deserializer<'de> for Value {
     where
         V: Visitor<'de>,
     {
-        match self {
+        match self.unstyle() {
             Value::Null => visitor.visit_unit(),
-            _ => Err(self.invalid_type(&visitor)),
+            other => Err(other.invalid_type(&visitor)),
         }
     }
 
@@ -497,7 +528,7 @@ impl<'de> Deserializer<'de> for Value {
         V: Visitor<'de>,
     {
         let tag;
-        visitor.visit_enum(match self {
+        visitor.visit_enum(match self.unstyle() {
             Value::Tagged(tagged) => EnumDeserializer {
                 tag: {
                     tag = tagged.tag.string;
@@ -512,6 +543,24 @@ impl<'de> Deserializer<'de> for Value {
                 },
                 value: None,
             },
+            Value::Mapping(mapping) if mapping.len() == 1 => {
+                let (key, value) = mapping.into_iter().next().unwrap();
+                match key.unstyle() {
+                    Value::String(variant) => EnumDeserializer {
+                        tag: {
+                            tag = variant;
+                            &tag
+                        },
+                        value: Some(value),
+                    },
+                    other => {
+                        return Err(Error::invalid_type(
+                            other.unexpected(),
+                            &"a string variant name",
+                        ));
+                    }
+                }
+            }
             other => {
                 return Err(Error::invalid_type(
                     other.unexpected(),
@@ -811,6 +860,10 @@ impl<'de> Deserializer<'de> for &'de Value {
                     value: Some(cloned_tagged.clone().value),
                 })
             }
+            Value::Binary(v) => visitor.visit_borrowed_bytes(v),
+            Value::Styled(styled) => {
+                (&styled.value).deserialize_any(visitor)
+            }
         }
     }
 
@@ -942,6 +995,7 @@ impl<'de> Deserializer<'de> for &'de Value {
         match self.untag_ref() {
             Value::String(v) => visitor.visit_borrowed_str(v),
             Value::Sequence(v) => visit_sequence_ref(v, visitor),
+            Value::Binary(v) => visitor.visit_borrowed_bytes(v),
             other => Err(other.invalid_type(&visitor)),
         }
     }
@@ -963,9 +1017,9 @@ impl<'de> Deserializer<'de> for &'de Value {
     where
         V: Visitor<'de>,
     {
-        match self {
+        match self.unstyle_ref() {
             Value::Null => visitor.visit_none(),
-            _ => visitor.visit_some(self),
+            other => visitor.visit_some(other),
         }
     }
 
@@ -973,9 +1027,9 @@ impl<'de> Deserializer<'de> for &'de Value {
     where
         V: Visitor<'de>,
     {
-        match self {
+        match self.unstyle_ref() {
             Value::Null => visitor.visit_unit(),
-            _ => Err(self.invalid_type(&visitor)),
+            other => Err(other.invalid_type(&visitor)),
         }
     }
 
@@ -1071,7 +1125,7 @@ impl<'de> Deserializer<'de> for &'de Value {
     where
         V: Visitor<'de>,
     {
-        visitor.visit_enum(match self {
+        visitor.visit_enum(match self.unstyle_ref() {
             Value::Tagged(tagged) => EnumRefDeserializer {
                 tag: tagged::nobang(&tagged.tag.string),
                 value: Some(&tagged.value),
@@ -1080,6 +1134,21 @@ impl<'de> Deserializer<'de> for &'de Value {
                 tag: variant,
                 value: None,
             },
+            Value::Mapping(mapping) if mapping.len() == 1 => {
+                let (key, value) = mapping.iter().next().unwrap();
+                match key.unstyle_ref() {
+                    Value::String(variant) => EnumRefDeserializer {
+                        tag: variant,
+                        value: Some(value),
+                    },
+                    other => {
+                        return Err(Error::invalid_type(
+                            other.unexpected(),
+                            &"a string variant name",
+                        ));
+                    }
+                }
+            }
             other => {
                 return Err(Error::invalid_type(
                     other.unexpected(),
@@ -1377,6 +1446,8 @@ impl Value {
             Value::Sequence(_) => Unexpected::Seq,
             Value::Mapping(_) => Unexpected::Map,
             Value::Tagged(_) => Unexpected::Enum,
+            Value::Binary(v) => Unexpected::Bytes(v),
+            Value::Styled(styled) => styled.value.unexpected(),
         }
     }
 }
@@ -42,6 +42,12 @@ impl Debug for Value {
             }
             Value::Mapping(mapping) => Debug::fmt(mapping, formatter),
             Value::Tagged(tagged) => Debug::fmt(tagged, formatter),
+            Value::Binary(bytes) => {
+                write!(formatter, "Binary({:?})", bytes)
+            }
+            Value::Styled(styled) => {
+                Debug::fmt(&styled.value, formatter)
+            }
         }
     }
 }
@@ -75,6 +75,7 @@ impl Index for usize {
                     return map.entry(n).or_insert(Value::Null);
                 }
                 Value::Tagged(tagged) => v = &mut tagged.value,
+                Value::Styled(styled) => v = &mut styled.value,
                 _ => panic!(
                     "cannot access index {} of YAML {}",
                     self,
@@ -119,6 +120,11 @@ where
     I: ?Sized + mapping::Index + ToOwned + Debug,
     Value: From<I::Owned>,
 {
+    // A null written in the source, like `~`, is replaced too.
+    if matches!(v, Value::Styled(styled) if styled.value == Value::Null)
+    {
+        *v = Value::Null;
+    }
     if let Value::Null = *v {
         *v = Value::Mapping(Mapping::new());
         return match v {
@@ -139,6 +145,7 @@ where
                     .or_insert(Value::Null);
             }
             Value::Tagged(tagged) => v = &mut tagged.value,
+            Value::Styled(styled) => v = &mut styled.value,
             _ => panic!(
                 "cannot access key {:?} in YAML {}",
                 index,
@@ -223,7 +230,8 @@ impl fmt::Display for Type<'_> {
             Value::String(_) => formatter.write_str("string"),
             Value::Sequence(_) => formatter.write_str("sequence"),
             Value::Mapping(_) => formatter.write_str("mapping"),
-            Value::Tagged(_) => unreachable!(),
+            Value::Binary(_) => formatter.write_str("binary"),
+            Value::Tagged(_) | Value::Styled(_) => unreachable!(),
         }
     }
 }
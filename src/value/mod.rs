@@ -11,6 +11,7 @@ mod from;
 mod index;
 mod partial_eq;
 mod ser;
+pub(crate) mod styled;
 /// A representation of YAML's `!Tag` syntax, used for enums.
 pub mod tagged;
 
@@ -20,19 +21,21 @@ use serde::{
     Serialize,
 };
 use std::{
+    cmp::Ordering,
     hash::{Hash, Hasher},
     mem,
 };
 
 pub use self::index::Index;
 pub use self::ser::Serializer;
+pub use self::styled::StyledValue;
 pub use self::tagged::{Tag, TaggedValue};
 #[doc(inline)]
 pub use crate::mapping::Mapping;
 pub use crate::number::Number;
 
 /// Represents any valid YAML value.
-#[derive(Clone, PartialEq, PartialOrd)]
+#[derive(Clone)]
 pub enum Value {
     /// Represents a YAML null value.
     Null,
@@ -50,6 +53,14 @@ pub enum Value {
     Mapping(Mapping),
     /// A representation of YAML's `!Tag` syntax, used for enums.
     Tagged(Box<TaggedValue>),
+    /// Represents binary data, written as a base64 `!!binary` scalar.
+    Binary(Vec<u8>),
+    /// A scalar that remembers the style and text it was written with.
+    /// Only loaded when [`DeserializerBuilder::preserve_scalars`] is
+    /// enabled, and transparent to everything but the serializer.
+    ///
+    /// [`DeserializerBuilder::preserve_scalars`]: crate::DeserializerBuilder::preserve_scalars
+    Styled(Box<StyledValue>),
 }
 
 /// The default value is `Value::Null`.
@@ -494,6 +505,39 @@ impl Value {
         }
     }
 
+    /// Returns true if the `Value` is binary data. Returns false
+    /// otherwise.
+    ///
+    /// ```
+    /// # use serde_yml::Value;
+    /// let v: Value = serde_yml::from_str("!!binary aGVsbG8=").unwrap();
+    /// assert!(v.is_binary());
+    /// ```
+    ///
+    /// ```
+    /// # use serde_yml::Value;
+    /// let v: Value = serde_yml::from_str("aGVsbG8=").unwrap();
+    /// assert!(!v.is_binary());
+    /// ```
+    pub fn is_binary(&self) -> bool {
+        self.as_binary().is_some()
+    }
+
+    /// If the `Value` is binary data, returns the associated bytes.
+    /// Returns None otherwise.
+    ///
+    /// ```
+    /// # use serde_yml::Value;
+    /// let v: Value = serde_yml::from_str("!!binary aGVsbG8=").unwrap();
+    /// assert_eq!(v.as_binary(), Some(&b"hello"[..]));
+    /// ```
+    pub fn as_binary(&self) -> Option<&[u8]> {
+        match self.untag_ref() {
+            Value::Binary(bytes) => Some(bytes),
+            _ => None,
+        }
+    }
+
     /// Returns true if the `Value` is a sequence. Returns false otherwise.
     ///
     /// ```
@@ -630,6 +674,10 @@ impl Value {
     /// The intended use of this in YAML is described in
     /// <https://yaml.org/type/merge.html>.
     ///
+    /// A `Value` keeps the `<<` keys as they were written until this is
+    /// called. Structs and maps deserialized straight from YAML have
+    /// their `<<` keys merged already.
+    ///
     /// ```
     /// use serde_yml::Value;
     ///
@@ -708,6 +756,92 @@ impl Value {
         }
         Ok(())
     }
+
+    /// Sorts the entries of every mapping in this value by key, including
+    /// mappings inside keys. See [`Mapping::sort_keys_recursive`].
+    ///
+    /// ```
+    /// use serde_yml::Value;
+    ///
+    /// let mut value: Value =
+    ///     serde_yml::from_str("{b: 1, a: [{z: 1, w: 2}], 10: x, 9: v}")
+    ///         .unwrap();
+    /// value.sort_keys_recursive();
+    ///
+    /// assert_eq!(
+    ///     serde_yml::to_string(&value).unwrap(),
+    ///     "9: v\n10: x\na:\n- w: 2\n  z: 1\nb: 1\n",
+    /// );
+    /// ```
+    pub fn sort_keys_recursive(&mut self) {
+        match self {
+            Value::Mapping(mapping) => mapping.sort_keys_recursive(),
+            Value::Sequence(sequence) => {
+                for value in sequence {
+                    value.sort_keys_recursive();
+                }
+            }
+            Value::Tagged(tagged) => tagged.value.sort_keys_recursive(),
+            _ => {}
+        }
+    }
+}
+
+impl Value {
+    /// The position of the variant in the declaration of `Value`, which
+    /// orders values of different types.
+    fn variant_index(&self) -> u8 {
+        match self {
+            Value::Null => 0,
+            Value::Bool(_) => 1,
+            Value::Number(_) => 2,
+            Value::String(_) => 3,
+            Value::Sequence(_) => 4,
+            Value::Mapping(_) => 5,
+            Value::Tagged(_) => 6,
+            Value::Binary(_) => 7,
+            Value::Styled(styled) => styled.value.variant_index(),
+        }
+    }
+}
+
+// A styled scalar compares like its value.
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Value::Styled(a), b) => a.value == *b,
+            (a, Value::Styled(b)) => *a == b.value,
+            (Value::Null, Value::Null) => true,
+            (Value::Bool(a), Value::Bool(b)) => a == b,
+            (Value::Number(a), Value::Number(b)) => a == b,
+            (Value::String(a), Value::String(b)) => a == b,
+            (Value::Sequence(a), Value::Sequence(b)) => a == b,
+            (Value::Mapping(a), Value::Mapping(b)) => a == b,
+            (Value::Tagged(a), Value::Tagged(b)) => a == b,
+            (Value::Binary(a), Value::Binary(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl PartialOrd for Value {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        match (self, other) {
+            (Value::Styled(a), b) => a.value.partial_cmp(b),
+            (a, Value::Styled(b)) => a.partial_cmp(&b.value),
+            (Value::Null, Value::Null) => Some(Ordering::Equal),
+            (Value::Bool(a), Value::Bool(b)) => a.partial_cmp(b),
+            (Value::Number(a), Value::Number(b)) => a.partial_cmp(b),
+            (Value::String(a), Value::String(b)) => a.partial_cmp(b),
+            (Value::Sequence(a), Value::Sequence(b)) => {
+                a.partial_cmp(b)
+            }
+            (Value::Mapping(a), Value::Mapping(b)) => a.partial_cmp(b),
+            (Value::Tagged(a), Value::Tagged(b)) => a.partial_cmp(b),
+            (Value::Binary(a), Value::Binary(b)) => a.partial_cmp(b),
+            (a, b) => a.variant_index().partial_cmp(&b.variant_index()),
+        }
+    }
 }
 
 impl Eq for Value {}
@@ -716,6 +850,9 @@ impl Eq for Value {}
 // mapping.rs in order for value[str] indexing to work.
 impl Hash for Value {
     fn hash<H: Hasher>(&self, state: &mut H) {
+        if let Value::Styled(styled) = self {
+            return styled.value.hash(state);
+        }
         mem::discriminant(self).hash(state);
         match self {
             Value::Null => {}
@@ -725,6 +862,8 @@ impl Hash for Value {
             Value::Sequence(v) => v.hash(state),
             Value::Mapping(v) => v.hash(state),
             Value::Tagged(v) => v.hash(state),
+            Value::Binary(v) => v.hash(state),
+            Value::Styled(v) => v.value.hash(state),
         }
     }
 }
@@ -21,7 +21,9 @@ impl Serialize for Value {
         S: serde::Serializer,
     {
         match self {
-            Value::Null => serializer.serialize_unit(),
+            Value::Null => crate::ser::serialize_null_value(|| {
+                serializer.serialize_unit()
+            }),
             Value::Bool(b) => serializer.serialize_bool(*b),
             Value::Number(n) => n.serialize(serializer),
             Value::String(s) => serializer.serialize_str(s),
@@ -36,6 +38,8 @@ impl Serialize for Value {
                 map.end()
             }
             Value::Tagged(tagged) => tagged.serialize(serializer),
+            Value::Binary(bytes) => serializer.serialize_bytes(bytes),
+            Value::Styled(styled) => styled.serialize(serializer),
         }
     }
 }
@@ -146,11 +150,7 @@ impl ser::Serializer for Serializer {
     }
 
     fn serialize_bytes(self, value: &[u8]) -> Result<Value> {
-        let vec = value
-            .iter()
-            .map(|&b| Value::Number(Number::from(b)))
-            .collect();
-        Ok(Value::Sequence(vec))
+        Ok(Value::Binary(value.to_owned()))
     }
 
     fn serialize_unit(self) -> Result<Value> {
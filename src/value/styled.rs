@@ -0,0 +1,279 @@
+// Copyright notice and licensing information.
+// SPDX-License-Identifier: Apache-2.0 OR MIT indicates dual licensing under Apache 2.0 or MIT licenses.
+// Copyright © 2024 Serde YML, Seamless YAML Serialization for Rust. All rights reserved.
+
+use crate::{
+    libyml::{emitter, parser::ScalarStyle},
+    modules::error::Error,
+    value::Value,
+};
+use serde::{
+    de::{
+        Deserialize, Deserializer, Error as _, SeqAccess, Unexpected,
+        Visitor,
+    },
+    forward_to_deserialize_any,
+    ser::{Serialize, Serializer},
+};
+use std::{cell::Cell, fmt, ptr};
+
+/// Name of the newtype struct through which a [`StyledValue`] is passed
+/// between this crate's deserializer or serializer and [`Value`].
+pub(crate) const STYLED: &str = "$serde_yml::private::Styled";
+
+/// A scalar together with the style and the text it was written with.
+///
+/// Scalars are loaded as `StyledValue`s when
+/// [`DeserializerBuilder::preserve_scalars`] is enabled. The wrapper is
+/// transparent: the accessors of [`Value`], indexing, comparisons and
+/// hashing all look through it to the inner value. As long as the value
+/// still matches the text, [`Serializer`](crate::Serializer) writes the
+/// scalar back in its style and with its text, so `0x1F` stays `0x1F`
+/// instead of becoming `31`.
+///
+/// The source of the scalar is not kept verbatim. Its text is what the
+/// scalar reads as, after escapes are replaced and lines are folded, and
+/// the emitter escapes and folds that text again in the same style. So
+/// `"\u00e9\t"` is written back as `"é\t"`, and a folded scalar written
+/// over several lines may be written back on fewer of them.
+///
+/// [`DeserializerBuilder::preserve_scalars`]: crate::DeserializerBuilder::preserve_scalars
+///
+/// # Examples
+///
+/// ```
+/// use serde::Deserialize;
+/// use serde_yml::{DeserializerBuilder, Value};
+///
+/// let yaml = "mask: 0x1F\nname: \"quoted\"\n";
+/// let builder = DeserializerBuilder::new().preserve_scalars(true);
+/// let value = Value::deserialize(builder.from_str(yaml)).unwrap();
+///
+/// assert_eq!(value["mask"], 31);
+/// assert_eq!(serde_yml::to_string(&value).unwrap(), yaml);
+/// ```
+#[derive(Clone, Debug)]
+pub struct StyledValue {
+    /// The value of the scalar.
+    pub value: Value,
+    style: ScalarStyle,
+    text: String,
+}
+
+impl StyledValue {
+    /// Creates a `StyledValue` for a scalar written in `style` as `text`,
+    /// without quotes or escapes, resolving its value the way the
+    /// deserializer would.
+    pub fn new(style: ScalarStyle, text: impl Into<String>) -> Self {
+        let text = text.into();
+        let value = resolve(style, &text);
+        StyledValue { value, style, text }
+    }
+
+    /// The style the scalar was written in.
+    pub fn style(&self) -> ScalarStyle {
+        self.style
+    }
+
+    /// The text of the scalar, without the quotes or escapes of its
+    /// style.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// The scalar as the emitter should write it, if the value still
+    /// matches the text.
+    pub(crate) fn presentation(
+        &self,
+    ) -> Option<(&str, emitter::ScalarStyle)> {
+        if self.value != resolve(self.style, &self.text) {
+            return None;
+        }
+        let style = match self.style {
+            ScalarStyle::Plain => emitter::ScalarStyle::Plain,
+            ScalarStyle::SingleQuoted => {
+                emitter::ScalarStyle::SingleQuoted
+            }
+            ScalarStyle::DoubleQuoted => {
+                emitter::ScalarStyle::DoubleQuoted
+            }
+            ScalarStyle::Literal => emitter::ScalarStyle::Literal,
+            ScalarStyle::Folded => emitter::ScalarStyle::Folded,
+        };
+        Some((&self.text, style))
+    }
+}
+
+/// Resolves a scalar without a tag: only plain scalars can be anything
+/// other than strings.
+fn resolve(style: ScalarStyle, text: &str) -> Value {
+    struct PlainScalar<'a>(&'a str);
+
+    impl<'de> Deserializer<'de> for PlainScalar<'_> {
+        type Error = Error;
+
+        fn deserialize_any<V>(
+            self,
+            visitor: V,
+        ) -> Result<V::Value, Error>
+        where
+            V: Visitor<'de>,
+        {
+            crate::de::visit_untagged_scalar(
+                visitor,
+                self.0,
+                None,
+                ScalarStyle::Plain,
+                crate::de::Resolution::Core,
+            )
+        }
+
+        forward_to_deserialize_any! {
+            bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char
+            str string bytes byte_buf option unit unit_struct
+            newtype_struct seq tuple tuple_struct map struct enum
+            identifier ignored_any
+        }
+    }
+
+    match style {
+        ScalarStyle::Plain => Value::deserialize(PlainScalar(text))
+            .unwrap_or_else(|_| Value::String(text.to_owned())),
+        _ => Value::String(text.to_owned()),
+    }
+}
+
+fn style_name(style: ScalarStyle) -> &'static str {
+    match style {
+        ScalarStyle::Plain => "plain",
+        ScalarStyle::SingleQuoted => "single_quoted",
+        ScalarStyle::DoubleQuoted => "double_quoted",
+        ScalarStyle::Literal => "literal",
+        ScalarStyle::Folded => "folded",
+    }
+}
+
+/// The style and the text of a scalar, in the form in which the
+/// deserializer hands them to [`Value`] as the content of a newtype
+/// struct.
+pub(crate) fn contents(style: ScalarStyle, text: &str) -> [&str; 2] {
+    [style_name(style), text]
+}
+
+/// Deserializes the [`contents`] of a styled scalar.
+pub(crate) fn deserialize<'de, D>(
+    deserializer: D,
+) -> Result<StyledValue, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    struct StyledVisitor;
+
+    impl<'de> Visitor<'de> for StyledVisitor {
+        type Value = StyledValue;
+
+        fn expecting(
+            &self,
+            formatter: &mut fmt::Formatter<'_>,
+        ) -> fmt::Result {
+            formatter.write_str("the style and text of a YAML scalar")
+        }
+
+        fn visit_seq<A>(
+            self,
+            mut seq: A,
+        ) -> Result<StyledValue, A::Error>
+        where
+            A: SeqAccess<'de>,
+        {
+            let style: String = match seq.next_element()? {
+                Some(style) => style,
+                None => return Err(A::Error::invalid_length(0, &self)),
+            };
+            let style = [
+                ScalarStyle::Plain,
+                ScalarStyle::SingleQuoted,
+                ScalarStyle::DoubleQuoted,
+                ScalarStyle::Literal,
+                ScalarStyle::Folded,
+            ]
+            .iter()
+            .copied()
+            .find(|candidate| style_name(*candidate) == style)
+            .ok_or_else(|| {
+                A::Error::invalid_value(
+                    Unexpected::Str(&style),
+                    &"a scalar style",
+                )
+            })?;
+            let text: String = match seq.next_element()? {
+                Some(text) => text,
+                None => return Err(A::Error::invalid_length(1, &self)),
+            };
+            Ok(StyledValue::new(style, text))
+        }
+    }
+
+    deserializer.deserialize_tuple(2, StyledVisitor)
+}
+
+thread_local! {
+    /// The styled scalar whose value is being serialized, which lets this
+    /// crate's serializer write the scalar with its original text while
+    /// any other serializer only sees the value.
+    static SERIALIZING: Cell<*const StyledValue> =
+        const { Cell::new(ptr::null()) };
+}
+
+/// Calls `f` with the styled scalar being serialized, if any.
+pub(crate) fn with_serializing<R>(
+    f: impl FnOnce(&StyledValue) -> Option<R>,
+) -> Option<R> {
+    let current = SERIALIZING.with(Cell::get);
+    // SAFETY: the pointer is only set while `StyledValue::serialize`
+    // borrows the scalar it points to.
+    unsafe { current.as_ref() }.and_then(f)
+}
+
+impl Serialize for StyledValue {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        struct Restore(*const StyledValue);
+
+        impl Drop for Restore {
+            fn drop(&mut self) {
+                SERIALIZING.with(|current| current.set(self.0));
+            }
+        }
+
+        let _restore =
+            Restore(SERIALIZING.with(|current| current.replace(self)));
+        serializer.serialize_newtype_struct(STYLED, &self.value)
+    }
+}
+
+impl Value {
+    /// Returns the value without the presentation of a styled scalar.
+    pub(crate) fn unstyle(self) -> Self {
+        match self {
+            Value::Styled(styled) => styled.value,
+            other => other,
+        }
+    }
+
+    /// Returns the value without the presentation of a styled scalar.
+    pub(crate) fn unstyle_ref(&self) -> &Self {
+        match self {
+            Value::Styled(styled) => &styled.value,
+            other => other,
+        }
+    }
+}
+
+impl From<StyledValue> for Value {
+    fn from(styled: StyledValue) -> Self {
+        Value::Styled(Box::new(styled))
+    }
+}
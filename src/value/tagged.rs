@@ -73,26 +73,35 @@ impl TryFrom<&[u8]> for Tag {
 impl Value {
     pub(crate) fn untag(self) -> Self {
         let mut cur = self;
-        while let Value::Tagged(tagged) = cur {
-            cur = tagged.value;
+        loop {
+            match cur {
+                Value::Tagged(tagged) => cur = tagged.value,
+                Value::Styled(styled) => cur = styled.value,
+                _ => return cur,
+            }
         }
-        cur
     }
 
     pub(crate) fn untag_ref(&self) -> &Self {
         let mut cur = self;
-        while let Value::Tagged(tagged) = cur {
-            cur = &tagged.value;
+        loop {
+            match cur {
+                Value::Tagged(tagged) => cur = &tagged.value,
+                Value::Styled(styled) => cur = &styled.value,
+                _ => return cur,
+            }
         }
-        cur
     }
 
     pub(crate) fn untag_mut(&mut self) -> &mut Self {
         let mut cur = self;
-        while let Value::Tagged(tagged) = cur {
-            cur = &mut tagged.value;
+        loop {
+            match cur {
+                Value::Tagged(tagged) => cur = &mut tagged.value,
+                Value::Styled(styled) => cur = &mut styled.value,
+                _ => return cur,
+            }
         }
-        cur
     }
 }
 
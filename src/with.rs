@@ -75,6 +75,7 @@
 ///   value: 1
 /// ```
 pub mod singleton_map {
+    use crate::ser::serialize_variant_content;
     use crate::value::{Mapping, Sequence, Value};
     use serde::de::{
         self, Deserialize, DeserializeSeed, Deserializer, EnumAccess,
@@ -327,7 +328,9 @@ pub mod singleton_map {
             T: ?Sized + Serialize,
         {
             let mut map = self.delegate.serialize_map(Some(1))?;
-            map.serialize_entry(variant, value)?;
+            serialize_variant_content(|| {
+                map.serialize_entry(variant, value)
+            })?;
             map.end()
         }
 
@@ -463,7 +466,9 @@ pub mod singleton_map {
         }
 
         fn end(mut self) -> Result<Self::Ok, Self::Error> {
-            self.map.serialize_value(&self.sequence)?;
+            serialize_variant_content(|| {
+                self.map.serialize_value(&self.sequence)
+            })?;
             self.map.end()
         }
     }
@@ -509,7 +514,9 @@ pub mod singleton_map {
         }
 
         fn end(mut self) -> Result<Self::Ok, Self::Error> {
-            self.map.serialize_value(&self.mapping)?;
+            serialize_variant_content(|| {
+                self.map.serialize_value(&self.mapping)
+            })?;
             self.map.end()
         }
     }
@@ -1362,9 +1369,9 @@ pub mod singleton_map_with {
 ///         bs: vec![Enum::Int(1)],
 ///     };
 ///
-///     let mut buf = Vec::new();
-///     let mut serializer = serde_yml::Serializer::new(&mut buf);
+///     let mut serializer = serde_yml::Serializer::new(Vec::new());
 ///     serde_yml::with::singleton_map_recursive::serialize(&object, &mut serializer).unwrap();
+///     let buf = serializer.into_inner().unwrap();
 ///     io::stdout().write_all(&buf).unwrap();
 ///
 ///     let deserializer = serde_yml::Deserializer::from_slice(&buf);
@@ -1373,6 +1380,7 @@ pub mod singleton_map_with {
 /// }
 /// ```
 pub mod singleton_map_recursive {
+    use crate::ser::serialize_variant_content;
     use crate::value::{Mapping, Sequence, Value};
     use serde::de::{
         self, Deserialize, DeserializeSeed, Deserializer, EnumAccess,
@@ -1609,10 +1617,12 @@ pub mod singleton_map_recursive {
             T: ?Sized + Serialize,
         {
             let mut map = self.delegate.serialize_map(Some(1))?;
-            map.serialize_entry(
-                variant,
-                &SingletonMapRecursive { delegate: value },
-            )?;
+            serialize_variant_content(|| {
+                map.serialize_entry(
+                    variant,
+                    &SingletonMapRecursive { delegate: value },
+                )
+            })?;
             map.end()
         }
 
@@ -1830,7 +1840,9 @@ pub mod singleton_map_recursive {
         }
 
         fn end(mut self) -> Result<Self::Ok, Self::Error> {
-            self.map.serialize_value(&self.sequence)?;
+            serialize_variant_content(|| {
+                self.map.serialize_value(&self.sequence)
+            })?;
             self.map.end()
         }
     }
@@ -1942,7 +1954,9 @@ pub mod singleton_map_recursive {
         }
 
         fn end(mut self) -> Result<Self::Ok, Self::Error> {
-            self.map.serialize_value(&self.mapping)?;
+            serialize_variant_content(|| {
+                self.map.serialize_value(&self.mapping)
+            })?;
             self.map.end()
         }
     }
@@ -2868,3 +2882,474 @@ pub mod singleton_map_recursive {
         }
     }
 }
+
+/// Serialize a sequence or mapping in YAML flow style, such as `[80, 443]`
+/// or `{x: 1, y: 2}`, regardless of the serializer's
+/// [`CollectionStyle`](crate::CollectionStyle).
+///
+/// Deserialization is unaffected: both styles are read the same way.
+/// Other serializers ignore the requested style.
+///
+/// # Example
+///
+/// ```
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Serialize, Deserialize, PartialEq, Debug)]
+/// struct Service {
+///     name: String,
+///     #[serde(with = "serde_yml::with::flow")]
+///     ports: Vec<u16>,
+/// }
+///
+/// let service = Service {
+///     name: "web".to_owned(),
+///     ports: vec![80, 443],
+/// };
+///
+/// let yaml = serde_yml::to_string(&service).unwrap();
+/// assert_eq!(yaml, "name: web\nports: [80, 443]\n");
+///
+/// let deserialized: Service = serde_yml::from_str(&yaml).unwrap();
+/// assert_eq!(service, deserialized);
+/// ```
+pub mod flow {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    /// Serializes `value`, asking for flow style if it is a sequence or
+    /// mapping.
+    pub fn serialize<T, S>(
+        value: &T,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error>
+    where
+        T: ?Sized + Serialize,
+        S: Serializer,
+    {
+        serializer.serialize_newtype_struct(crate::ser::FLOW, value)
+    }
+
+    /// Deserializes the value as usual.
+    pub fn deserialize<'de, T, D>(
+        deserializer: D,
+    ) -> Result<T, D::Error>
+    where
+        T: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        T::deserialize(deserializer)
+    }
+}
+
+/// Serialize a sequence or mapping in YAML block style, with one entry
+/// per line, regardless of the serializer's
+/// [`CollectionStyle`](crate::CollectionStyle).
+///
+/// A collection nested inside a flow collection is always written in flow
+/// style. Empty collections are written as `[]` and `{}`.
+///
+/// # Example
+///
+/// ```
+/// use serde::Serialize;
+/// use serde_yml::{CollectionStyle, SerializerBuilder};
+///
+/// #[derive(Serialize)]
+/// struct Job {
+///     #[serde(with = "serde_yml::with::block")]
+///     script: Vec<&'static str>,
+///     tags: Vec<&'static str>,
+/// }
+///
+/// let job = Job {
+///     script: vec!["make", "make test"],
+///     tags: vec!["linux"],
+/// };
+///
+/// let builder =
+///     SerializerBuilder::new().collection_style(CollectionStyle::FlowLeaves);
+/// let yaml = serde_yml::to_string_with(&job, &builder).unwrap();
+/// assert_eq!(yaml, "script:\n- make\n- make test\ntags: [linux]\n");
+/// ```
+pub mod block {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    /// Serializes `value`, asking for block style if it is a sequence or
+    /// mapping.
+    pub fn serialize<T, S>(
+        value: &T,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error>
+    where
+        T: ?Sized + Serialize,
+        S: Serializer,
+    {
+        serializer.serialize_newtype_struct(crate::ser::BLOCK, value)
+    }
+
+    /// Deserializes the value as usual.
+    pub fn deserialize<'de, T, D>(
+        deserializer: D,
+    ) -> Result<T, D::Error>
+    where
+        T: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        T::deserialize(deserializer)
+    }
+}
+
+/// Serialize a string as a plain, unquoted YAML scalar where that is
+/// safe.
+///
+/// Strings that would be read back as something other than a string,
+/// such as `"yes"` or `"1.0"`, are still single-quoted, and strings that
+/// plain style cannot represent, such as ones with line breaks, keep
+/// their usual style. Values that are not strings are unaffected.
+///
+/// # Example
+///
+/// ```
+/// use serde::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct Answer {
+///     #[serde(with = "serde_yml::with::plain")]
+///     text: &'static str,
+///     #[serde(with = "serde_yml::with::plain")]
+///     flag: &'static str,
+/// }
+///
+/// let answer = Answer { text: "hello", flag: "yes" };
+/// let yaml = serde_yml::to_string(&answer).unwrap();
+/// assert_eq!(yaml, "text: hello\nflag: 'yes'\n");
+/// ```
+pub mod plain {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    /// Serializes `value`, asking for plain style if it is a string.
+    pub fn serialize<T, S>(
+        value: &T,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error>
+    where
+        T: ?Sized + Serialize,
+        S: Serializer,
+    {
+        serializer.serialize_newtype_struct(crate::ser::PLAIN, value)
+    }
+
+    /// Deserializes the value as usual.
+    pub fn deserialize<'de, T, D>(
+        deserializer: D,
+    ) -> Result<T, D::Error>
+    where
+        T: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        T::deserialize(deserializer)
+    }
+}
+
+/// Serialize a string as a single-quoted YAML scalar, such as `'text'`.
+///
+/// Strings with characters that single quotes cannot represent, such as
+/// control characters, fall back to double quotes. Values that are not
+/// strings are unaffected.
+///
+/// # Example
+///
+/// ```
+/// use serde::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct Greeting {
+///     #[serde(with = "serde_yml::with::single_quoted")]
+///     text: &'static str,
+/// }
+///
+/// let yaml = serde_yml::to_string(&Greeting { text: "hello" }).unwrap();
+/// assert_eq!(yaml, "text: 'hello'\n");
+/// ```
+pub mod single_quoted {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    /// Serializes `value`, asking for single-quoted style if it is a
+    /// string.
+    pub fn serialize<T, S>(
+        value: &T,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error>
+    where
+        T: ?Sized + Serialize,
+        S: Serializer,
+    {
+        serializer
+            .serialize_newtype_struct(crate::ser::SINGLE_QUOTED, value)
+    }
+
+    /// Deserializes the value as usual.
+    pub fn deserialize<'de, T, D>(
+        deserializer: D,
+    ) -> Result<T, D::Error>
+    where
+        T: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        T::deserialize(deserializer)
+    }
+}
+
+/// Serialize a string as a double-quoted YAML scalar, such as `"text"`.
+///
+/// Double quotes can represent any string, using escapes such as `\t`
+/// where needed. Values that are not strings are unaffected.
+///
+/// # Example
+///
+/// ```
+/// use serde::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct Greeting {
+///     #[serde(with = "serde_yml::with::double_quoted")]
+///     text: &'static str,
+/// }
+///
+/// let yaml = serde_yml::to_string(&Greeting { text: "hello" }).unwrap();
+/// assert_eq!(yaml, "text: \"hello\"\n");
+/// ```
+pub mod double_quoted {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    /// Serializes `value`, asking for double-quoted style if it is a
+    /// string.
+    pub fn serialize<T, S>(
+        value: &T,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error>
+    where
+        T: ?Sized + Serialize,
+        S: Serializer,
+    {
+        serializer
+            .serialize_newtype_struct(crate::ser::DOUBLE_QUOTED, value)
+    }
+
+    /// Deserializes the value as usual.
+    pub fn deserialize<'de, T, D>(
+        deserializer: D,
+    ) -> Result<T, D::Error>
+    where
+        T: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        T::deserialize(deserializer)
+    }
+}
+
+/// Serialize a string as a literal block scalar, introduced by `|`, which
+/// keeps every line break as written.
+///
+/// Block scalars cannot be used inside flow collections, in mapping keys,
+/// or for strings with trailing spaces or control characters; those fall
+/// back to double quotes. Values that are not strings are unaffected.
+///
+/// # Example
+///
+/// ```
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Serialize, Deserialize, PartialEq, Debug)]
+/// struct Step {
+///     #[serde(with = "serde_yml::with::literal")]
+///     run: String,
+/// }
+///
+/// let step = Step {
+///     run: "make".to_owned(),
+/// };
+///
+/// let yaml = serde_yml::to_string(&step).unwrap();
+/// assert_eq!(yaml, "run: |-\n  make\n");
+///
+/// let deserialized: Step = serde_yml::from_str(&yaml).unwrap();
+/// assert_eq!(step, deserialized);
+/// ```
+pub mod literal {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    /// Serializes `value`, asking for literal block style if it is a
+    /// string.
+    pub fn serialize<T, S>(
+        value: &T,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error>
+    where
+        T: ?Sized + Serialize,
+        S: Serializer,
+    {
+        serializer.serialize_newtype_struct(crate::ser::LITERAL, value)
+    }
+
+    /// Deserializes the value as usual.
+    pub fn deserialize<'de, T, D>(
+        deserializer: D,
+    ) -> Result<T, D::Error>
+    where
+        T: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        T::deserialize(deserializer)
+    }
+}
+
+/// Serialize a string as a folded block scalar, introduced by `>`. Single
+/// line breaks in a folded scalar are read back as spaces, so long
+/// paragraphs can be wrapped at the serializer's line width.
+///
+/// Block scalars cannot be used inside flow collections, in mapping keys,
+/// or for strings with trailing spaces or control characters; those fall
+/// back to double quotes. Values that are not strings are unaffected.
+///
+/// # Example
+///
+/// ```
+/// use serde::{Deserialize, Serialize};
+/// use serde_yml::SerializerBuilder;
+///
+/// #[derive(Serialize, Deserialize, PartialEq, Debug)]
+/// struct Package {
+///     #[serde(with = "serde_yml::with::folded")]
+///     description: String,
+/// }
+///
+/// let package = Package {
+///     description: "A long description that is wrapped".to_owned(),
+/// };
+///
+/// let builder = SerializerBuilder::new().width(Some(20));
+/// let yaml = serde_yml::to_string_with(&package, &builder).unwrap();
+/// assert_eq!(
+///     yaml,
+///     "description: >-\n  A long description that\n  is wrapped\n",
+/// );
+///
+/// let deserialized: Package = serde_yml::from_str(&yaml).unwrap();
+/// assert_eq!(package, deserialized);
+/// ```
+pub mod folded {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    /// Serializes `value`, asking for folded block style if it is a
+    /// string.
+    pub fn serialize<T, S>(
+        value: &T,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error>
+    where
+        T: ?Sized + Serialize,
+        S: Serializer,
+    {
+        serializer.serialize_newtype_struct(crate::ser::FOLDED, value)
+    }
+
+    /// Deserializes the value as usual.
+    pub fn deserialize<'de, T, D>(
+        deserializer: D,
+    ) -> Result<T, D::Error>
+    where
+        T: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        T::deserialize(deserializer)
+    }
+}
+
+/// Serialize an `Rc` or `Arc` so that every occurrence of the same
+/// allocation after the first is written as an alias, when the serializer
+/// is built with [`AnchorMode::Shared`](crate::AnchorMode::Shared) or
+/// [`AnchorMode::Repeated`](crate::AnchorMode::Repeated).
+///
+/// Pointers are compared within one document, and a node is only written
+/// as an alias if it is also written the same as the anchored one, since
+/// the allocation of a pointer dropped during serialization may be reused
+/// by another. With anchors off, and with other serializers, the value is
+/// serialized as usual.
+///
+/// Deserializing gives every field its own allocation, whether or not the
+/// YAML uses an alias.
+///
+/// # Example
+///
+/// ```
+/// use serde::{Deserialize, Serialize};
+/// use serde_yml::{AnchorMode, SerializerBuilder};
+/// use std::sync::Arc;
+///
+/// #[derive(Serialize, Deserialize, PartialEq, Debug)]
+/// struct Job {
+///     #[serde(with = "serde_yml::with::shared")]
+///     env: Arc<Vec<String>>,
+/// }
+///
+/// let env = Arc::new(vec!["CI=1".to_owned()]);
+/// let jobs = vec![
+///     Job { env: Arc::clone(&env) },
+///     Job { env: Arc::clone(&env) },
+/// ];
+///
+/// let builder = SerializerBuilder::new().anchors(AnchorMode::Shared);
+/// let yaml = serde_yml::to_string_with(&jobs, &builder).unwrap();
+/// assert_eq!(
+///     yaml,
+///     "- env: &id001\n  - CI=1\n- env: *id001\n",
+/// );
+///
+/// let deserialized: Vec<Job> = serde_yml::from_str(&yaml).unwrap();
+/// assert_eq!(jobs, deserialized);
+/// ```
+pub mod shared {
+    use crate::private;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use std::ops::Deref;
+    use std::rc::Rc;
+    use std::sync::Arc;
+
+    /// A reference-counted pointer, [`Rc`] or [`Arc`], whose address
+    /// identifies the allocation it shares.
+    ///
+    /// This trait is sealed and cannot be implemented outside this crate.
+    pub trait SharedPointer: Deref + private::Sealed {}
+
+    impl<T> SharedPointer for Rc<T> where T: ?Sized {}
+
+    impl<T> SharedPointer for Arc<T> where T: ?Sized {}
+
+    /// Serializes the value behind `pointer`, passing its address on to
+    /// the serializer.
+    pub fn serialize<P, S>(
+        pointer: &P,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error>
+    where
+        P: SharedPointer,
+        P::Target: Serialize,
+        S: Serializer,
+    {
+        serializer
+            .serialize_newtype_struct(crate::ser::SHARED, &**pointer)
+    }
+
+    /// Deserializes the value into a new allocation.
+    pub fn deserialize<'de, P, D>(
+        deserializer: D,
+    ) -> Result<P, D::Error>
+    where
+        P: SharedPointer + From<P::Target>,
+        P::Target: Deserialize<'de> + Sized,
+        D: Deserializer<'de>,
+    {
+        P::Target::deserialize(deserializer).map(P::from)
+    }
+}
@@ -14,10 +14,16 @@
 use indoc::indoc;
 use serde_derive::Deserialize;
 use serde_yml::Value::String as SerdeString;
-use serde_yml::{Deserializer, Number, Value};
-use std::collections::BTreeMap;
+use serde_yml::{
+    Deserializer, DeserializerBuilder, DuplicateKeys, Number, Schema,
+    Value, YamlVersion,
+};
+use std::cell::Cell;
+use std::collections::{BTreeMap, HashMap};
 use std::fmt::Debug;
 use std::fmt::Formatter;
+use std::fmt::Write as _;
+use std::io;
 use std::string::String;
 
 fn test_de<T>(yaml: &str, expected: &T)
@@ -36,6 +42,13 @@ where
 
     serde_yml::from_str::<serde::de::IgnoredAny>(yaml).unwrap();
 
+    let deserialized: T =
+        serde_yml::from_reader(yaml.as_bytes()).unwrap();
+    assert_eq!(*expected, deserialized);
+
+    serde_yml::from_reader::<_, serde::de::IgnoredAny>(yaml.as_bytes())
+        .unwrap();
+
     let mut deserializer = Deserializer::from_str(yaml);
     let document = deserializer.next().unwrap();
     let deserialized = T::deserialize(document).unwrap();
@@ -801,3 +814,590 @@ fn test_enum_untagged() {
         assert_eq!(expected, deserialized);
     }
 }
+
+fn test_de_with<T>(
+    yaml: &str,
+    builder: &DeserializerBuilder,
+    expected: &T,
+) where
+    T: serde::de::DeserializeOwned + PartialEq + Debug,
+{
+    let deserialized: T =
+        serde_yml::from_str_with(yaml, builder).unwrap();
+    assert_eq!(*expected, deserialized);
+
+    let deserialized: T =
+        serde_yml::from_reader_with(yaml.as_bytes(), builder).unwrap();
+    assert_eq!(*expected, deserialized);
+}
+
+#[test]
+fn test_duplicate_keys_first_wins() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Server {
+        host: String,
+        port: u16,
+    }
+
+    let yaml = indoc! {"
+        port: 80
+        host: a
+        port: 8080
+        ~: x
+        null: y
+    "};
+    let builder = DeserializerBuilder::new()
+        .duplicate_keys(DuplicateKeys::FirstWins);
+
+    let expected = Server {
+        host: "a".to_owned(),
+        port: 80,
+    };
+    test_de_with(yaml, &builder, &expected);
+
+    let mut expected = HashMap::new();
+    expected.insert(Some("port".to_owned()), "80".to_owned());
+    expected.insert(Some("host".to_owned()), "a".to_owned());
+    expected.insert(None, "x".to_owned());
+    test_de_with(yaml, &builder, &expected);
+
+    let mut expected = serde_yml::Mapping::new();
+    expected.insert("port".into(), 80.into());
+    expected.insert("host".into(), "a".into());
+    expected.insert(Value::Null, "x".into());
+    test_de_with(yaml, &builder, &expected);
+    test_de_with(yaml, &builder, &Value::Mapping(expected));
+}
+
+#[test]
+fn test_duplicate_keys_last_wins() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Server {
+        host: String,
+        port: u16,
+    }
+
+    let yaml = indoc! {"
+        port: 80
+        host: a
+        port: 8080
+    "};
+    let builder = DeserializerBuilder::new()
+        .duplicate_keys(DuplicateKeys::LastWins);
+
+    let expected = Server {
+        host: "a".to_owned(),
+        port: 8080,
+    };
+    test_de_with(yaml, &builder, &expected);
+
+    let mut expected = BTreeMap::new();
+    expected.insert("port".to_owned(), 8080);
+    test_de_with("{port: 80, port: 8080}", &builder, &expected);
+
+    // The last value keeps the position of the first key.
+    let value: Value =
+        serde_yml::from_str_with(yaml, &builder).unwrap();
+    let keys: Vec<_> = value.as_mapping().unwrap().keys().collect();
+    assert_eq!(keys, ["port", "host"]);
+    assert_eq!(value["port"], 8080);
+}
+
+#[test]
+fn test_duplicate_keys_collect_all() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Server {
+        host: Vec<String>,
+        port: Vec<u16>,
+        alias: Option<Vec<String>>,
+    }
+
+    let yaml = indoc! {"
+        port: 80
+        host: a
+        port: 8080
+    "};
+    let builder = DeserializerBuilder::new()
+        .duplicate_keys(DuplicateKeys::CollectAll);
+
+    let expected = Server {
+        host: vec!["a".to_owned()],
+        port: vec![80, 8080],
+        alias: None,
+    };
+    test_de_with(yaml, &builder, &expected);
+
+    let mut expected = BTreeMap::new();
+    expected.insert("host".to_owned(), vec![Value::from("a")]);
+    expected.insert("port".to_owned(), vec![80.into(), 8080.into()]);
+    test_de_with(yaml, &builder, &expected);
+
+    let yaml = indoc! {"
+        a:
+          b: 1
+          b: 2
+        a: 3
+    "};
+    let expected: Value =
+        serde_yml::from_str("a: [{b: [1, 2]}, 3]").unwrap();
+    test_de_with(yaml, &builder, &expected);
+}
+
+#[test]
+fn test_merge_keys() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Point {
+        x: i32,
+        y: i32,
+        r: i32,
+        label: String,
+    }
+
+    // From https://yaml.org/type/merge.html.
+    let yaml = indoc! {"
+        - &CENTER { x: 1, 'y': 2 }
+        - &LEFT { x: 0, 'y': 2 }
+        - &BIG { r: 10 }
+        - &SMALL { r: 1 }
+        - x: 1
+          'y': 2
+          r: 10
+          label: center/big
+        - << : *CENTER
+          r: 10
+          label: center/big
+        - << : [ *CENTER, *BIG ]
+          label: center/big
+        - << : [ *BIG, *LEFT, *SMALL ]
+          x: 1
+          label: center/big
+    "};
+    let expected = Point {
+        x: 1,
+        y: 2,
+        r: 10,
+        label: "center/big".to_owned(),
+    };
+    let points: (
+        Value,
+        Value,
+        Value,
+        Value,
+        Point,
+        Point,
+        Point,
+        Point,
+    ) = serde_yml::from_str(yaml).unwrap();
+    assert_eq!(points.4, expected);
+    assert_eq!(points.5, expected);
+    assert_eq!(points.6, expected);
+    assert_eq!(points.7, expected);
+
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Drawing {
+        point: Point,
+    }
+
+    // The merge keys of a merged mapping are merged as well.
+    let yaml = indoc! {"
+        center: &CENTER { x: 1, 'y': 2 }
+        big: &BIG { <<: *CENTER, r: 10 }
+        point: { <<: *BIG, label: center/big }
+    "};
+    let drawing: Drawing = serde_yml::from_str(yaml).unwrap();
+    assert_eq!(drawing.point, expected);
+
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Job {
+        image: String,
+        script: Vec<String>,
+        tags: Option<Vec<String>>,
+        variables: BTreeMap<String, String>,
+    }
+
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Pipeline {
+        build: Job,
+        test: Job,
+    }
+
+    let yaml = indoc! {"
+        .defaults: &defaults
+          image: rust
+          tags: [docker]
+          variables: &variables
+            CARGO_HOME: .cargo
+        build:
+          <<: [*defaults, {script: [make]}]
+          script: [cargo build]
+          variables:
+            <<: *variables
+            PROFILE: release
+        test:
+          <<: *defaults
+          script: [cargo test]
+    "};
+    let mut value: Value = serde_yml::from_str(yaml).unwrap();
+    value.apply_merge().unwrap();
+    let merged: Pipeline = serde_yml::from_value(value).unwrap();
+    let pipeline: Pipeline = serde_yml::from_str(yaml).unwrap();
+    assert_eq!(pipeline, merged);
+    assert_eq!(
+        pipeline.build,
+        Job {
+            image: "rust".to_owned(),
+            script: vec!["cargo build".to_owned()],
+            tags: Some(vec!["docker".to_owned()]),
+            variables: BTreeMap::from([
+                ("CARGO_HOME".to_owned(), ".cargo".to_owned()),
+                ("PROFILE".to_owned(), "release".to_owned()),
+            ]),
+        },
+    );
+
+    // A `Value` keeps the `<<` keys until they are merged.
+    let value: Value = serde_yml::from_str(yaml).unwrap();
+    assert_eq!(value["test"]["<<"]["image"], "rust");
+}
+
+#[test]
+fn test_quoted_merge_key() {
+    let map = BTreeMap::from([
+        ("<<".to_owned(), "x".to_owned()),
+        ("a".to_owned(), "b".to_owned()),
+    ]);
+    let yaml = serde_yml::to_string(&map).unwrap();
+    assert_eq!(yaml, "'<<': x\na: b\n");
+    test_de(&yaml, &map);
+}
+
+#[test]
+fn test_yaml11_resolution() {
+    let yaml = indoc! {"
+        - [yes, Yes, YES, on, On, ON, true]
+        - [no, No, NO, off, Off, OFF, false]
+        - [y, n, yEs, 0o17, 1e3, 0:30, 1:60]
+        - [0777, -0777, 0b1010, 0x_FF, 1_000, +12, 0]
+        - [1:30, -1:30, 190:20:30]
+        - [1_000.5, 1:30.5, 1.0e+3, .5, -.inf, 2.]
+    "};
+    let expected: Value = serde_yml::from_str(indoc! {"
+        - [true, true, true, true, true, true, true]
+        - [false, false, false, false, false, false, false]
+        - [y, n, yEs, '0o17', '1e3', '0:30', '1:60']
+        - [511, -511, 10, 255, 1000, 12, 0]
+        - [90, -90, 685230]
+        - [1000.5, 90.5, 1000.0, 0.5, -.inf, 2.0]
+    "})
+    .unwrap();
+    let builder =
+        DeserializerBuilder::new().yaml_version(YamlVersion::V1_1);
+    test_de_with(yaml, &builder, &expected);
+
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Service {
+        enabled: bool,
+        mode: u32,
+        offset: i64,
+        ratio: f64,
+        name: String,
+    }
+
+    let yaml = indoc! {"
+        enabled: on
+        mode: 0644
+        offset: -1_000
+        ratio: 0:01.5
+        name: no
+    "};
+    let expected = Service {
+        enabled: true,
+        mode: 0o644,
+        offset: -1000,
+        ratio: 1.5,
+        name: "no".to_owned(),
+    };
+    test_de_with(yaml, &builder, &expected);
+}
+
+#[test]
+fn test_schemas() {
+    let yaml = indoc! {"
+        - no
+        - True
+        - true
+        - ~
+        - null
+        -
+        - 047
+        - 0x1F
+        - 12
+        - -3
+        - 1.5
+        - 1e3
+        - .inf
+    "};
+    let cases = [
+        (
+            Schema::Failsafe,
+            "['no', 'True', 'true', '~', 'null', '', '047', '0x1F', \
+             '12', '-3', '1.5', '1e3', '.inf']",
+        ),
+        (
+            Schema::Json,
+            "['no', 'True', true, '~', null, '', '047', '0x1F', \
+             12, -3, 1.5, 1000.0, '.inf']",
+        ),
+        (
+            Schema::Core,
+            "['no', true, true, null, null, null, '047', 31, \
+             12, -3, 1.5, 1000.0, .inf]",
+        ),
+    ];
+    for (schema, expected) in cases {
+        let builder = DeserializerBuilder::new().schema(schema);
+        let expected: Value = serde_yml::from_str(expected).unwrap();
+        test_de_with(yaml, &builder, &expected);
+    }
+
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Entry {
+        enabled: bool,
+        count: u8,
+        ratio: f64,
+        name: String,
+        note: Option<String>,
+    }
+
+    let yaml = indoc! {"
+        enabled: true
+        count: 3
+        ratio: 0.5
+        name: no
+        note: null
+    "};
+    let expected = Entry {
+        enabled: true,
+        count: 3,
+        ratio: 0.5,
+        name: "no".to_owned(),
+        note: None,
+    };
+    for schema in [Schema::Failsafe, Schema::Json, Schema::Core] {
+        let builder = DeserializerBuilder::new().schema(schema);
+        test_de_with(yaml, &builder, &expected);
+    }
+}
+
+#[test]
+fn test_yaml11_directive() {
+    let yaml11: Value = serde_yml::from_str("[true, 511, 90]").unwrap();
+    let yaml12: Value =
+        serde_yml::from_str("[yes, '0777', '1:30']").unwrap();
+
+    // A document without a directive follows the builder, whatever the
+    // version of the documents before it.
+    let yaml = indoc! {"
+        %YAML 1.1
+        ---
+        [yes, 0777, 1:30]
+        ---
+        [yes, 0777, 1:30]
+    "};
+    let values: Vec<Value> = Deserializer::from_str(yaml)
+        .map(|document| {
+            serde::Deserialize::deserialize(document).unwrap()
+        })
+        .collect();
+    assert_eq!(values, [yaml11.clone(), yaml12.clone()]);
+
+    let builder =
+        DeserializerBuilder::new().yaml_version(YamlVersion::V1_1);
+    let values: Vec<Value> = builder
+        .from_str(yaml)
+        .map(|document| {
+            serde::Deserialize::deserialize(document).unwrap()
+        })
+        .collect();
+    assert_eq!(values, [yaml11.clone(), yaml11]);
+
+    let yaml = indoc! {"
+        %YAML 1.2
+        ---
+        [yes, 0777, 1:30]
+    "};
+    let value: Value =
+        serde_yml::from_str_with(yaml, &builder).unwrap();
+    assert_eq!(value, yaml12);
+    let value: Value = serde::Deserialize::deserialize(
+        builder.from_reader(yaml.as_bytes()),
+    )
+    .unwrap();
+    assert_eq!(value, yaml12);
+}
+
+#[test]
+fn test_yaml11_default_value() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Link {
+        library: String,
+        version: Option<f64>,
+    }
+
+    let yaml = indoc! {"
+        - library: library1.dll
+        - library: {=: library2.dll, version: 2.3}
+          version: {version: 2.3, =: 2.4}
+    "};
+    let expected = vec![
+        Link {
+            library: "library1.dll".to_owned(),
+            version: None,
+        },
+        Link {
+            library: "library2.dll".to_owned(),
+            version: Some(2.4),
+        },
+    ];
+    let builder =
+        DeserializerBuilder::new().yaml_version(YamlVersion::V1_1);
+    let deserialized: Vec<Link> =
+        serde::Deserialize::deserialize(builder.from_str(yaml))
+            .unwrap();
+    assert_eq!(expected, deserialized);
+
+    // A `Value` keeps the mapping.
+    let value: Value =
+        serde::Deserialize::deserialize(builder.from_str(yaml))
+            .unwrap();
+    assert_eq!(value[1]["library"]["="], "library2.dll");
+}
+
+#[test]
+fn test_from_reader_streaming() {
+    thread_local! {
+        static BYTES_READ: Cell<usize> = const { Cell::new(0) };
+    }
+
+    struct CountingReader<'a>(&'a [u8]);
+
+    impl io::Read for CountingReader<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let len = self.0.read(buf)?;
+            BYTES_READ.with(|read| read.set(read.get() + len));
+            Ok(len)
+        }
+    }
+
+    fn bytes_read<'de, D>(deserializer: D) -> Result<usize, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        <String as serde::Deserialize>::deserialize(deserializer)?;
+        Ok(BYTES_READ.with(Cell::get))
+    }
+
+    #[derive(Deserialize, Debug)]
+    struct Item {
+        id: usize,
+        #[serde(rename = "name", deserialize_with = "bytes_read")]
+        read: usize,
+    }
+
+    let mut yaml = String::new();
+    for id in 0..10_000 {
+        writeln!(yaml, "- id: {}\n  name: item {}", id, id).unwrap();
+    }
+    let items: Vec<Item> =
+        serde_yml::from_reader(CountingReader(yaml.as_bytes()))
+            .unwrap();
+    assert_eq!(items.len(), 10_000);
+    assert!(items.iter().enumerate().all(|(i, item)| item.id == i));
+    // The input is read in chunks as the items are deserialized.
+    assert!(items[0].read < yaml.len() / 2);
+    assert!(items.windows(2).all(|pair| pair[0].read <= pair[1].read));
+
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Entry {
+        id: usize,
+        name: String,
+    }
+
+    // Anchored nodes are kept for the aliases that follow them.
+    let yaml = indoc! {"
+        - &first {id: 1, name: first}
+        - <<: *first
+          id: 2
+        - *first
+    "};
+    let entries: Vec<Entry> =
+        serde_yml::from_reader(yaml.as_bytes()).unwrap();
+    let expected = vec![
+        Entry {
+            id: 1,
+            name: "first".to_owned(),
+        },
+        Entry {
+            id: 2,
+            name: "first".to_owned(),
+        },
+        Entry {
+            id: 1,
+            name: "first".to_owned(),
+        },
+    ];
+    assert_eq!(expected, entries);
+}
+
+#[test]
+fn test_from_reader_unread_root() {
+    // Whether a value is present, without reading it.
+    #[derive(PartialEq, Debug)]
+    struct Present(bool);
+
+    impl<'de> serde::Deserialize<'de> for Present {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: serde::Deserializer<'de>,
+        {
+            struct PresentVisitor;
+
+            impl<'de> serde::de::Visitor<'de> for PresentVisitor {
+                type Value = Present;
+
+                fn expecting(
+                    &self,
+                    formatter: &mut Formatter<'_>,
+                ) -> std::fmt::Result {
+                    formatter.write_str("an optional value")
+                }
+
+                fn visit_none<E>(self) -> Result<Present, E> {
+                    Ok(Present(false))
+                }
+
+                fn visit_some<D>(
+                    self,
+                    _deserializer: D,
+                ) -> Result<Present, D::Error>
+                where
+                    D: serde::Deserializer<'de>,
+                {
+                    Ok(Present(true))
+                }
+            }
+
+            deserializer.deserialize_option(PresentVisitor)
+        }
+    }
+
+    test_de("a: 1\nb: 2\n", &Present(true));
+    test_de("[1, [2, 3]]\n", &Present(true));
+    test_de("~\n", &Present(false));
+
+    let yaml = "a: 1\nb: 2\n---\nc: 3\n";
+    let expected = serde_yml::from_str::<Present>(yaml).unwrap_err();
+    let error = serde_yml::from_reader::<_, Present>(yaml.as_bytes())
+        .unwrap_err();
+    assert_eq!(error.to_string(), expected.to_string());
+}
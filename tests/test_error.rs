@@ -6,18 +6,21 @@
 #![allow(clippy::zero_sized_map_values)]
 
 use indoc::indoc;
-use serde::de::Deserialize;
+use serde::de::{Deserialize, DeserializeOwned};
 #[cfg(not(miri))]
 use serde::de::{SeqAccess, Visitor};
 use serde_derive::{Deserialize, Serialize};
 use serde_yml::value::{Tag, TaggedValue};
-use serde_yml::{Deserializer, Value};
-#[cfg(not(miri))]
+use serde_yml::{
+    Deserializer, DeserializerBuilder, DuplicateKeys, NestedEnumStyle,
+    Schema, SerializerBuilder, Value,
+};
 use std::collections::BTreeMap;
 #[cfg(not(miri))]
 use std::fmt;
 use std::fmt::Debug;
 use std::fmt::Formatter;
+use std::io;
 
 fn test_error<'de, T>(yaml: &'de str, expected: &str)
 where
@@ -26,6 +29,10 @@ where
     let result = serde_yml::from_str::<T>(yaml);
     assert_eq!(expected, result.unwrap_err().to_string());
 
+    let result =
+        T::deserialize(Deserializer::from_reader(yaml.as_bytes()));
+    assert_eq!(expected, result.unwrap_err().to_string());
+
     let mut deserializer = Deserializer::from_str(yaml);
     if let Some(first_document) = deserializer.next() {
         if deserializer.next().is_none() {
@@ -128,8 +135,12 @@ fn test_ignored_unknown_anchor() {
 
 #[test]
 fn test_bytes() {
-    let expected = "serialization and deserialization of bytes in YAML is not implemented";
-    test_error::<&[u8]>("...", expected);
+    let yaml = indoc! {"
+        ---
+        !!binary '@@@'
+    "};
+    let expected = "invalid value: string \"@@@\", expected base64-encoded binary data at line 2 column 1";
+    test_error::<&[u8]>(yaml, expected);
 }
 
 #[test]
@@ -193,19 +204,21 @@ fn test_serialize_nested_enum() {
         Struct { x: usize },
     }
 
+    let builder =
+        SerializerBuilder::new().nested_enums(NestedEnumStyle::Error);
     let expected =
         "serializing nested enums in YAML is not supported yet";
 
     let e = Outer::Inner(Inner::Newtype(0));
-    let error = serde_yml::to_string(&e).unwrap_err();
+    let error = serde_yml::to_string_with(&e, &builder).unwrap_err();
     assert_eq!(error.to_string(), expected);
 
     let e = Outer::Inner(Inner::Tuple(0, 0));
-    let error = serde_yml::to_string(&e).unwrap_err();
+    let error = serde_yml::to_string_with(&e, &builder).unwrap_err();
     assert_eq!(error.to_string(), expected);
 
     let e = Outer::Inner(Inner::Struct { x: 0 });
-    let error = serde_yml::to_string(&e).unwrap_err();
+    let error = serde_yml::to_string_with(&e, &builder).unwrap_err();
     assert_eq!(error.to_string(), expected);
 
     let e = Value::Tagged(Box::new(TaggedValue {
@@ -215,7 +228,7 @@ fn test_serialize_nested_enum() {
             value: Value::Null,
         })),
     }));
-    let error = serde_yml::to_string(&e).unwrap_err();
+    let error = serde_yml::to_string_with(&e, &builder).unwrap_err();
     assert_eq!(error.to_string(), expected);
 }
 
@@ -512,3 +525,295 @@ fn test_duplicate_keys() {
     let expected = "duplicate entry in YAML map at line 2 column 1";
     test_error::<Value>(yaml, expected);
 }
+
+#[test]
+fn test_duplicate_keys_error() {
+    #[derive(Deserialize, Debug)]
+    #[allow(dead_code)]
+    struct Server {
+        host: String,
+        port: u16,
+    }
+
+    let builder =
+        DeserializerBuilder::new().duplicate_keys(DuplicateKeys::Error);
+    let yaml = indoc! {"
+        port: 80
+        host: a
+        port: 8080
+    "};
+    let expected = "duplicate entry with key \"port\" at line 3 column 1, first defined at line 1 column 1";
+    test_error_with::<Value>(yaml, &builder, expected);
+    test_error_with::<Server>(yaml, &builder, expected);
+    test_error_with::<BTreeMap<String, Value>>(
+        yaml, &builder, expected,
+    );
+
+    let yaml = indoc! {"
+        servers:
+          - {1: a, 0x1: b}
+    "};
+    let expected = "duplicate entry with key 1 at line 2 column 12, first defined at line 2 column 6";
+    test_error_with::<Value>(yaml, &builder, expected);
+}
+
+fn test_error_with<T>(
+    yaml: &str,
+    builder: &DeserializerBuilder,
+    expected: &str,
+) where
+    T: DeserializeOwned + Debug,
+{
+    let result = serde_yml::from_str_with::<T>(yaml, builder);
+    assert_eq!(expected, result.unwrap_err().to_string());
+
+    let result =
+        serde_yml::from_reader_with::<_, T>(yaml.as_bytes(), builder);
+    assert_eq!(expected, result.unwrap_err().to_string());
+}
+
+#[test]
+fn test_max_depth() {
+    let builder = DeserializerBuilder::new().max_depth(2);
+    let expected = "recursion limit exceeded at line 1 column 3";
+    test_error_with::<Value>("[[[1]]]", &builder, expected);
+
+    let value: Value =
+        serde_yml::from_str_with("[[1]]", &builder).unwrap();
+    assert_eq!(value[0][0], 1);
+}
+
+#[test]
+fn test_max_alias_expansions() {
+    let yaml = indoc! {"
+        a: &a [1, 2]
+        b: [*a, *a, *a]
+    "};
+    let builder = DeserializerBuilder::new().max_alias_expansions(2);
+    test_error_with::<Value>(
+        yaml,
+        &builder,
+        "repetition limit exceeded",
+    );
+
+    let builder = DeserializerBuilder::new().max_alias_expansions(3);
+    let value: Value =
+        serde_yml::from_str_with(yaml, &builder).unwrap();
+    assert_eq!(value["b"][2][1], 2);
+}
+
+#[test]
+fn test_max_input_size() {
+    let builder = DeserializerBuilder::new().max_input_size(8);
+    let expected = "input is larger than the limit of 8 bytes";
+    test_error_with::<Value>("key: value", &builder, expected);
+
+    let value: Value =
+        serde_yml::from_str_with("key: val", &builder).unwrap();
+    assert_eq!(value["key"], "val");
+}
+
+#[test]
+fn test_max_scalar_length() {
+    let builder = DeserializerBuilder::new().max_scalar_length(3);
+    let expected =
+        "scalar is longer than the limit of 3 bytes at line 2 column 4";
+    test_error_with::<Value>("a: abc\nb: abcd\n", &builder, expected);
+}
+
+#[test]
+fn test_max_events() {
+    // A mapping with one entry is three events: the start, the key and
+    // the value. The end is the fourth.
+    let builder = DeserializerBuilder::new().max_events(3);
+    let expected =
+        "number of events exceeds the limit of 3 at line 1 column 6";
+    test_error_with::<Value>("{a: 1}", &builder, expected);
+
+    let builder = DeserializerBuilder::new().max_events(4);
+    let value: Value =
+        serde_yml::from_str_with("{a: 1}", &builder).unwrap();
+    assert_eq!(value["a"], 1);
+}
+
+#[test]
+fn test_max_events_before_type_error() {
+    // The document stops short of the merged entry, which must not be
+    // reported as missing.
+    #[derive(Deserialize, Debug)]
+    struct Merged {
+        #[allow(dead_code)]
+        a: i32,
+    }
+
+    let builder = DeserializerBuilder::new().max_events(3);
+    let expected =
+        "number of events exceeds the limit of 3 at line 1 column 6";
+    test_error_with::<Merged>("<<: {a: 1}\n", &builder, expected);
+}
+
+#[test]
+fn test_max_aliases() {
+    let yaml = indoc! {"
+        a: &a 1
+        b: *a
+        c: *a
+    "};
+    let builder = DeserializerBuilder::new().max_aliases(1);
+    let expected =
+        "number of aliases exceeds the limit of 1 at line 3 column 4";
+    test_error_with::<Value>(yaml, &builder, expected);
+
+    let builder = DeserializerBuilder::new().max_aliases(2);
+    let value: Value =
+        serde_yml::from_str_with(yaml, &builder).unwrap();
+    assert_eq!(value["c"], 1);
+}
+
+#[test]
+fn test_reject_unknown_tags() {
+    #[derive(Deserialize, Debug, PartialEq)]
+    enum Shape {
+        Circle { radius: f64 },
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Drawing {
+        name: String,
+        shape: Shape,
+    }
+
+    let builder = DeserializerBuilder::new().reject_unknown_tags(true);
+    let yaml = "name: !!str 1\nshape: !Circle {radius: 2.0}\n";
+    let drawing: Drawing =
+        serde_yml::from_str_with(yaml, &builder).unwrap();
+    assert_eq!(drawing.name, "1");
+    assert_eq!(drawing.shape, Shape::Circle { radius: 2.0 });
+
+    let yaml = "name: !Name x\nshape: !Circle {radius: 2.0}\n";
+    let expected = "unknown tag !Name at line 1 column 7";
+    test_error_with::<Drawing>(yaml, &builder, expected);
+
+    let yaml = "shape: !Circle {radius: !Radius 2.0}\n";
+    let expected = "unknown tag !Radius at line 1 column 25";
+    test_error_with::<Value>(
+        yaml,
+        &builder,
+        "unknown tag !Circle at line 1 column 8",
+    );
+    test_error_with::<BTreeMap<String, Shape>>(
+        yaml, &builder, expected,
+    );
+
+    let yaml = "!<tag:example.com,2024:point> [1, 2]\n";
+    let expected = "unknown tag tag:example.com,2024:point";
+    test_error_with::<Vec<i32>>(yaml, &builder, expected);
+}
+
+#[test]
+fn test_safe_builder() {
+    let builder = DeserializerBuilder::safe();
+    let yaml = indoc! {"
+        a: &a [1, 2]
+        b: *a
+    "};
+    let expected =
+        "number of aliases exceeds the limit of 0 at line 2 column 4";
+    test_error_with::<Value>(yaml, &builder, expected);
+
+    let yaml = "key: !Tag value\n";
+    let expected = "unknown tag !Tag at line 1 column 6";
+    test_error_with::<Value>(yaml, &builder, expected);
+
+    let yaml = "[".repeat(65) + &"]".repeat(65);
+    let expected = "recursion limit exceeded at line 1 column 65";
+    test_error_with::<Value>(&yaml, &builder, expected);
+
+    let yaml = "x".repeat(4 * 1024 * 1024 + 1);
+    let expected = "input is larger than the limit of 4194304 bytes";
+    test_error_with::<Value>(&yaml, &builder, expected);
+
+    let value: Value =
+        serde_yml::from_str_with("a: [1, 2]\nb: !!str 3\n", &builder)
+            .unwrap();
+    assert_eq!(value["b"], "3");
+}
+
+#[test]
+fn test_json_schema() {
+    #[derive(Deserialize, Debug)]
+    struct Settings {
+        #[allow(dead_code)]
+        enabled: bool,
+        #[allow(dead_code)]
+        mask: u32,
+    }
+
+    let builder = DeserializerBuilder::new().schema(Schema::Json);
+    let yaml = "enabled: True\nmask: 31\n";
+    let expected = "enabled: invalid type: string \"True\", expected a boolean at line 1 column 10";
+    test_error_with::<Settings>(yaml, &builder, expected);
+
+    let yaml = "enabled: true\nmask: 0x1F\n";
+    let expected = "mask: invalid type: string \"0x1F\", expected u32 at line 2 column 7";
+    test_error_with::<Settings>(yaml, &builder, expected);
+}
+
+#[test]
+fn test_merge_keys() {
+    #[derive(Deserialize, Debug)]
+    struct Point {
+        #[allow(dead_code)]
+        x: i32,
+    }
+
+    let yaml = "<<: 1\nx: 0\n";
+    let expected = "expected a mapping or list of mappings for merging, but found scalar";
+    test_error::<Point>(yaml, expected);
+
+    let yaml = "<<: [{x: 1}, [x]]\n";
+    let expected = "expected a mapping for merging, but found sequence";
+    test_error::<Point>(yaml, expected);
+
+    let yaml = "<<: !Base {x: 1}\n";
+    let expected = "unexpected tagged value in merge";
+    test_error::<Point>(yaml, expected);
+
+    let yaml = "<<: @\n";
+    let expected = "found character that cannot start any token at line 1 column 5, while scanning for the next token";
+    test_error::<Point>(yaml, expected);
+}
+
+#[test]
+fn test_reader_error() {
+    struct FailingReader<'a>(&'a [u8]);
+
+    impl io::Read for FailingReader<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.0.is_empty() {
+                return Err(io::Error::new(
+                    io::ErrorKind::ConnectionReset,
+                    "connection reset",
+                ));
+            }
+            self.0.read(buf)
+        }
+    }
+
+    let result = serde_yml::from_reader::<_, Vec<i32>>(FailingReader(
+        b"- 1\n- 2\n",
+    ));
+    let error = result.unwrap_err();
+    assert_eq!("connection reset", error.to_string());
+    assert_eq!(
+        io::ErrorKind::ConnectionReset,
+        error.io_error().unwrap().kind(),
+    );
+
+    // An error in the input is found before the rest of it is read.
+    let result = serde_yml::from_reader::<_, Vec<i32>>(FailingReader(
+        b"- 1\n- x\n- 3\n- 4\n- 5\n",
+    ));
+    let expected = "\\[1\\]: invalid type: string \"x\", expected i32 at line 2 column 3";
+    assert_eq!(expected, result.unwrap_err().to_string());
+}
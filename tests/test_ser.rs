@@ -1,11 +1,23 @@
 #[cfg(test)]
 mod tests {
-    use serde::{ser::Serializer as _, Serialize};
+    use serde::{ser::Serializer as _, Deserialize, Serialize};
     use serde_yml::{
         libyml::emitter::{Scalar, ScalarStyle},
-        Serializer, State,
+        value::{Tag, TaggedValue},
+        AnchorMode, CollectionStyle, ComplexKeyStyle, DocumentWriter,
+        FloatFormat, LineBreak, MappingWriter, NestedEnumStyle,
+        NonFiniteFloats, NullStyle, QuotingPolicy, SequenceWriter,
+        Serializer, SerializerBuilder, SortKeys, State, Value,
+        YamlVersion,
+    };
+    use std::{
+        cell::{Cell, RefCell},
+        collections::{BTreeMap, HashMap},
+        fmt::Write,
+        io,
+        rc::Rc,
+        thread,
     };
-    use std::{collections::BTreeMap, fmt::Write};
 
     // Test cases for scalar serialization
     #[test]
@@ -14,6 +26,7 @@ mod tests {
         let mut buffer = Vec::new();
         let mut serializer = Serializer::new(&mut buffer);
         let scalar_value = Scalar {
+            anchor: None,
             tag: None,
             value: "test value",
             style: ScalarStyle::Plain,
@@ -21,6 +34,7 @@ mod tests {
 
         // Act
         serializer.emit_scalar(scalar_value).unwrap();
+        drop(serializer);
 
         // Assert
         assert_eq!(
@@ -39,6 +53,7 @@ mod tests {
 
         // Act
         serializer.emit_sequence_start().unwrap();
+        drop(serializer);
 
         // Assert
         assert_eq!(
@@ -57,6 +72,7 @@ mod tests {
 
         // Act
         serializer.emit_mapping_start().unwrap();
+        drop(serializer);
 
         // Assert
         assert_eq!(
@@ -76,6 +92,7 @@ mod tests {
 
         // Act
         serializer.flush_mapping_start().unwrap();
+        drop(serializer);
 
         // Assert
         assert_eq!(
@@ -145,6 +162,7 @@ mod tests {
         // Act
         serializer.serialize_bool(true).unwrap();
         serializer.serialize_bool(false).unwrap();
+        drop(serializer);
 
         // Assert
         assert_eq!(
@@ -164,6 +182,7 @@ mod tests {
         // Act
         serializer.serialize_i64(42).unwrap();
         serializer.serialize_i64(-100).unwrap();
+        drop(serializer);
 
         // Assert
         assert_eq!(
@@ -185,6 +204,7 @@ mod tests {
         serializer.serialize_f64(f64::INFINITY).unwrap();
         serializer.serialize_f64(f64::NEG_INFINITY).unwrap();
         serializer.serialize_f64(f64::NAN).unwrap();
+        drop(serializer);
 
         // Assert
         assert_eq!(
@@ -204,6 +224,7 @@ mod tests {
         // Act
         serializer.serialize_char('a').unwrap();
         serializer.serialize_char('💻').unwrap();
+        drop(serializer);
 
         // Assert
         assert_eq!(
@@ -225,6 +246,7 @@ mod tests {
         // Act
         some_value.serialize(&mut serializer).unwrap();
         none_value.serialize(&mut serializer).unwrap();
+        drop(serializer);
 
         // Assert
         assert_eq!(
@@ -252,6 +274,7 @@ mod tests {
         MyEnum::A.serialize(&mut serializer).unwrap();
         MyEnum::B(42).serialize(&mut serializer).unwrap();
         MyEnum::C { x: 1, y: 2 }.serialize(&mut serializer).unwrap();
+        drop(serializer);
 
         // Assert
         assert_eq!(
@@ -271,6 +294,7 @@ mod tests {
 
         // Act
         sequence.serialize(&mut serializer).unwrap();
+        drop(serializer);
 
         // Assert
         assert_eq!(
@@ -292,6 +316,7 @@ mod tests {
 
         // Act
         map.serialize(&mut serializer).unwrap();
+        drop(serializer);
 
         // Assert
         assert_eq!(
@@ -331,6 +356,7 @@ mod tests {
             },
         };
         person.serialize(&mut serializer).unwrap();
+        drop(serializer);
 
         // Assert
         assert_eq!(
@@ -361,6 +387,7 @@ mod tests {
             age: None,
         };
         user.serialize(&mut serializer).unwrap();
+        drop(serializer);
 
         // Assert
         assert_eq!(
@@ -388,6 +415,7 @@ mod tests {
             value: "example".to_string(),
         };
         tagged_value.serialize(&mut serializer).unwrap();
+        drop(serializer);
 
         // Assert
         assert_eq!(
@@ -407,6 +435,7 @@ mod tests {
 
         // Act
         large_sequence.serialize(&mut serializer).unwrap();
+        drop(serializer);
 
         // Assert
         let mut expected_output = String::new(); // Create an empty String
@@ -436,6 +465,7 @@ mod tests {
 
         // Act
         nested_sequences.serialize(&mut serializer).unwrap();
+        drop(serializer);
 
         // Assert
         assert_eq!(
@@ -463,6 +493,7 @@ mod tests {
 
         // Act
         nested_maps.serialize(&mut serializer).unwrap();
+        drop(serializer);
 
         // Assert
         assert_eq!(
@@ -502,6 +533,7 @@ mod tests {
             },
         };
         mixed_data.serialize(&mut serializer).unwrap();
+        drop(serializer);
 
         // Assert
         assert_eq!(
@@ -523,6 +555,7 @@ mod tests {
         // Act
         empty_sequence.serialize(&mut serializer).unwrap();
         empty_map.serialize(&mut serializer).unwrap();
+        drop(serializer);
 
         // Assert
         assert_eq!(
@@ -542,6 +575,7 @@ mod tests {
 
         // Act
         special_string.serialize(&mut serializer).unwrap();
+        drop(serializer);
 
         // Assert
         assert_eq!(
@@ -586,6 +620,7 @@ mod tests {
             value: "example".to_string(),
         };
         custom_struct.serialize(&mut serializer).unwrap();
+        drop(serializer);
 
         // Assert
         assert_eq!(
@@ -594,4 +629,1839 @@ mod tests {
             "Serialized custom serializer doesn't match expected output"
         );
     }
+
+    // Test cases for configuring indentation with the builder
+    #[test]
+    fn test_builder_indent() {
+        // Arrange
+        let mut map = BTreeMap::new();
+        map.insert("outer", BTreeMap::from([("inner", 1)]));
+        let builder = SerializerBuilder::new().indent(4);
+
+        // Act
+        let yaml = serde_yml::to_string_with(&map, &builder).unwrap();
+
+        // Assert
+        assert_eq!(
+            yaml, "outer:\n    inner: 1\n",
+            "Indentation doesn't match the configured width"
+        );
+    }
+
+    // Test cases for out of range indentation
+    #[test]
+    fn test_builder_indent_is_clamped() {
+        // Arrange
+        let mut map = BTreeMap::new();
+        map.insert("outer", BTreeMap::from([("inner", 1)]));
+        let builder = SerializerBuilder::new().indent(20);
+
+        // Act
+        let yaml = serde_yml::to_string_with(&map, &builder).unwrap();
+
+        // Assert
+        assert_eq!(
+            yaml,
+            format!("outer:\n{}inner: 1\n", " ".repeat(9)),
+            "Indentation should be clamped to 9 spaces"
+        );
+    }
+
+    // Test cases for wrapping long scalars at the line width
+    #[test]
+    fn test_builder_width() {
+        // Arrange
+        let text = "aaaa bbbb cccc dddd eeee ffff gggg hhhh";
+        let builder = SerializerBuilder::new().width(Some(20));
+
+        // Act
+        let wrapped =
+            serde_yml::to_string_with(&text, &builder).unwrap();
+        let unwrapped = serde_yml::to_string(&text).unwrap();
+
+        // Assert
+        assert_eq!(
+            wrapped, "aaaa bbbb cccc dddd eeee\n  ffff gggg hhhh\n",
+            "Long scalar should be wrapped at the configured width"
+        );
+        assert_eq!(unwrapped, format!("{}\n", text));
+        assert_eq!(
+            serde_yml::from_str::<String>(&wrapped).unwrap(),
+            text
+        );
+    }
+
+    // Test cases for indented sequences inside mappings
+    #[test]
+    fn test_builder_indent_sequences() {
+        // Arrange
+        let yaml = "a:\n- 1\n- b:\n  - x\n  c: |\n    line\n\n    end\n- - w\nz: 2\n";
+        let value: Value = serde_yml::from_str(yaml).unwrap();
+        let builder = SerializerBuilder::new().indent_sequences(true);
+
+        // Act
+        let indented =
+            serde_yml::to_string_with(&value, &builder).unwrap();
+
+        // Assert
+        assert_eq!(
+            indented,
+            "a:\n  - 1\n  - b:\n      - x\n    c: |\n      line\n\n      end\n  - - w\nz: 2\n",
+            "Sequences in mappings should be indented under their key"
+        );
+        assert_eq!(
+            serde_yml::from_str::<Value>(&indented).unwrap(),
+            value
+        );
+    }
+
+    // Test cases for indented sequences across several documents
+    #[test]
+    fn test_builder_indent_sequences_multiple_documents() {
+        // Arrange
+        let mut buffer = Vec::new();
+        let mut serializer = SerializerBuilder::new()
+            .indent(4)
+            .indent_sequences(true)
+            .build(&mut buffer);
+        let map = BTreeMap::from([("k", vec![1, 2])]);
+
+        // Act
+        map.serialize(&mut serializer).unwrap();
+        map.serialize(&mut serializer).unwrap();
+        drop(serializer);
+
+        // Assert
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            "k:\n    - 1\n    - 2\n---\nk:\n    - 1\n    - 2\n",
+            "Each document should be indented the same way"
+        );
+    }
+
+    // Test cases for the default builder matching Serializer::new
+    #[test]
+    fn test_builder_default_matches_new() {
+        // Arrange
+        let map = BTreeMap::from([("k", vec![1, 2])]);
+
+        // Act
+        let with_builder = serde_yml::to_string_with(
+            &map,
+            &Serializer::<Vec<u8>>::builder(),
+        )
+        .unwrap();
+
+        // Assert
+        assert_eq!(with_builder, serde_yml::to_string(&map).unwrap());
+        assert_eq!(with_builder, "k:\n- 1\n- 2\n");
+    }
+
+    // Test cases for the flow collection style
+    #[test]
+    fn test_collection_style_flow() {
+        // Arrange
+        let value: Value =
+            serde_yml::from_str("a: [1, {b: 2}]\nc: {}\n").unwrap();
+        let builder = SerializerBuilder::new()
+            .collection_style(CollectionStyle::Flow);
+
+        // Act
+        let yaml = serde_yml::to_string_with(&value, &builder).unwrap();
+
+        // Assert
+        assert_eq!(
+            yaml, "{a: [1, {b: 2}], c: {}}\n",
+            "Every collection should be written in flow style"
+        );
+        assert_eq!(serde_yml::from_str::<Value>(&yaml).unwrap(), value);
+    }
+
+    // Test cases for flow style on leaf collections only
+    #[test]
+    fn test_collection_style_flow_leaves() {
+        // Arrange
+        #[derive(Serialize)]
+        struct Point {
+            x: i32,
+            z: i32,
+        }
+
+        #[derive(Serialize)]
+        struct Shape {
+            name: &'static str,
+            points: Vec<Point>,
+            tags: Vec<&'static str>,
+            nested: Vec<Vec<u8>>,
+        }
+
+        let shape = Shape {
+            name: "line",
+            points: vec![Point { x: 1, z: 2 }, Point { x: 3, z: 4 }],
+            tags: vec!["a", "yes"],
+            nested: vec![vec![1, 2], vec![]],
+        };
+        let builder = SerializerBuilder::new()
+            .collection_style(CollectionStyle::FlowLeaves);
+
+        // Act
+        let yaml = serde_yml::to_string_with(&shape, &builder).unwrap();
+
+        // Assert
+        assert_eq!(
+            yaml,
+            "name: line\npoints:\n- {x: 1, z: 2}\n- {x: 3, z: 4}\ntags: [a, 'yes']\nnested:\n- [1, 2]\n- []\n",
+            "Only collections of scalars should be written in flow style"
+        );
+    }
+
+    // Test cases for a flat document root with flow leaves
+    #[test]
+    fn test_collection_style_flow_leaves_root_is_block() {
+        // Arrange
+        let builder = SerializerBuilder::new()
+            .collection_style(CollectionStyle::FlowLeaves);
+
+        // Act
+        let yaml =
+            serde_yml::to_string_with(&vec![1, 2], &builder).unwrap();
+
+        // Assert
+        assert_eq!(yaml, "- 1\n- 2\n");
+    }
+
+    // Test cases for the explicit document start marker
+    #[test]
+    fn test_builder_explicit_start() {
+        // Arrange
+        let builder = SerializerBuilder::new().explicit_start(true);
+
+        // Act
+        let yaml =
+            serde_yml::to_string_with(&vec![1, 2], &builder).unwrap();
+
+        // Assert
+        assert_eq!(yaml, "---\n- 1\n- 2\n");
+    }
+
+    // Test cases for explicit markers across several documents
+    #[test]
+    fn test_builder_explicit_start_and_end_multiple_documents() {
+        // Arrange
+        let mut buffer = Vec::new();
+        let mut serializer = SerializerBuilder::new()
+            .explicit_start(true)
+            .explicit_end(true)
+            .build(&mut buffer);
+
+        // Act
+        1.serialize(&mut serializer).unwrap();
+        "two".serialize(&mut serializer).unwrap();
+        drop(serializer);
+
+        // Assert
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            "--- 1\n...\n--- two\n...\n",
+            "Every document should be enclosed in markers"
+        );
+    }
+
+    // Test cases for the %YAML directive
+    #[test]
+    fn test_builder_version_directive() {
+        // Arrange
+        let builder = SerializerBuilder::new()
+            .version_directive(YamlVersion::V1_2);
+        let map = BTreeMap::from([("k", 1)]);
+
+        // Act
+        let yaml = serde_yml::to_string_with(&map, &builder).unwrap();
+
+        // Assert
+        assert_eq!(yaml, "%YAML 1.2\n---\nk: 1\n");
+        let deserialized: BTreeMap<String, i32> =
+            serde_yml::from_str(&yaml).unwrap();
+        assert_eq!(deserialized["k"], 1);
+    }
+
+    // Test cases for %TAG shorthand of tagged values and enum variants
+    #[test]
+    fn test_builder_tag_directive() {
+        // Arrange
+        #[derive(Serialize)]
+        enum Shape {
+            Circle(u32),
+        }
+
+        let value = Value::Tagged(Box::new(TaggedValue {
+            tag: Tag::new("tag:example.com,2024:widget"),
+            value: Value::from(1),
+        }));
+        let builder = SerializerBuilder::new()
+            .tag_directive("!app!", "tag:example.com,2024:")
+            .tag_directive("!s!", "!");
+
+        // Act
+        let tagged =
+            serde_yml::to_string_with(&value, &builder).unwrap();
+        let variant =
+            serde_yml::to_string_with(&Shape::Circle(2), &builder)
+                .unwrap();
+
+        // Assert
+        assert_eq!(
+            tagged,
+            "%TAG !app! tag:example.com,2024:\n%TAG !s! %21\n--- !app!widget 1\n",
+        );
+        let deserialized: Value = serde_yml::from_str(&tagged).unwrap();
+        assert_eq!(deserialized, value);
+        assert_eq!(
+            variant,
+            "%TAG !app! tag:example.com,2024:\n%TAG !s! %21\n--- !s!Circle 2\n",
+        );
+    }
+
+    // Test cases for tags outside of any declared prefix
+    #[test]
+    fn test_builder_tag_directive_unmatched_tag() {
+        // Arrange
+        let value = Value::Tagged(Box::new(TaggedValue {
+            tag: Tag::new("Thing"),
+            value: Value::from(1),
+        }));
+        let builder = SerializerBuilder::new()
+            .tag_directive("!app!", "tag:example.com,2024:");
+
+        // Act
+        let yaml = serde_yml::to_string_with(&value, &builder).unwrap();
+
+        // Assert
+        assert_eq!(
+            yaml,
+            "%TAG !app! tag:example.com,2024:\n--- !Thing 1\n"
+        );
+        let deserialized: Value = serde_yml::from_str(&yaml).unwrap();
+        assert_eq!(deserialized, value);
+    }
+
+    // Test cases for aliases of shared pointers
+    #[test]
+    fn test_anchors_shared() {
+        // Arrange
+        #[derive(Serialize)]
+        struct Service {
+            #[serde(with = "serde_yml::with::shared")]
+            env: Rc<BTreeMap<&'static str, &'static str>>,
+            #[serde(with = "serde_yml::with::shared")]
+            name: Rc<str>,
+        }
+
+        let env = Rc::new(BTreeMap::from([("LOG", "debug")]));
+        let services = vec![
+            Service {
+                env: Rc::clone(&env),
+                name: Rc::from("web"),
+            },
+            Service {
+                env,
+                name: Rc::from("web"),
+            },
+        ];
+        let builder =
+            SerializerBuilder::new().anchors(AnchorMode::Shared);
+
+        // Act
+        let yaml =
+            serde_yml::to_string_with(&services, &builder).unwrap();
+
+        // Assert
+        assert_eq!(
+            yaml,
+            "- env: &id001\n    LOG: debug\n  name: web\n- env: *id001\n  name: web\n",
+            "Only the same allocation should be aliased"
+        );
+        let value: Value = serde_yml::from_str(&yaml).unwrap();
+        assert_eq!(value[0], value[1]);
+    }
+
+    // Test cases for shared pointers with anchors turned off
+    #[test]
+    fn test_anchors_off() {
+        // Arrange
+        #[derive(Serialize)]
+        struct Pair {
+            #[serde(with = "serde_yml::with::shared")]
+            first: Rc<Vec<u8>>,
+            #[serde(with = "serde_yml::with::shared")]
+            second: Rc<Vec<u8>>,
+        }
+
+        let shared = Rc::new(vec![1]);
+        let pair = Pair {
+            first: Rc::clone(&shared),
+            second: shared,
+        };
+
+        // Act
+        let yaml = serde_yml::to_string(&pair).unwrap();
+
+        // Assert
+        assert_eq!(yaml, "first:\n- 1\nsecond:\n- 1\n");
+    }
+
+    // Test cases for shared pointers whose allocation is reused
+    #[test]
+    fn test_anchors_shared_reused_allocation() {
+        // Arrange
+        thread_local! {
+            // One allocation, rewritten for every field, as if each
+            // temporary pointer reused the allocation of the one before.
+            static SLOT: RefCell<Rc<Vec<u8>>> = RefCell::new(Rc::new(Vec::new()));
+        }
+
+        fn temporary<S>(
+            value: &[u8],
+            serializer: S,
+        ) -> Result<S::Ok, S::Error>
+        where
+            S: serde::Serializer,
+        {
+            SLOT.with(|slot| {
+                let mut pointer = slot.borrow_mut();
+                *Rc::get_mut(&mut pointer).unwrap() = value.to_vec();
+                serde_yml::with::shared::serialize(
+                    &*pointer, serializer,
+                )
+            })
+        }
+
+        #[derive(Serialize)]
+        struct Pair {
+            #[serde(serialize_with = "temporary")]
+            a: Vec<u8>,
+            #[serde(serialize_with = "temporary")]
+            b: Vec<u8>,
+        }
+
+        let pair = Pair {
+            a: vec![1, 1],
+            b: vec![2, 2],
+        };
+        let builder =
+            SerializerBuilder::new().anchors(AnchorMode::Shared);
+
+        // Act
+        let yaml = serde_yml::to_string_with(&pair, &builder).unwrap();
+
+        // Assert
+        assert_eq!(yaml, "a:\n- 1\n- 1\nb:\n- 2\n- 2\n");
+    }
+
+    // Test cases for aliases of repeated Value subtrees
+    #[test]
+    fn test_anchors_repeated() {
+        // Arrange
+        let value: Value = serde_yml::from_str(
+            "a: {x: 1, y: [1, 2]}\nb: {x: 1, y: [1, 2]}\nc: [1, 2]\nd: []\ne: []",
+        )
+        .unwrap();
+        let builder =
+            SerializerBuilder::new().anchors(AnchorMode::Repeated);
+
+        // Act
+        let yaml = serde_yml::to_string_with(&value, &builder).unwrap();
+
+        // Assert
+        assert_eq!(
+            yaml,
+            "a: &id001\n  x: 1\n  'y': &id002\n  - 1\n  - 2\nb: *id001\nc: *id002\nd: []\ne: []\n",
+            "Identical non-empty collections should be aliased"
+        );
+        let deserialized: Value = serde_yml::from_str(&yaml).unwrap();
+        assert_eq!(deserialized, value);
+    }
+
+    // Test cases for anchor names in several documents
+    #[test]
+    fn test_anchors_multiple_documents() {
+        // Arrange
+        let mut buffer = Vec::new();
+        let mut serializer = SerializerBuilder::new()
+            .anchors(AnchorMode::Repeated)
+            .build(&mut buffer);
+        let document = vec![vec![1], vec![1]];
+
+        // Act
+        document.serialize(&mut serializer).unwrap();
+        document.serialize(&mut serializer).unwrap();
+        drop(serializer);
+
+        // Assert
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            "- &id001\n  - 1\n- *id001\n---\n- &id001\n  - 1\n- *id001\n",
+            "Anchors should be numbered from the start of each document"
+        );
+    }
+
+    // Test cases for the header comment of every document
+    #[test]
+    fn test_header_comment_multiple_documents() {
+        // Arrange
+        let mut buffer = Vec::new();
+        let mut serializer = SerializerBuilder::new()
+            .header_comment("Generated\n\nby a test")
+            .build(&mut buffer);
+
+        // Act
+        BTreeMap::from([("k", 1)])
+            .serialize(&mut serializer)
+            .unwrap();
+        BTreeMap::from([("k", 2)])
+            .serialize(&mut serializer)
+            .unwrap();
+        drop(serializer);
+
+        // Assert
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            "# Generated\n#\n# by a test\nk: 1\n---\n# Generated\n#\n# by a test\nk: 2\n",
+            "The header should follow the document start marker"
+        );
+    }
+
+    // Test cases for comments before mapping keys and sequence items
+    #[test]
+    fn test_comments_by_path() {
+        // Arrange
+        let value: Value = serde_yml::from_str(
+            "name: web\nports: [80, 443]\nenv: [{LOG: debug}]",
+        )
+        .unwrap();
+        let builder = SerializerBuilder::new()
+            .comment("name", "Service name")
+            .comment("ports[1]", "TLS")
+            .comment("env[0]", "First")
+            .comment("env[0].LOG", "Log level")
+            .comment("missing", "Never written");
+
+        // Act
+        let yaml = serde_yml::to_string_with(&value, &builder).unwrap();
+
+        // Assert
+        assert_eq!(
+            yaml,
+            "# Service name\nname: web\nports:\n- 80\n# TLS\n- 443\nenv:\n# First\n# Log level\n- LOG: debug\n",
+        );
+        let deserialized: Value = serde_yml::from_str(&yaml).unwrap();
+        assert_eq!(deserialized, value);
+    }
+
+    // Test cases for comments in indented sequences
+    #[test]
+    fn test_comments_with_indent_sequences() {
+        // Arrange
+        let value: Value =
+            serde_yml::from_str("hosts: [web, db]").unwrap();
+        let builder = SerializerBuilder::new()
+            .indent(4)
+            .indent_sequences(true)
+            .comment("hosts[1]", "Replica");
+
+        // Act
+        let yaml = serde_yml::to_string_with(&value, &builder).unwrap();
+
+        // Assert
+        assert_eq!(
+            yaml, "hosts:\n    - web\n    # Replica\n    - db\n",
+            "Comments should be indented like the shifted item"
+        );
+    }
+
+    // Test cases for nested enums written with the outer variant as a key
+    #[test]
+    fn test_nested_enum_outer_singleton_map() {
+        // Arrange
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        enum Outer {
+            Inner(Inner),
+        }
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        enum Inner {
+            Tuple(u8, u8),
+            Struct { x: u8 },
+        }
+
+        let builder = SerializerBuilder::new()
+            .nested_enums(NestedEnumStyle::OuterSingletonMap);
+        let things = vec![
+            Outer::Inner(Inner::Tuple(1, 2)),
+            Outer::Inner(Inner::Struct { x: 3 }),
+        ];
+
+        // Act
+        let yaml =
+            serde_yml::to_string_with(&things, &builder).unwrap();
+        let value = serde_yml::to_value(&things).unwrap();
+        let from_value =
+            serde_yml::to_string_with(&value, &builder).unwrap();
+        let deserialized: Vec<Outer> =
+            serde_yml::from_str(&yaml).unwrap();
+
+        // Assert
+        assert_eq!(
+            yaml,
+            "- Inner: !Tuple\n  - 1\n  - 2\n- Inner: !Struct\n    x: 3\n",
+            "The outer variant should be the key of a mapping"
+        );
+        assert_eq!(
+            from_value, yaml,
+            "Value should give the same output"
+        );
+        assert_eq!(deserialized, things, "Output should round trip");
+    }
+
+    // Test cases for sorting the keys of maps but not of structs
+    #[test]
+    fn test_sort_keys_maps() {
+        // Arrange
+        const NAMES: [&str; 20] = [
+            "u", "t", "s", "r", "q", "p", "o", "m", "l", "k", "j", "i",
+            "h", "g", "f", "e", "d", "c", "b", "a",
+        ];
+
+        #[derive(Serialize)]
+        struct Service {
+            name: &'static str,
+            env: HashMap<&'static str, &'static str>,
+        }
+
+        let service = Service {
+            name: "web",
+            env: (0..20).map(|i| (NAMES[i], "x")).collect(),
+        };
+        let builder =
+            SerializerBuilder::new().sort_keys(SortKeys::Maps);
+
+        // Act
+        let yaml =
+            serde_yml::to_string_with(&service, &builder).unwrap();
+
+        // Assert
+        let mut expected = String::from("name: web\nenv:\n");
+        let mut names = NAMES.to_vec();
+        names.sort_unstable();
+        for name in names {
+            writeln!(expected, "  {}: x", name).unwrap();
+        }
+        assert_eq!(yaml, expected, "Map keys should be sorted");
+    }
+
+    // Test cases for sorting the keys of every mapping
+    #[test]
+    fn test_sort_keys_all() {
+        // Arrange
+        #[derive(Serialize)]
+        struct Limits {
+            memory: u32,
+            cpu: u32,
+        }
+
+        #[derive(Serialize)]
+        struct Service {
+            name: &'static str,
+            limits: Limits,
+            ports: HashMap<u16, &'static str>,
+        }
+
+        let service = Service {
+            name: "web",
+            limits: Limits {
+                memory: 512,
+                cpu: 2,
+            },
+            ports: HashMap::from([(8080, "admin"), (443, "https")]),
+        };
+        let builder = SerializerBuilder::new().sort_keys(SortKeys::All);
+
+        // Act
+        let yaml =
+            serde_yml::to_string_with(&service, &builder).unwrap();
+
+        // Assert
+        assert_eq!(
+            yaml,
+            "limits:\n  cpu: 2\n  memory: 512\nname: web\nports:\n  443: https\n  8080: admin\n",
+            "Struct fields and numeric keys should be sorted"
+        );
+    }
+
+    // Test cases for sorted keys matching Value::sort_keys_recursive
+    #[test]
+    fn test_sort_keys_value() {
+        // Arrange
+        let mut value: Value = serde_yml::from_str(
+            "{b: 1, 2: x, null: n, true: t, [1]: s, !t x: tg, 1.5: f}",
+        )
+        .unwrap();
+        let builder = SerializerBuilder::new().sort_keys(SortKeys::All);
+
+        // Act
+        let sorted =
+            serde_yml::to_string_with(&value, &builder).unwrap();
+        value.sort_keys_recursive();
+
+        // Assert
+        assert_eq!(
+            sorted,
+            "null: 'n'\ntrue: t\n2: x\n1.5: f\nb: 1\n? - 1\n: s\n!t x: tg\n",
+            "Keys should be sorted by type, then by value"
+        );
+        assert_eq!(
+            serde_yml::to_string(&value).unwrap(),
+            sorted,
+            "Value::sort_keys_recursive should give the same order"
+        );
+    }
+
+    // Test cases for sorting float keys that include NaN
+    #[test]
+    fn test_sort_keys_nan() {
+        // Arrange
+        let mut value: Value = serde_yml::from_str(
+            "{.nan: a, 2.5: b, x: s, -.inf: c, 1.0: d, .inf: e}",
+        )
+        .unwrap();
+        let builder = SerializerBuilder::new().sort_keys(SortKeys::All);
+
+        // Act
+        let sorted =
+            serde_yml::to_string_with(&value, &builder).unwrap();
+        value.sort_keys_recursive();
+
+        // Assert
+        assert_eq!(
+            sorted,
+            "-.inf: c\n1.0: d\n2.5: b\n.inf: e\n.nan: a\nx: s\n",
+            "NaN should sort after every other float"
+        );
+        assert_eq!(
+            serde_yml::to_string(&value).unwrap(),
+            sorted,
+            "Value::sort_keys_recursive should give the same order"
+        );
+    }
+
+    // Test cases for the quoting policy
+    #[test]
+    fn test_quoting_minimal() {
+        // Arrange
+        let words = vec![
+            "yes", "off", "1:30", "0x1F", "1_000", "<<", "true",
+            "null", "", "12", "0123", "1.5", "text",
+        ];
+        let builder =
+            SerializerBuilder::new().quoting(QuotingPolicy::Minimal);
+
+        // Act
+        let yaml = serde_yml::to_string_with(&words, &builder).unwrap();
+
+        // Assert
+        assert_eq!(
+            yaml,
+            "- yes\n- off\n- 1:30\n- '0x1F'\n- 1_000\n- <<\n- 'true'\n- 'null'\n- ''\n- '12'\n- '0123'\n- '1.5'\n- text\n",
+            "Only strings read as another type should be quoted"
+        );
+        let back: Vec<String> = serde_yml::from_str(&yaml).unwrap();
+        assert_eq!(back, words, "Strings should round trip");
+    }
+
+    #[test]
+    fn test_quoting_yaml11_safe() {
+        // Arrange
+        let words = vec![
+            "yes", "Off", "1:30", "0o17", "1_000", "<<", "=", "text",
+        ];
+
+        // Act
+        let yaml = serde_yml::to_string(&words).unwrap();
+
+        // Assert
+        assert_eq!(
+            yaml,
+            "- 'yes'\n- 'Off'\n- '1:30'\n- '0o17'\n- '1_000'\n- '<<'\n- '='\n- text\n",
+            "Strings a YAML 1.1 parser could misread should be quoted"
+        );
+    }
+
+    #[test]
+    fn test_quoting_always() {
+        // Arrange
+        let mut map = BTreeMap::new();
+        map.insert("key", "it's\tdone");
+        map.insert("text", "plain");
+        let single =
+            SerializerBuilder::new().quoting(QuotingPolicy::Always);
+        let double = SerializerBuilder::new()
+            .quoting(QuotingPolicy::AlwaysDouble);
+
+        // Act
+        let single = serde_yml::to_string_with(&map, &single).unwrap();
+        let double = serde_yml::to_string_with(&map, &double).unwrap();
+
+        // Assert
+        assert_eq!(
+            single,
+            "'key': \"it's\\tdone\"\n'text': 'plain'\n",
+            "Every string should be quoted, with double quotes for escapes"
+        );
+        assert_eq!(
+            double, "\"key\": \"it's\\tdone\"\n\"text\": \"plain\"\n",
+            "Every string should be double quoted"
+        );
+    }
+
+    #[test]
+    fn test_quoting_always_with_plain() {
+        // Arrange
+        #[derive(Serialize)]
+        struct Entry {
+            #[serde(with = "serde_yml::with::plain")]
+            label: String,
+            #[serde(with = "serde_yml::with::plain")]
+            flag: String,
+        }
+        let entry = Entry {
+            label: "text".to_owned(),
+            flag: "yes".to_owned(),
+        };
+        let builder =
+            SerializerBuilder::new().quoting(QuotingPolicy::Always);
+
+        // Act
+        let yaml = serde_yml::to_string_with(&entry, &builder).unwrap();
+
+        // Assert
+        assert_eq!(
+            yaml, "'label': text\n'flag': 'yes'\n",
+            "Plain fields should stay plain where that is safe"
+        );
+    }
+
+    // Test cases for the null style
+    #[test]
+    fn test_null_style() {
+        // Arrange
+        let mut map = BTreeMap::new();
+        map.insert("a", None);
+        map.insert("b", Some(vec![None, Some(1)]));
+        let tilde =
+            SerializerBuilder::new().null_style(NullStyle::Tilde);
+        let empty =
+            SerializerBuilder::new().null_style(NullStyle::Empty);
+
+        // Act
+        let tilde = serde_yml::to_string_with(&map, &tilde).unwrap();
+        let empty = serde_yml::to_string_with(&map, &empty).unwrap();
+
+        // Assert
+        assert_eq!(tilde, "a: ~\nb:\n- ~\n- 1\n", "Nulls should be ~");
+        assert_eq!(empty, "a:\nb:\n-\n- 1\n", "Nulls should be empty");
+        let back: BTreeMap<&str, Option<Vec<Option<i32>>>> =
+            serde_yml::from_str(&empty).unwrap();
+        assert_eq!(back, map, "Empty nulls should round trip");
+    }
+
+    #[test]
+    fn test_null_style_empty_fallback() {
+        // Arrange
+        let mut map = BTreeMap::new();
+        map.insert(None, vec![None, Some(1)]);
+        let builder = SerializerBuilder::new()
+            .null_style(NullStyle::Empty)
+            .collection_style(CollectionStyle::Flow);
+
+        // Act
+        let yaml = serde_yml::to_string_with(&map, &builder).unwrap();
+
+        // Assert
+        assert_eq!(
+            yaml, "{null: [null, 1]}\n",
+            "Nulls that cannot be empty should be written as null"
+        );
+        let back: BTreeMap<Option<i32>, Vec<Option<i32>>> =
+            serde_yml::from_str(&yaml).unwrap();
+        assert_eq!(back, map, "Nulls should round trip");
+    }
+
+    // Test cases for ASCII-only and canonical output
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    enum Shape {
+        Circle(u32),
+        Rect { w: i32, h: i32 },
+        Dot,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Drawing {
+        title: String,
+        scale: f64,
+        layer: Option<u32>,
+        visible: bool,
+        shapes: Vec<Shape>,
+        labels: BTreeMap<String, char>,
+    }
+
+    fn drawing() -> Drawing {
+        Drawing {
+            title: "Café 日本 😀".to_owned(),
+            scale: 1.5,
+            layer: None,
+            visible: true,
+            shapes: vec![
+                Shape::Circle(3),
+                Shape::Rect { w: 2, h: -4 },
+                Shape::Dot,
+            ],
+            labels: BTreeMap::from([("first".to_owned(), 'é')]),
+        }
+    }
+
+    #[test]
+    fn test_ascii_only() {
+        // Arrange
+        let drawing = drawing();
+        let builder = SerializerBuilder::new().ascii_only(true);
+
+        // Act
+        let yaml =
+            serde_yml::to_string_with(&drawing, &builder).unwrap();
+
+        // Assert
+        assert!(yaml.is_ascii(), "Output should be ASCII: {}", yaml);
+        assert!(
+            yaml.starts_with(
+                "title: \"Caf\\xE9 \\u65E5\\u672C \\U0001F600\"\n"
+            ),
+            "Non-ASCII characters should be escaped: {}",
+            yaml
+        );
+        let back: Drawing = serde_yml::from_str(&yaml).unwrap();
+        assert_eq!(back, drawing, "Output should read back the same");
+        let value: Value = serde_yml::from_str(&yaml).unwrap();
+        assert_eq!(
+            value,
+            serde_yml::to_value(&drawing).unwrap(),
+            "Output should read back as the same Value"
+        );
+    }
+
+    #[test]
+    fn test_canonical() {
+        // Arrange
+        let drawing = drawing();
+        let builder = SerializerBuilder::new().canonical(true);
+
+        // Act
+        let yaml =
+            serde_yml::to_string_with(&drawing, &builder).unwrap();
+
+        // Assert
+        assert!(
+            yaml.starts_with(
+                "---\n!!map {\n  ? !!str \"title\"\n  : !!str \"Café 日本 😀\",\n  ? !!str \"scale\"\n  : !!float \"1.5\",\n  ? !!str \"layer\"\n  : !!null \"null\",\n"
+            ),
+            "Every node should have an explicit tag: {}",
+            yaml
+        );
+        let back: Drawing = serde_yml::from_str(&yaml).unwrap();
+        assert_eq!(back, drawing, "Output should read back the same");
+    }
+
+    #[test]
+    fn test_canonical_value() {
+        // Arrange
+        let value: Value = serde_yml::from_str(
+            "{a: [1, -2.5, true, null, '3', x], b: {c: ~}, 4: .inf}",
+        )
+        .unwrap();
+        let builder = SerializerBuilder::new().canonical(true);
+
+        // Act
+        let yaml = serde_yml::to_string_with(&value, &builder).unwrap();
+
+        // Assert
+        let back: Value = serde_yml::from_str(&yaml).unwrap();
+        assert_eq!(back, value, "Output should read back the same");
+        assert_eq!(
+            serde_yml::to_string_with(&back, &builder).unwrap(),
+            yaml,
+            "Canonical output should be stable"
+        );
+    }
+
+    // Test cases for line breaks
+    #[derive(Serialize)]
+    struct Bundle {
+        name: &'static str,
+        script: &'static str,
+        hosts: Vec<&'static str>,
+    }
+
+    fn bundle() -> Bundle {
+        Bundle {
+            name: "deploy",
+            script: "echo start\necho done\n",
+            hosts: vec!["web", "db"],
+        }
+    }
+
+    #[test]
+    fn test_line_break_crlf() {
+        // Arrange
+        let mut buffer = Vec::new();
+        let builder =
+            SerializerBuilder::new().line_break(LineBreak::CrLf);
+
+        // Act
+        serde_yml::to_writer_with(&mut buffer, &bundle(), &builder)
+            .unwrap();
+
+        // Assert
+        assert_eq!(
+            buffer,
+            b"name: deploy\r\nscript: |\r\n  echo start\r\n  echo done\r\nhosts:\r\n- web\r\n- db\r\n",
+            "Every line, including block scalar lines, should end with CRLF"
+        );
+        let back: BTreeMap<String, Value> =
+            serde_yml::from_slice(&buffer).unwrap();
+        assert_eq!(
+            back["script"], "echo start\necho done\n",
+            "Block scalar content should read back unchanged"
+        );
+    }
+
+    #[test]
+    fn test_line_break_cr_with_layout() {
+        // Arrange
+        let mut buffer = Vec::new();
+        let builder = SerializerBuilder::new()
+            .line_break(LineBreak::Cr)
+            .indent_sequences(true)
+            .header_comment("Bundle")
+            .comment("hosts", "Targets");
+
+        // Act
+        serde_yml::to_writer_with(&mut buffer, &bundle(), &builder)
+            .unwrap();
+
+        // Assert
+        assert_eq!(
+            buffer,
+            b"# Bundle\rname: deploy\rscript: |\r  echo start\r  echo done\r# Targets\rhosts:\r  - web\r  - db\r",
+            "Comments and indented sequences should use the same break"
+        );
+    }
+
+    // Test cases for writing several documents
+    #[test]
+    fn test_to_writer_documents() {
+        // Arrange
+        let manifests = vec![
+            BTreeMap::from([("kind", "Service")]),
+            BTreeMap::from([("kind", "Deployment")]),
+        ];
+        let mut buffer = Vec::new();
+
+        // Act
+        serde_yml::to_writer_documents(&mut buffer, &manifests)
+            .unwrap();
+
+        // Assert
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            "---\nkind: Service\n---\nkind: Deployment\n",
+            "Each value should be written as a document"
+        );
+    }
+
+    #[test]
+    fn test_document_writer_flushes_each_document() {
+        // Arrange
+        #[derive(Default)]
+        struct Log {
+            written: Vec<u8>,
+            flushed: Vec<String>,
+        }
+
+        impl io::Write for Log {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                self.written.extend_from_slice(buf);
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                let written = String::from_utf8(self.written.clone());
+                self.flushed.push(written.unwrap());
+                Ok(())
+            }
+        }
+
+        let mut writer = DocumentWriter::new(Log::default());
+
+        // Act
+        writer.write(&"first").unwrap();
+        writer.write(&vec![1, 2]).unwrap();
+        let log = writer.finish().unwrap();
+
+        // Assert
+        assert_eq!(
+            log.flushed[..2],
+            ["--- first\n", "--- first\n---\n- 1\n- 2\n"],
+            "Each document should be flushed as soon as it is written"
+        );
+    }
+
+    #[test]
+    fn test_document_writer_append() {
+        // Arrange
+        let mut log = Vec::new();
+        let builder = SerializerBuilder::new()
+            .collection_style(CollectionStyle::Flow);
+
+        // Act
+        let mut writer =
+            DocumentWriter::with_builder(&mut log, &builder);
+        writer.write(&BTreeMap::from([("event", "start")])).unwrap();
+        writer.write(&"kept\n\n").unwrap();
+        writer.finish().unwrap();
+        let mut writer =
+            DocumentWriter::with_builder(&mut log, &builder);
+        writer.write(&BTreeMap::from([("event", "stop")])).unwrap();
+        writer.finish().unwrap();
+
+        // Assert
+        let yaml = String::from_utf8(log).unwrap();
+        assert_eq!(
+            yaml,
+            "--- {event: start}\n--- |+\n  kept\n\n...\n--- {event: stop}\n",
+            "Appended documents should be separate from earlier ones"
+        );
+        let documents: Vec<Value> =
+            serde_yml::Deserializer::from_str(&yaml)
+                .map(|document| Value::deserialize(document).unwrap())
+                .collect();
+        assert_eq!(
+            documents,
+            [
+                serde_yml::from_str::<Value>("event: start").unwrap(),
+                Value::from("kept\n\n"),
+                serde_yml::from_str::<Value>("event: stop").unwrap(),
+            ],
+            "Every document should read back"
+        );
+    }
+
+    // Test cases for writing a sequence or mapping one entry at a time
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Record {
+        id: u32,
+        name: String,
+    }
+
+    #[test]
+    fn test_sequence_writer() {
+        // Arrange
+        let mut writer = SequenceWriter::new(Vec::new()).unwrap();
+
+        // Act
+        for id in 1..=2 {
+            let name = format!("user{}", id);
+            writer.write(&Record { id, name }).unwrap();
+        }
+        let yaml = writer.finish().unwrap();
+
+        // Assert
+        assert_eq!(
+            String::from_utf8(yaml).unwrap(),
+            "- id: 1\n  name: user1\n- id: 2\n  name: user2\n",
+            "Elements should be written as a sequence"
+        );
+    }
+
+    #[test]
+    fn test_sequence_writer_empty() {
+        // Arrange
+        let writer = SequenceWriter::new(Vec::new()).unwrap();
+
+        // Act
+        let yaml = writer.finish().unwrap();
+
+        // Assert
+        assert_eq!(yaml, b"[]\n", "No elements should give []");
+    }
+
+    #[test]
+    fn test_sequence_writer_streams() {
+        // Arrange
+        struct Counter {
+            bytes: Rc<Cell<usize>>,
+        }
+
+        impl io::Write for Counter {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                self.bytes.set(self.bytes.get() + buf.len());
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let bytes = Rc::new(Cell::new(0));
+        let counter = Counter {
+            bytes: Rc::clone(&bytes),
+        };
+        let mut writer = SequenceWriter::new(counter).unwrap();
+        let name = "x".repeat(100);
+
+        // Act
+        for id in 0..10_000 {
+            writer
+                .write(&Record {
+                    id,
+                    name: name.clone(),
+                })
+                .unwrap();
+        }
+        let written = bytes.get();
+        writer.finish().unwrap();
+
+        // Assert
+        assert!(
+            written > 1_000_000,
+            "Elements should reach the writer before the sequence ends"
+        );
+        assert!(
+            bytes.get() - written < 100_000,
+            "Only a bounded buffer should be left at the end"
+        );
+    }
+
+    #[test]
+    fn test_mapping_writer() {
+        // Arrange
+        let builder = SerializerBuilder::new()
+            .collection_style(CollectionStyle::FlowLeaves);
+        let mut writer =
+            MappingWriter::with_builder(Vec::new(), &builder).unwrap();
+
+        // Act
+        writer.write_entry("ports", &[80, 443]).unwrap();
+        writer.write_entry(&7, "seven").unwrap();
+        writer
+            .write_entry(
+                "owner",
+                &Record {
+                    id: 1,
+                    name: "ops".to_owned(),
+                },
+            )
+            .unwrap();
+        let yaml = String::from_utf8(writer.finish().unwrap()).unwrap();
+
+        // Assert
+        assert_eq!(
+            yaml,
+            "ports: [80, 443]\n7: seven\nowner: {id: 1, name: ops}\n",
+            "Entries should be written as a mapping"
+        );
+    }
+
+    // Test cases for the ownership of the writer
+    #[test]
+    fn test_serializer_is_send() {
+        // Arrange
+        fn assert_send<T: Send>() {}
+
+        // Act & Assert
+        assert_send::<Serializer<Vec<u8>>>();
+        assert_send::<Serializer<&mut Vec<u8>>>();
+        assert_send::<DocumentWriter<io::Sink>>();
+        assert_send::<SequenceWriter<io::Sink>>();
+        assert_send::<MappingWriter<io::Sink>>();
+    }
+
+    #[test]
+    fn test_serializer_moved_across_threads() {
+        // Arrange
+        let mut serializer = SerializerBuilder::new()
+            .anchors(AnchorMode::Repeated)
+            .build(Vec::new());
+        vec![1].serialize(&mut serializer).unwrap();
+
+        // Act
+        let buffer = thread::spawn(move || {
+            let repeated = vec![vec![2], vec![2]];
+            repeated.serialize(&mut serializer).unwrap();
+            serializer.into_inner().unwrap()
+        })
+        .join()
+        .unwrap();
+
+        // Assert
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            "- 1\n---\n- &id001\n  - 2\n- *id001\n",
+            "The serializer should carry on in the other thread"
+        );
+    }
+
+    #[test]
+    fn test_serializer_borrowed_writer_in_thread() {
+        // Arrange
+        let worker = thread::spawn(|| {
+            let mut buffer = Vec::new();
+            let mut serializer = Serializer::new(&mut buffer);
+
+            // Act
+            BTreeMap::from([("worker", 1)])
+                .serialize(&mut serializer)
+                .unwrap();
+            drop(serializer);
+            buffer
+        });
+
+        // Assert
+        assert_eq!(
+            worker.join().unwrap(),
+            b"worker: 1\n",
+            "A serializer should borrow a writer owned by its thread"
+        );
+    }
+
+    #[test]
+    fn test_serializer_into_inner_returns_borrowed_writer() {
+        // Arrange
+        let mut buffer = b"# header\n".to_vec();
+        let mut serializer = Serializer::new(&mut buffer);
+
+        // Act
+        "value".serialize(&mut serializer).unwrap();
+        let writer = serializer.into_inner().unwrap();
+        writer.extend_from_slice(b"# footer\n");
+
+        // Assert
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            "# header\nvalue\n# footer\n",
+            "The writer should be usable again after the serializer"
+        );
+    }
+
+    // Test cases for omitting null and empty fields
+    #[derive(Serialize)]
+    struct Probe {
+        path: Option<&'static str>,
+        port: Option<u16>,
+    }
+
+    #[derive(Serialize)]
+    enum Policy {
+        Always {},
+    }
+
+    #[derive(Serialize)]
+    struct Manifest {
+        name: &'static str,
+        labels: BTreeMap<&'static str, Option<&'static str>>,
+        ports: Vec<u16>,
+        probe: Probe,
+        args: Vec<Option<&'static str>>,
+        restart: Policy,
+        matrix: Vec<Vec<u16>>,
+    }
+
+    fn manifest() -> Manifest {
+        Manifest {
+            name: "web",
+            labels: BTreeMap::from([
+                ("tier", Some("front")),
+                ("zone", None),
+            ]),
+            ports: vec![],
+            probe: Probe {
+                path: None,
+                port: None,
+            },
+            args: vec![None, Some("-v")],
+            restart: Policy::Always {},
+            matrix: vec![vec![]],
+        }
+    }
+
+    #[test]
+    fn test_omit_nulls() {
+        // Arrange
+        let builder = SerializerBuilder::new().omit_nulls(true);
+
+        // Act
+        let yaml =
+            serde_yml::to_string_with(&manifest(), &builder).unwrap();
+
+        // Assert
+        assert_eq!(
+            yaml,
+            "name: web\nlabels:\n  tier: front\nports: []\nprobe: {}\nargs:\n- null\n- '-v'\nrestart: !Always {}\nmatrix:\n- []\n",
+            "Null entries should be left out, nulls in sequences kept"
+        );
+    }
+
+    #[test]
+    fn test_omit_empty_collections() {
+        // Arrange
+        let builder = SerializerBuilder::new()
+            .omit_nulls(true)
+            .omit_empty_collections(true);
+
+        // Act
+        let yaml =
+            serde_yml::to_string_with(&manifest(), &builder).unwrap();
+
+        // Assert
+        assert_eq!(
+            yaml,
+            "name: web\nlabels:\n  tier: front\nargs:\n- null\n- '-v'\nrestart: !Always {}\nmatrix:\n- []\n",
+            "Empty entries should be left out, including emptied ones"
+        );
+    }
+
+    #[test]
+    fn test_omit_empty_collections_keeps_nulls() {
+        // Arrange
+        let builder =
+            SerializerBuilder::new().omit_empty_collections(true);
+
+        // Act
+        let yaml =
+            serde_yml::to_string_with(&manifest(), &builder).unwrap();
+
+        // Assert
+        assert_eq!(
+            yaml,
+            "name: web\nlabels:\n  tier: front\n  zone: null\nprobe:\n  path: null\n  port: null\nargs:\n- null\n- '-v'\nrestart: !Always {}\nmatrix:\n- []\n",
+            "Only empty collections should be left out"
+        );
+    }
+
+    #[test]
+    fn test_omit_in_value() {
+        // Arrange
+        let value: Value = serde_yml::from_str(
+            "{a: ~, b: {c: [], d: {}}, e: [{}, null], {k: ~}: 1, f: !t {}}",
+        )
+        .unwrap();
+        let builder = SerializerBuilder::new()
+            .omit_nulls(true)
+            .omit_empty_collections(true)
+            .collection_style(CollectionStyle::FlowLeaves)
+            .sort_keys(SortKeys::Maps);
+
+        // Act
+        let yaml = serde_yml::to_string_with(&value, &builder).unwrap();
+
+        // Assert
+        assert_eq!(
+            yaml, "e:\n- {}\n- null\nf: !t {}\n? k: null\n: 1\n",
+            "Value entries should be left out like struct fields, except in keys"
+        );
+    }
+
+    #[test]
+    fn test_omit_keeps_unit_and_variants() {
+        // Arrange
+        #[derive(Serialize)]
+        enum Mode {
+            Auto(Option<u8>),
+            Fixed(Vec<u8>),
+            Custom {},
+        }
+
+        #[derive(Serialize)]
+        struct Settings {
+            marker: (),
+            #[serde(with = "serde_yml::with::singleton_map")]
+            auto: Mode,
+            #[serde(with = "serde_yml::with::singleton_map")]
+            fixed: Mode,
+            #[serde(with = "serde_yml::with::singleton_map_recursive")]
+            custom: Mode,
+        }
+
+        let settings = Settings {
+            marker: (),
+            auto: Mode::Auto(None),
+            fixed: Mode::Fixed(vec![]),
+            custom: Mode::Custom {},
+        };
+        let builder = SerializerBuilder::new()
+            .omit_nulls(true)
+            .omit_empty_collections(true);
+
+        // Act
+        let yaml =
+            serde_yml::to_string_with(&settings, &builder).unwrap();
+
+        // Assert
+        assert_eq!(
+            yaml,
+            "marker: null\nauto:\n  Auto: null\nfixed:\n  Fixed: []\ncustom:\n  Custom: {}\n",
+            "Unit values and variant content should be kept"
+        );
+    }
+
+    #[test]
+    fn test_omit_everything() {
+        // Arrange
+        let probe = Probe {
+            path: None,
+            port: None,
+        };
+        let builder = SerializerBuilder::new()
+            .omit_nulls(true)
+            .omit_empty_collections(true);
+
+        // Act
+        let yaml = serde_yml::to_string_with(&probe, &builder).unwrap();
+
+        // Assert
+        assert_eq!(
+            yaml, "{}\n",
+            "The root should be written when empty"
+        );
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Reading {
+        ratio: f64,
+        scale: f32,
+        total: f64,
+    }
+
+    #[test]
+    fn test_float_fixed() {
+        // Arrange
+        let reading = Reading {
+            ratio: 2.0 / 3.0,
+            scale: 0.125,
+            total: 1e21,
+        };
+        let builder = SerializerBuilder::new()
+            .float_format(FloatFormat::Fixed(2));
+
+        // Act
+        let yaml =
+            serde_yml::to_string_with(&reading, &builder).unwrap();
+
+        // Assert
+        assert_eq!(
+            yaml, "ratio: 0.67\nscale: 0.12\ntotal: 1000000000000000000000.00\n",
+            "Floats should be rounded to two decimal places"
+        );
+    }
+
+    #[test]
+    fn test_float_no_exponent_with_decimal_point() {
+        // Arrange
+        let reading = Reading {
+            ratio: 1e-7,
+            scale: 3.0,
+            total: 1e21,
+        };
+        let builder = SerializerBuilder::new()
+            .float_format(FloatFormat::NoExponent)
+            .float_decimal_point(true);
+
+        // Act
+        let yaml =
+            serde_yml::to_string_with(&reading, &builder).unwrap();
+        let value: Value = serde_yml::from_str(&yaml).unwrap();
+
+        // Assert
+        assert_eq!(
+            yaml, "ratio: 0.0000001\nscale: 3.0\ntotal: 1000000000000000000000.0\n",
+            "Floats should have a decimal point and no exponent"
+        );
+        assert!(
+            value["scale"].is_f64() && value["total"].is_f64(),
+            "Whole numbers should read back as floats"
+        );
+        assert_eq!(
+            serde_yml::from_str::<Reading>(&yaml).unwrap(),
+            reading
+        );
+    }
+
+    #[test]
+    fn test_float_decimal_point_with_exponent() {
+        // Arrange
+        let values = vec![1e100, -2.5e-10, 7.0];
+        let builder =
+            SerializerBuilder::new().float_decimal_point(true);
+
+        // Act
+        let yaml =
+            serde_yml::to_string_with(&values, &builder).unwrap();
+
+        // Assert
+        assert_eq!(
+            yaml, "- 1.0e100\n- -2.5e-10\n- 7.0\n",
+            "The decimal point should go before the exponent"
+        );
+        assert_eq!(
+            serde_yml::from_str::<Vec<f64>>(&yaml).unwrap(),
+            values
+        );
+    }
+
+    #[test]
+    fn test_non_finite_floats() {
+        // Arrange
+        let values = vec![f64::INFINITY, f64::NEG_INFINITY, f64::NAN];
+        let quote = SerializerBuilder::new()
+            .non_finite_floats(NonFiniteFloats::Quote);
+        let error = SerializerBuilder::new()
+            .non_finite_floats(NonFiniteFloats::Error);
+
+        // Act
+        let emitted = serde_yml::to_string(&values).unwrap();
+        let quoted =
+            serde_yml::to_string_with(&values, &quote).unwrap();
+        let err =
+            serde_yml::to_string_with(&[1.5f32, f32::NAN], &error)
+                .unwrap_err();
+
+        // Assert
+        assert_eq!(emitted, "- .inf\n- -.inf\n- .nan\n");
+        assert_eq!(
+            quoted, "- '.inf'\n- '-.inf'\n- '.nan'\n",
+            "Non-finite floats should be written as strings"
+        );
+        assert_eq!(
+            err.to_string(),
+            "serializing an infinite or NaN float is not allowed"
+        );
+        assert_eq!(
+            serde_yml::to_string_with(&[1.5f32], &error).unwrap(),
+            "- 1.5\n",
+            "Finite floats should be allowed"
+        );
+    }
+
+    #[derive(
+        Serialize, Deserialize, Debug, PartialEq, Eq, PartialOrd, Ord,
+    )]
+    struct Point {
+        x: i32,
+        y: i32,
+    }
+
+    fn complex_keys_value() -> Value {
+        serde_yml::from_str(
+            "{? [1, 2]: a, ? {x: 1, y: [2]}: {b: [3, 4]}, ? []: e, ? [[5]]: [6]}",
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_complex_keys_explicit() {
+        // Arrange
+        let mut map = BTreeMap::new();
+        map.insert((1u32, 2u32), "a".to_owned());
+        map.insert((3, 4), "b".to_owned());
+        let value = complex_keys_value();
+
+        // Act
+        let yaml = serde_yml::to_string(&map).unwrap();
+        let value_yaml = serde_yml::to_string(&value).unwrap();
+
+        // Assert
+        assert_eq!(
+            yaml, "? - 1\n  - 2\n: a\n? - 3\n  - 4\n: b\n",
+            "Tuple keys should be written after an explicit `?`"
+        );
+        assert_eq!(
+            value_yaml,
+            "? - 1\n  - 2\n: a\n? x: 1\n  'y':\n  - 2\n: b:\n  - 3\n  - 4\n[]: e\n? - - 5\n: - 6\n"
+        );
+        assert_eq!(
+            serde_yml::from_str::<BTreeMap<(u32, u32), String>>(&yaml)
+                .unwrap(),
+            map
+        );
+        assert_eq!(
+            serde_yml::from_str::<Value>(&value_yaml).unwrap(),
+            value
+        );
+    }
+
+    #[test]
+    fn test_complex_keys_flow() {
+        // Arrange
+        let mut map = BTreeMap::new();
+        map.insert(Point { x: 1, y: 2 }, vec![1, 2]);
+        map.insert(Point { x: 3, y: 4 }, vec![]);
+        let value = complex_keys_value();
+        let builder = SerializerBuilder::new()
+            .complex_keys(ComplexKeyStyle::Flow);
+
+        // Act
+        let yaml = serde_yml::to_string_with(&map, &builder).unwrap();
+        let value_yaml =
+            serde_yml::to_string_with(&value, &builder).unwrap();
+
+        // Assert
+        assert_eq!(
+            yaml, "{x: 1, 'y': 2}:\n  - 1\n  - 2\n{x: 3, 'y': 4}: []\n",
+            "Struct keys should be written as implicit flow keys"
+        );
+        assert_eq!(
+            value_yaml,
+            "[1, 2]: a\n{x: 1, 'y': [2]}:\n  b:\n  - 3\n  - 4\n[]: e\n[[5]]:\n  - 6\n"
+        );
+        assert_eq!(
+            serde_yml::from_str::<BTreeMap<Point, Vec<u8>>>(&yaml)
+                .unwrap(),
+            map
+        );
+        assert_eq!(
+            serde_yml::from_str::<Value>(&value_yaml).unwrap(),
+            value
+        );
+    }
+
+    #[test]
+    fn test_complex_keys_flow_nested() {
+        // Arrange
+        let value: Value = serde_yml::from_str(
+            "- {? [1]: ~, ? [2]: !t [3], ? [4]: \"x\\ny\\n\"}\n- {k: {? {a: 1}: b}}",
+        )
+        .unwrap();
+        let builder = SerializerBuilder::new()
+            .complex_keys(ComplexKeyStyle::Flow)
+            .indent(4)
+            .null_style(NullStyle::Empty);
+
+        // Act
+        let yaml = serde_yml::to_string_with(&value, &builder).unwrap();
+
+        // Assert
+        assert_eq!(
+            yaml,
+            "-   [1]:\n    [2]: !t\n    - 3\n    [4]: |\n        x\n        y\n-   k:\n        {a: 1}: b\n",
+            "Keys inside sequences and mappings should be implicit"
+        );
+        assert_eq!(serde_yml::from_str::<Value>(&yaml).unwrap(), value);
+    }
+
+    #[test]
+    fn test_complex_keys_flow_too_long() {
+        // Arrange
+        let key: Vec<u32> = (0..300).collect();
+        let mut map = BTreeMap::new();
+        map.insert(key, 1);
+        let builder = SerializerBuilder::new()
+            .complex_keys(ComplexKeyStyle::Flow);
+
+        // Act
+        let yaml = serde_yml::to_string_with(&map, &builder).unwrap();
+
+        // Assert
+        assert!(
+            yaml.starts_with("? [0, 1, 2,"),
+            "A long key should stay explicit"
+        );
+        assert_eq!(
+            serde_yml::from_str::<BTreeMap<Vec<u32>, u8>>(&yaml)
+                .unwrap(),
+            map
+        );
+    }
+
+    #[test]
+    fn test_complex_keys_through_value() {
+        // Arrange
+        let mut map = BTreeMap::new();
+        map.insert((1u32, 2u32), Point { x: 3, y: 4 });
+
+        // Act
+        let value = serde_yml::to_value(&map).unwrap();
+        let yaml = serde_yml::to_string(&value).unwrap();
+
+        // Assert
+        let key = Value::Sequence(vec![1.into(), 2.into()]);
+        assert_eq!(value[&key]["x"], 3, "The key should be a sequence");
+        assert_eq!(
+            serde_yml::from_str::<BTreeMap<(u32, u32), Point>>(&yaml)
+                .unwrap(),
+            map
+        );
+        assert_eq!(
+            serde_yml::from_value::<BTreeMap<(u32, u32), Point>>(value)
+                .unwrap(),
+            map
+        );
+    }
+
+    #[test]
+    fn test_complex_keys_single_entry() {
+        // Arrange
+        #[derive(
+            Serialize,
+            Deserialize,
+            Debug,
+            PartialEq,
+            Eq,
+            PartialOrd,
+            Ord,
+        )]
+        enum Shape {
+            Dot(u8),
+            Line(u8, u8),
+        }
+        let dot: BTreeMap<Shape, u8> = [(Shape::Dot(1), 2)].into();
+        let line: BTreeMap<Shape, u8> = [(Shape::Line(1, 2), 3)].into();
+        let nested: BTreeMap<BTreeMap<Shape, u8>, u8> =
+            [(dot, 4)].into();
+        let value: Value =
+            serde_yml::from_str("k: {? {a: 1}: b}").unwrap();
+
+        // Act
+        let nested_yaml = serde_yml::to_string(&nested).unwrap();
+        let line_yaml = serde_yml::to_string(&line).unwrap();
+        let value_yaml = serde_yml::to_string(&value).unwrap();
+
+        // Assert
+        assert_eq!(nested_yaml, "? !Dot 1: 2\n: 4\n");
+        assert_eq!(line_yaml, "? !Line\n- 1\n- 2\n: 3\n");
+        assert_eq!(value_yaml, "k:\n  ? a: 1\n  : b\n");
+        assert_eq!(
+            serde_yml::from_str::<BTreeMap<BTreeMap<Shape, u8>, u8>>(
+                &nested_yaml
+            )
+            .unwrap(),
+            nested
+        );
+        assert_eq!(
+            serde_yml::from_str::<BTreeMap<Shape, u8>>(&line_yaml)
+                .unwrap(),
+            line
+        );
+        assert_eq!(
+            serde_yml::from_str::<Value>(&value_yaml).unwrap(),
+            value
+        );
+    }
 }
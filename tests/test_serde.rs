@@ -505,6 +505,55 @@ fn test_nested_enum() {
     test_serde(&thing, yaml);
 }
 
+#[test]
+fn test_nested_enum_variants() {
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    enum Outer {
+        Inner(Inner),
+        Outer(Box<Outer>),
+    }
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    enum Inner {
+        Newtype(usize),
+        Tuple(usize, usize),
+        Struct { x: usize },
+    }
+
+    let thing = Outer::Inner(Inner::Newtype(1));
+    let yaml = indoc! {"
+        !Inner
+        Newtype: 1
+    "};
+    test_serde(&thing, yaml);
+
+    let thing = Outer::Inner(Inner::Tuple(1, 2));
+    let yaml = indoc! {"
+        !Inner
+        Tuple:
+        - 1
+        - 2
+    "};
+    test_serde(&thing, yaml);
+
+    let thing = vec![Outer::Inner(Inner::Struct { x: 1 })];
+    let yaml = indoc! {"
+        - !Inner
+          Struct:
+            x: 1
+    "};
+    test_serde(&thing, yaml);
+
+    let thing = Outer::Outer(Box::new(Outer::Outer(Box::new(
+        Outer::Inner(Inner::Newtype(1)),
+    ))));
+    let yaml = indoc! {"
+        !Outer
+        Outer: !Inner
+          Newtype: 1
+    "};
+    test_serde(&thing, yaml);
+}
+
 #[test]
 fn test_option() {
     let thing = vec![Some(1), None, Some(3)];
@@ -717,3 +766,95 @@ fn test_long_string() {
 
     test_serde(&thing, yaml);
 }
+
+#[derive(PartialEq, Debug)]
+struct Bytes(Vec<u8>);
+
+impl serde::Serialize for Bytes {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_bytes(&self.0)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for Bytes {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct BytesVisitor;
+
+        impl serde::de::Visitor<'_> for BytesVisitor {
+            type Value = Bytes;
+
+            fn expecting(
+                &self,
+                formatter: &mut std::fmt::Formatter<'_>,
+            ) -> std::fmt::Result {
+                formatter.write_str("a byte array")
+            }
+
+            fn visit_bytes<E>(self, v: &[u8]) -> Result<Bytes, E> {
+                Ok(Bytes(v.to_vec()))
+            }
+
+            fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<Bytes, E> {
+                Ok(Bytes(v))
+            }
+        }
+
+        deserializer.deserialize_byte_buf(BytesVisitor)
+    }
+}
+
+#[test]
+fn test_binary() {
+    let thing = Bytes(b"hello".to_vec());
+    let yaml = indoc! {"
+        !!binary aGVsbG8=
+    "};
+    test_serde(&thing, yaml);
+}
+
+#[test]
+fn test_binary_empty() {
+    let thing = Bytes(Vec::new());
+    let yaml = indoc! {"
+        !!binary
+    "};
+    test_serde(&thing, yaml);
+}
+
+#[test]
+fn test_long_binary() {
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Data {
+        pub(crate) data: Bytes,
+    }
+
+    let thing = Data {
+        data: Bytes((0..=255).collect()),
+    };
+
+    let yaml = indoc! {"
+        data: !!binary |-
+          AAECAwQFBgcICQoLDA0ODxAREhMUFRYXGBkaGxwdHh8gISIjJCUmJygpKissLS4vMDEyMzQ1Njc4
+          OTo7PD0+P0BBQkNERUZHSElKS0xNTk9QUVJTVFVWV1hZWltcXV5fYGFiY2RlZmdoaWprbG1ub3Bx
+          cnN0dXZ3eHl6e3x9fn+AgYKDhIWGh4iJiouMjY6PkJGSk5SVlpeYmZqbnJ2en6ChoqOkpaanqKmq
+          q6ytrq+wsbKztLW2t7i5uru8vb6/wMHCw8TFxsfIycrLzM3Oz9DR0tPU1dbX2Nna29zd3t/g4eLj
+          5OXm5+jp6uvs7e7v8PHy8/T19vf4+fr7/P3+/w==
+    "};
+
+    test_serde(&thing, yaml);
+}
+
+#[test]
+fn test_binary_value() {
+    let thing = Value::Binary(vec![0, 1, 2, 254, 255]);
+    let yaml = indoc! {"
+        !!binary AAEC/v8=
+    "};
+    test_serde(&thing, yaml);
+}
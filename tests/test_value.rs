@@ -12,7 +12,9 @@
 use indoc::indoc;
 use serde::{de::IntoDeserializer, Deserialize};
 use serde_derive::Serialize;
-use serde_yml::{Number, Value};
+use serde_yml::libyml::parser::ScalarStyle;
+use serde_yml::value::{Tag, TaggedValue};
+use serde_yml::{DeserializerBuilder, Number, Value};
 
 #[test]
 fn test_nan() {
@@ -162,3 +164,212 @@ fn test_tagged() {
     let serialized = serde_yml::to_value(&value).unwrap();
     assert_eq!(value, serialized);
 }
+
+#[test]
+fn test_tag_directive() {
+    let yaml = indoc! {"
+        %TAG !e! tag:example.com,2024:
+        ---
+        a: !e!widget 1
+        b: !!str 2
+        c: !<tag:example.com,2024:gadget> [3]
+    "};
+    let value: Value = serde_yml::from_str(yaml).unwrap();
+
+    let tagged = |tag: &str, value: Value| {
+        Value::Tagged(Box::new(TaggedValue {
+            tag: Tag::new(tag),
+            value,
+        }))
+    };
+    assert_eq!(
+        value["a"],
+        tagged("tag:example.com,2024:widget", Value::from(1)),
+    );
+    assert_eq!(value["b"], Value::from("2"));
+    assert_eq!(
+        value["c"],
+        tagged(
+            "tag:example.com,2024:gadget",
+            Value::Sequence(vec![Value::from(3)]),
+        ),
+    );
+}
+
+#[test]
+fn test_sort_keys_recursive() {
+    let yaml = indoc! {"
+        b: 1
+        a:
+          - z: 1
+            w: 2
+        10: ten
+        9: nine
+        ? {q: 1, p: 2}
+        : key
+    "};
+    let mut value: Value = serde_yml::from_str(yaml).unwrap();
+    value.sort_keys_recursive();
+
+    let expected = indoc! {"
+        9: nine
+        10: ten
+        a:
+        - w: 2
+          z: 1
+        b: 1
+        ? p: 2
+          q: 1
+        : key
+    "};
+    assert_eq!(serde_yml::to_string(&value).unwrap(), expected);
+}
+
+fn preserved(yaml: &str) -> Value {
+    let builder = DeserializerBuilder::new().preserve_scalars(true);
+    serde_yml::from_str_with(yaml, &builder).unwrap()
+}
+
+#[test]
+fn test_preserve_scalars_round_trip() {
+    let yaml = indoc! {r#"
+        mask: 0x1F
+        octal: 0o17
+        ratio: 1.50
+        exponent: 1e3
+        signed: +12
+        infinite: .INF
+        enabled: True
+        tilde: ~
+        empty:
+        name: "quoted"
+        single: 'quoted'
+        zip: 007
+        0x10: hex key
+        literal: |
+          line one
+          line two
+        list:
+        - 1.0
+        - "x"
+    "#};
+    let value = preserved(yaml);
+    assert_eq!(serde_yml::to_string(&value).unwrap(), yaml);
+
+    let value: Value = serde_yml::from_str(yaml).unwrap();
+    assert_ne!(serde_yml::to_string(&value).unwrap(), yaml);
+}
+
+#[test]
+fn test_preserve_scalars_folded() {
+    // The text is kept folded, so its lines are folded again on output.
+    let yaml = "k: >\n  folded\n  text\n";
+    let value = preserved(yaml);
+    let output = serde_yml::to_string(&value).unwrap();
+    assert_eq!(output, "k: >\n  folded text\n");
+    assert_eq!(preserved(&output), value);
+}
+
+#[test]
+fn test_preserve_scalars_escaped() {
+    // The text is kept unescaped, so only what needs escaping is escaped
+    // again on output.
+    let yaml = "k: \"\\u00e9\\t\"\n";
+    let value = preserved(yaml);
+    assert_eq!(value["k"], "\u{e9}\t");
+    let output = serde_yml::to_string(&value).unwrap();
+    assert_eq!(output, "k: \"\u{e9}\\t\"\n");
+    assert_eq!(preserved(&output), value);
+}
+
+#[test]
+fn test_preserve_scalars_changed_value() {
+    let mut value = preserved("mask: 0x1F\nname: \"web\"\ntilde: ~\n");
+    value["mask"] = Value::from(32);
+    value["name"] = Value::from("db");
+    value["tilde"]["nested"] = Value::from(true);
+
+    let expected = indoc! {"
+        mask: 32
+        name: db
+        tilde:
+          nested: true
+    "};
+    assert_eq!(serde_yml::to_string(&value).unwrap(), expected);
+}
+
+#[test]
+fn test_preserve_scalars_transparent() {
+    let yaml = "mask: 0x1F\nname: \"web\"\n? 0x10\n: key\n";
+    let value = preserved(yaml);
+    assert_eq!(value, serde_yml::from_str::<Value>(yaml).unwrap());
+    assert_eq!(value["mask"].as_u64(), Some(31));
+    assert_eq!(value["name"], "web");
+    assert_eq!(value[16], "key");
+    assert!(value.get("name").unwrap().is_string());
+
+    let styled = match &value {
+        Value::Mapping(mapping) => match mapping.get("mask") {
+            Some(Value::Styled(styled)) => styled,
+            other => panic!("expected a styled scalar: {:?}", other),
+        },
+        other => panic!("expected a mapping: {:?}", other),
+    };
+    assert_eq!(styled.text(), "0x1F");
+    assert_eq!(styled.style(), ScalarStyle::Plain);
+
+    // Other serializers only see the value.
+    let value = serde_yml::to_value(&value).unwrap();
+    assert!(matches!(value["mask"], Value::Number(_)));
+}
+
+#[test]
+fn test_preserve_scalars_keeps_tagged_and_typed() {
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Config {
+        mask: u32,
+        name: String,
+    }
+
+    let builder = DeserializerBuilder::new().preserve_scalars(true);
+    let config: Config =
+        serde_yml::from_str_with("mask: 0x1F\nname: 'web'\n", &builder)
+            .unwrap();
+    assert_eq!(
+        config,
+        Config {
+            mask: 31,
+            name: "web".to_owned(),
+        },
+    );
+
+    let value = preserved("!Tag 0x1F\n");
+    assert_eq!(serde_yml::to_string(&value).unwrap(), "!Tag 31\n");
+}
+
+#[test]
+fn test_preserve_scalars_from_value() {
+    #[derive(Deserialize, Debug, PartialEq)]
+    enum Choice {
+        A,
+        B(u8),
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Doc {
+        o: Option<i32>,
+        u: (),
+        e: Choice,
+        f: Choice,
+    }
+
+    let value = preserved("o: ~\nu: null\ne: A\nf: {B: 1}\n");
+    let expected = Doc {
+        o: None,
+        u: (),
+        e: Choice::A,
+        f: Choice::B(1),
+    };
+    assert_eq!(Doc::deserialize(&value).unwrap(), expected);
+    assert_eq!(serde_yml::from_value::<Doc>(value).unwrap(), expected);
+}
@@ -427,4 +427,128 @@ mod tests {
             .unwrap();
         assert_eq!(value, deserialized);
     }
+
+    // Test serialization and deserialization using flow and block
+    #[test]
+    fn test_flow_and_block() {
+        use serde_yml::{CollectionStyle, SerializerBuilder};
+        use std::collections::BTreeMap;
+
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        struct TestStruct {
+            #[serde(with = "flow")]
+            ports: Vec<u16>,
+            #[serde(with = "flow")]
+            point: BTreeMap<String, i32>,
+            #[serde(with = "block")]
+            script: Vec<String>,
+            #[serde(with = "flow")]
+            missing: Option<Vec<u16>>,
+        }
+
+        let test_struct = TestStruct {
+            ports: vec![80, 443],
+            point: BTreeMap::from([
+                ("x".to_owned(), 1),
+                ("z".to_owned(), 2),
+            ]),
+            script: vec!["make".to_owned()],
+            missing: None,
+        };
+        let yaml = serde_yml::to_string(&test_struct).unwrap();
+        assert_eq!(
+            yaml,
+            "ports: [80, 443]\npoint: {x: 1, z: 2}\nscript:\n- make\nmissing: null\n"
+        );
+        let deserialized: TestStruct =
+            serde_yml::from_str(&yaml).unwrap();
+        assert_eq!(test_struct, deserialized);
+
+        // The serializer-wide style does not override the field
+        let builder = SerializerBuilder::new()
+            .collection_style(CollectionStyle::FlowLeaves);
+        let yaml =
+            serde_yml::to_string_with(&test_struct, &builder).unwrap();
+        assert_eq!(
+            yaml,
+            "ports: [80, 443]\npoint: {x: 1, z: 2}\nscript:\n- make\nmissing: null\n"
+        );
+
+        // Other serializers ignore the style
+        let value = serde_yml::to_value(&test_struct).unwrap();
+        assert_eq!(value["ports"][1], 443);
+    }
+
+    // Test serialization and deserialization using the scalar styles
+    #[test]
+    fn test_scalar_styles() {
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        struct TestStruct {
+            #[serde(with = "literal")]
+            script: String,
+            #[serde(with = "folded")]
+            description: String,
+            #[serde(with = "double_quoted")]
+            name: String,
+            #[serde(with = "single_quoted")]
+            label: String,
+            #[serde(with = "plain")]
+            answer: String,
+            #[serde(with = "double_quoted")]
+            port: u16,
+        }
+
+        let test_struct = TestStruct {
+            script: "set -e\nmake\n".to_owned(),
+            description: "first paragraph\n\nsecond".to_owned(),
+            name: "web".to_owned(),
+            label: "tab\there".to_owned(),
+            answer: "yes".to_owned(),
+            port: 80,
+        };
+        let yaml = serde_yml::to_string(&test_struct).unwrap();
+        assert_eq!(
+            yaml,
+            "script: |\n  set -e\n  make\ndescription: >-\n  first paragraph\n\n\n  second\nname: \"web\"\nlabel: \"tab\\there\"\nanswer: 'yes'\nport: 80\n"
+        );
+        let deserialized: TestStruct =
+            serde_yml::from_str(&yaml).unwrap();
+        assert_eq!(test_struct, deserialized);
+    }
+
+    // Test that block styles fall back where they can't be used
+    #[test]
+    fn test_scalar_styles_fallback() {
+        use std::collections::BTreeMap;
+
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        struct TestStruct {
+            #[serde(with = "flow")]
+            inline: BTreeMap<String, Wrapped>,
+            #[serde(with = "literal")]
+            trailing: String,
+            #[serde(with = "plain")]
+            lines: String,
+        }
+
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        struct Wrapped(#[serde(with = "literal")] String);
+
+        let test_struct = TestStruct {
+            inline: BTreeMap::from([(
+                "k".to_owned(),
+                Wrapped("a\nb".to_owned()),
+            )]),
+            trailing: "space ".to_owned(),
+            lines: "a\nb".to_owned(),
+        };
+        let yaml = serde_yml::to_string(&test_struct).unwrap();
+        assert_eq!(
+            yaml,
+            "inline: {k: \"a\\nb\"}\ntrailing: \"space \"\nlines: |-\n  a\n  b\n"
+        );
+        let deserialized: TestStruct =
+            serde_yml::from_str(&yaml).unwrap();
+        assert_eq!(test_struct, deserialized);
+    }
 }